@@ -144,7 +144,7 @@ impl Application {
                 }));
             })
         );
-        let is_sandboxed = futures::executor::block_on(async { ashpd::is_sandboxed().await });
+        let is_sandboxed = ashpd::is_sandboxed();
         // The restart app requires the Flatpak portal
         gtk_macros::get_action!(self, @restart).set_enabled(is_sandboxed);
 
@@ -1,6 +1,6 @@
 use std::os::unix::prelude::RawFd;
 
-use ashpd::desktop::camera;
+use ashpd::desktop::camera::{self, CameraAccess};
 use glib::clone;
 use gtk::{glib, prelude::*, subclass::prelude::*};
 
@@ -99,7 +99,7 @@ impl CameraPage {
         self.action_set_enabled("camera.stop", true);
         self.action_set_enabled("camera.start", false);
         match stream().await {
-            Ok(stream_fd) => {
+            Ok(Ok(stream_fd)) => {
                 let streams = camera::pipewire_streams(stream_fd).await.unwrap();
                 let n_cameras = streams.len();
                 for s in streams.iter() {
@@ -140,6 +140,18 @@ impl CameraPage {
                     NotificationKind::Success,
                 );
             }
+            Ok(Err(access)) => {
+                if access == CameraAccess::NoCamera {
+                    imp.camera_available.set_text("No");
+                    self.send_notification("No camera available", NotificationKind::Error);
+                } else {
+                    self.send_notification(
+                        "Permission to access the camera denied",
+                        NotificationKind::Error,
+                    );
+                }
+                self.stop_stream();
+            }
             Err(err) => {
                 tracing::error!("Failed to start a camera stream {:#?}", err);
                 self.send_notification(
@@ -167,10 +179,13 @@ impl CameraPage {
     }
 }
 
-async fn stream() -> ashpd::Result<RawFd> {
+/// The PipeWire remote, or why the camera can't be accessed.
+async fn stream() -> ashpd::Result<Result<RawFd, CameraAccess>> {
     let proxy = camera::Camera::new().await?;
-    proxy.request_access().await?;
-    proxy.open_pipe_wire_remote().await
+    match proxy.access().await? {
+        CameraAccess::Granted => Ok(Ok(proxy.open_pipe_wire_remote().await?)),
+        access => Ok(Err(access)),
+    }
 }
 
 async fn camera_available() -> ashpd::Result<bool> {
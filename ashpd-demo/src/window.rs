@@ -127,7 +127,7 @@ mod imp {
             if config::PROFILE == "Devel" {
                 obj.add_css_class("devel");
             }
-            let is_sandboxed = futures::executor::block_on(async { ashpd::is_sandboxed().await });
+            let is_sandboxed = ashpd::is_sandboxed();
             // Add pages based on whether the app is sandboxed
             if is_sandboxed {
                 self.sidebar
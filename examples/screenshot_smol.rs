@@ -0,0 +1,38 @@
+//! Take a screenshot on the smol executor.
+//!
+//! Run it without the default features so ASHPD doesn't pull any runtime:
+//!
+//! ```sh
+//! cargo run --example screenshot_smol --no-default-features
+//! ```
+
+use ashpd::desktop::screenshot::ScreenshotRequest;
+
+fn main() -> ashpd::Result<()> {
+    let executor = std::sync::Arc::new(smol::Executor::new());
+
+    smol::block_on(executor.run(async {
+        // Build the connection without zbus's internal executor thread and
+        // drive it from the smol executor instead.
+        let connection = ashpd::zbus::ConnectionBuilder::session()?
+            .internal_executor(false)
+            .build()
+            .await?;
+        let zbus_executor = connection.executor().clone();
+        executor
+            .spawn(async move {
+                loop {
+                    zbus_executor.tick().await;
+                }
+            })
+            .detach();
+        ashpd::set_session_connection(connection).expect("No portal was used yet");
+
+        let uri = ScreenshotRequest::default()
+            .interactive(true)
+            .build()
+            .await?;
+        println!("URI: {}", uri);
+        Ok(())
+    }))
+}
@@ -20,13 +20,30 @@
 //!     Ok(())
 //! }
 //! ```
+//!
+//! Loading the user's avatar
+//!
+//! ```rust, no_run
+//! use ashpd::desktop::account::UserInformationRequest;
+//!
+//! async fn run() -> ashpd::Result<()> {
+//!     let response = UserInformationRequest::default().build().await?;
+//!
+//!     if let Some(bytes) = response.image_bytes().await? {
+//!         // Load the PNG / JPEG image with your image loading library
+//!         println!("Avatar of {} bytes", bytes.len());
+//!     }
+//!
+//!     Ok(())
+//! }
+//! ```
 
 use zbus::zvariant::{DeserializeDict, SerializeDict, Type};
 
 use super::{HandleToken, DESTINATION, PATH};
 use crate::{
-    helpers::{call_request_method, session_connection},
-    Error, WindowIdentifier,
+    helpers::{call_request_method, check_window_kind, read_file, session_connection},
+    Error, Portal, WindowIdentifier,
 };
 
 #[derive(SerializeDict, Type, Debug, Default)]
@@ -42,7 +59,15 @@ struct UserInformationOptions {
 pub struct UserInformationResponse {
     id: String,
     name: String,
-    image: url::Url,
+    // The portal might send an empty string when the user has no avatar
+    image: Option<String>,
+}
+
+/// Shows the user name followed by their identifier, e.g. `Jane Doe (jane)`.
+impl std::fmt::Display for UserInformationResponse {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} ({})", self.name, self.id)
+    }
 }
 
 impl UserInformationResponse {
@@ -56,9 +81,31 @@ impl UserInformationResponse {
         &self.name
     }
 
-    /// User image uri.
-    pub fn image(&self) -> &url::Url {
-        &self.image
+    /// User image uri, if the user has an avatar.
+    pub fn image(&self) -> Option<url::Url> {
+        self.image
+            .as_deref()
+            .filter(|image| !image.is_empty())
+            .and_then(|image| url::Url::parse(image).ok())
+    }
+
+    /// Read the user's image, usually a PNG or JPEG file.
+    ///
+    /// Returns `None` if the user has no avatar. Fails with [`Error::IO`] if
+    /// the image is not a local file, and with [`Error::File`] if it can't be
+    /// read.
+    pub async fn image_bytes(&self) -> Result<Option<Vec<u8>>, Error> {
+        let image = match self.image() {
+            Some(image) => image,
+            None => return Ok(None),
+        };
+        let path = image.to_file_path().map_err(|_| {
+            std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!("{} is not a local file", image),
+            )
+        })?;
+        Ok(Some(read_file(&path).await?))
     }
 
     /// Creates a new builder-pattern struct instance to construct
@@ -76,7 +123,7 @@ impl<'a> AccountProxy<'a> {
     pub async fn new() -> Result<AccountProxy<'a>, Error> {
         let connection = session_connection().await?;
         let proxy = zbus::ProxyBuilder::new_bare(&connection)
-            .interface("org.freedesktop.portal.Account")?
+            .interface(Portal::Account.as_interface_name())?
             .path(PATH)?
             .destination(DESTINATION)?
             .build()
@@ -93,6 +140,7 @@ impl<'a> AccountProxy<'a> {
         identifier: &WindowIdentifier,
         options: UserInformationOptions,
     ) -> Result<UserInformationResponse, Error> {
+        check_window_kind(identifier)?;
         call_request_method(
             self.inner(),
             &options.handle_token,
@@ -116,7 +164,12 @@ pub struct UserInformationRequest {
 
 impl UserInformationRequest {
     #[must_use]
-    /// Sets a user-visible reason for the request.
+    /// Sets a user-visible reason for the request, shown in the dialog asking
+    /// the user to share their information.
+    ///
+    /// **Note** without a reason, or with an empty one, the user is left
+    /// guessing why the application needs it and is more likely to deny the
+    /// request.
     pub fn reason(mut self, reason: &str) -> Self {
         self.set_reason(reason);
         self
@@ -143,3 +196,52 @@ impl UserInformationRequest {
         proxy.user_information(&self.identifier, self.options).await
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::HashMap, error::Error as _, io::ErrorKind, path::Path};
+
+    use byteorder::LE;
+    use zbus::zvariant::{from_slice, to_bytes, EncodingContext as Context, OwnedValue};
+
+    use super::{UserInformationRequest, UserInformationResponse};
+    use crate::Error;
+
+    #[test]
+    fn serialize_reason() {
+        let ctxt = Context::<LE>::new_dbus(0);
+
+        let request = UserInformationRequest::default();
+        let encoded = to_bytes(ctxt, &request.options).unwrap();
+        let decoded: HashMap<String, OwnedValue> = from_slice(&encoded, ctxt).unwrap();
+        assert!(!decoded.contains_key("reason"));
+
+        let request = request.reason("Show your name on the title screen");
+        let encoded = to_bytes(ctxt, &request.options).unwrap();
+        let decoded: HashMap<String, OwnedValue> = from_slice(&encoded, ctxt).unwrap();
+        assert_eq!(
+            <&str>::try_from(&decoded["reason"]).unwrap(),
+            "Show your name on the title screen"
+        );
+    }
+
+    #[test]
+    fn missing_image() {
+        let response = UserInformationResponse {
+            id: "user".to_owned(),
+            name: "User".to_owned(),
+            image: Some("file:///nonexistent/ashpd/avatar.png".to_owned()),
+        };
+
+        let err = zbus::block_on(response.image_bytes()).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            r#"Failed to access "/nonexistent/ashpd/avatar.png""#
+        );
+        let io = err.source().unwrap().downcast_ref::<std::io::Error>();
+        assert_eq!(io.unwrap().kind(), ErrorKind::NotFound);
+        assert!(
+            matches!(err, Error::File(path, _) if path == Path::new("/nonexistent/ashpd/avatar.png"))
+        );
+    }
+}
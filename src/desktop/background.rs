@@ -28,13 +28,21 @@
 //!
 //! If no `command` is provided, the [`Exec`](https://specifications.freedesktop.org/desktop-entry-spec/desktop-entry-spec-latest.html#exec-variables) line from the [desktop
 //! file](https://specifications.freedesktop.org/desktop-entry-spec/desktop-entry-spec-latest.html#introduction) will be used.
+//!
+//! The portal doesn't tell whether the application is currently running in
+//! the background. The backends, like the GNOME and KDE ones, track the state
+//! of the running applications through
+//! `org.freedesktop.impl.portal.Background.GetAppState`, but that interface is
+//! private to xdg-desktop-portal and can't be reached from the sandbox.
+//! Applications wanting to adjust their behavior have to rely on their own
+//! windows being visible or not instead.
 
 use zbus::zvariant::{DeserializeDict, SerializeDict, Type};
 
 use super::{HandleToken, DESTINATION, PATH};
 use crate::{
-    helpers::{call_request_method, session_connection},
-    Error, WindowIdentifier,
+    helpers::{call_request_method, check_window_kind, session_connection},
+    Error, Portal, WindowIdentifier,
 };
 
 #[derive(SerializeDict, Type, Debug, Default)]
@@ -84,7 +92,7 @@ impl<'a> BackgroundProxy<'a> {
     pub async fn new() -> Result<BackgroundProxy<'a>, Error> {
         let connection = session_connection().await?;
         let proxy = zbus::ProxyBuilder::new_bare(&connection)
-            .interface("org.freedesktop.portal.Background")?
+            .interface(Portal::Background.as_interface_name())?
             .path(PATH)?
             .destination(DESTINATION)?
             .build()
@@ -101,6 +109,7 @@ impl<'a> BackgroundProxy<'a> {
         identifier: &WindowIdentifier,
         options: BackgroundOptions,
     ) -> Result<BackgroundResponse, Error> {
+        check_window_kind(identifier)?;
         call_request_method(
             self.inner(),
             &options.handle_token,
@@ -169,7 +178,12 @@ impl BackgroundRequest {
     }
 
     #[must_use]
-    /// Sets a user-visible reason for the request.
+    /// Sets a user-visible reason for the request, shown in the dialog asking
+    /// the user to let the application run in the background.
+    ///
+    /// **Note** without a reason, or with an empty one, the user is left
+    /// guessing why the application needs it and is more likely to deny the
+    /// request.
     pub fn reason(mut self, reason: &str) -> Self {
         self.set_reason(reason);
         self
@@ -187,3 +201,32 @@ impl BackgroundRequest {
             .await
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use byteorder::LE;
+    use zbus::zvariant::{from_slice, to_bytes, EncodingContext as Context, OwnedValue};
+
+    use super::BackgroundRequest;
+
+    #[test]
+    fn serialize_reason() {
+        let ctxt = Context::<LE>::new_dbus(0);
+
+        let request = BackgroundRequest::default().auto_start(true);
+        let encoded = to_bytes(ctxt, &request.options).unwrap();
+        let decoded: HashMap<String, OwnedValue> = from_slice(&encoded, ctxt).unwrap();
+        assert!(!decoded.contains_key("reason"));
+
+        let request = request.reason("Automatically fetch your latest mails");
+        let encoded = to_bytes(ctxt, &request.options).unwrap();
+        let decoded: HashMap<String, OwnedValue> = from_slice(&encoded, ctxt).unwrap();
+        assert_eq!(
+            <&str>::try_from(&decoded["reason"]).unwrap(),
+            "Automatically fetch your latest mails"
+        );
+        assert!(bool::try_from(&decoded["autostart"]).unwrap());
+    }
+}
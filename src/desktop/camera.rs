@@ -16,6 +16,24 @@
 //!     Ok(())
 //! }
 //! ```
+//!
+//! Telling the user why the camera can't be used:
+//!
+//! ```rust,no_run
+//! use ashpd::desktop::camera::{Camera, CameraAccess};
+//!
+//! pub async fn run() -> ashpd::Result<()> {
+//!     let camera = Camera::new().await?;
+//!     match camera.access().await? {
+//!         CameraAccess::Granted => {
+//!             let remote_fd = camera.open_pipe_wire_remote().await?;
+//!         }
+//!         CameraAccess::Denied => println!("The permission to use the camera was denied"),
+//!         CameraAccess::NoCamera => println!("No camera is plugged"),
+//!     }
+//!     Ok(())
+//! }
+//! ```
 
 use std::{
     collections::HashMap,
@@ -24,10 +42,10 @@ use std::{
 
 use zbus::zvariant::{OwnedFd, SerializeDict, Type, Value};
 
-use super::{HandleToken, DESTINATION, PATH};
+use super::{request::ResponseError, HandleToken, DESTINATION, PATH};
 use crate::{
     helpers::{call_basic_response_method, call_method, session_connection},
-    Error,
+    Error, Portal,
 };
 
 #[derive(SerializeDict, Type, Debug, Default)]
@@ -36,6 +54,29 @@ struct CameraAccessOptions {
     handle_token: HandleToken,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+/// The outcome of [`Camera::access`].
+pub enum CameraAccess {
+    /// The access to the camera was granted.
+    Granted,
+    /// The user, or the permission store, denied the access to the camera.
+    Denied,
+    /// There is no camera to access.
+    NoCamera,
+}
+
+impl CameraAccess {
+    /// Maps the outcome of [`Camera::request_access`], the user cancelling
+    /// the request meaning the access was denied.
+    fn from_response(response: Result<(), Error>) -> Result<Self, Error> {
+        match response {
+            Ok(()) => Ok(Self::Granted),
+            Err(Error::Response(ResponseError::Cancelled)) => Ok(Self::Denied),
+            Err(err) => Err(err),
+        }
+    }
+}
+
 /// The interface lets sandboxed applications access camera devices, such as web
 /// cams.
 ///
@@ -48,8 +89,12 @@ impl<'a> Camera<'a> {
     /// Create a new instance of [`Camera`].
     pub async fn new() -> Result<Camera<'a>, Error> {
         let connection = session_connection().await?;
-        let proxy = zbus::ProxyBuilder::new_bare(&connection)
-            .interface("org.freedesktop.portal.Camera")?
+        Self::with_connection(&connection).await
+    }
+
+    async fn with_connection(connection: &zbus::Connection) -> Result<Camera<'a>, Error> {
+        let proxy = zbus::ProxyBuilder::new_bare(connection)
+            .interface(Portal::Camera.as_interface_name())?
             .path(PATH)?
             .destination(DESTINATION)?
             .build()
@@ -80,6 +125,19 @@ impl<'a> Camera<'a> {
         .await
     }
 
+    /// Checks whether a camera is present and requests an access to it.
+    ///
+    /// Unlike [`Camera::request_access`], which fails with
+    /// [`Error::Response`] when the access is denied, the denial is reported
+    /// as [`CameraAccess::Denied`] so it can be told apart from an actual
+    /// failure.
+    pub async fn access(&self) -> Result<CameraAccess, Error> {
+        if !self.is_present().await? {
+            return Ok(CameraAccess::NoCamera);
+        }
+        CameraAccess::from_response(self.request_access().await)
+    }
+
     /// Open a file descriptor to the PipeWire remote where the camera nodes are
     /// available.
     ///
@@ -87,6 +145,11 @@ impl<'a> Camera<'a> {
     ///
     /// File descriptor of an open PipeWire remote.
     ///
+    /// The portal only returns the file descriptor, none of the versions of
+    /// the interface tell which nodes are the cameras. With the `pipewire`
+    /// feature, use `pipewire_node_ids` to list them, or [`request`] to get
+    /// the file descriptor along with the camera streams.
+    ///
     /// # Specifications
     ///
     /// See also [`OpenPipeWireRemote`](https://flatpak.github.io/xdg-desktop-portal/index.html#gdbus-method-org-freedesktop-portal-Camera.OpenPipeWireRemote).
@@ -260,6 +323,69 @@ pub async fn pipewire_streams(fd: RawFd) -> Result<Vec<Stream>, pw::Error> {
     Ok(streams)
 }
 
+/// The ids of the PipeWire camera nodes available through the remote `fd`,
+/// returned by [`Camera::open_pipe_wire_remote`].
+///
+/// Only the nodes of a `Video/Source` media class with a `Camera` media role
+/// are returned, one per camera when several are plugged. The ids can be
+/// passed as the target node of a PipeWire stream, to GStreamer's
+/// `pipewiresrc` for example.
+///
+/// It runs a PipeWire main loop on a separate thread until the remote listed
+/// all its nodes.
+///
+/// **Note** this requires the `pipewire` feature, and the socket referenced
+/// by `fd` must not be used while this function is running.
+#[cfg(feature = "pipewire")]
+pub async fn pipewire_node_ids(fd: RawFd) -> Result<Vec<u32>, Error> {
+    let streams = pipewire_streams(fd).await?;
+    Ok(streams
+        .iter()
+        .filter(|stream| is_video_source(&stream.properties))
+        .map(Stream::node_id)
+        .collect())
+}
+
+// The camera nodes are sources, the streams of the applications using them
+// have the Camera role as well.
+#[cfg(feature = "pipewire")]
+fn is_video_source(properties: &HashMap<String, String>) -> bool {
+    properties.get("media.class").map(String::as_str) == Some("Video/Source")
+}
+
+/// Whether there is any camera available.
+///
+/// It only reads [`Camera::is_present`], without requesting an access to the
+/// camera: no dialog is shown, so it can be used to show or hide a camera
+/// button before asking for the permission.
+///
+/// ```rust,no_run
+/// use ashpd::desktop::camera;
+///
+/// async fn run() -> ashpd::Result<()> {
+///     if camera::is_present().await? {
+///         // Show the camera button
+///     }
+///     Ok(())
+/// }
+/// ```
+pub async fn is_present() -> Result<bool, Error> {
+    let connection = session_connection().await?;
+    is_present_on(&connection).await
+}
+
+async fn is_present_on(connection: &zbus::Connection) -> Result<bool, Error> {
+    let proxy = zbus::ProxyBuilder::new_bare(connection)
+        .interface(Portal::Camera.as_interface_name())?
+        .path(PATH)?
+        .destination(DESTINATION)?
+        // The property is only read once
+        .cache_properties(zbus::CacheProperties::No)
+        .build()
+        .await?;
+    Camera(proxy).is_present().await
+}
+
 #[cfg(not(feature = "pipewire"))]
 pub async fn request() -> Result<Option<RawFd>, Error> {
     let proxy = Camera::new().await?;
@@ -283,3 +409,127 @@ pub async fn request() -> Result<Option<(RawFd, Vec<Stream>)>, Error> {
         Ok(None)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    #[cfg(not(feature = "tokio"))]
+    use std::collections::HashMap;
+
+    #[cfg(not(feature = "tokio"))]
+    use zbus::zvariant::{OwnedValue, Value};
+
+    #[cfg(not(feature = "tokio"))]
+    use super::{Camera, CameraAccess};
+    #[cfg(not(feature = "tokio"))]
+    use crate::{
+        desktop::{ResponseError, PATH},
+        Error,
+    };
+
+    #[cfg(feature = "pipewire")]
+    #[test]
+    fn video_source() {
+        use std::collections::HashMap;
+
+        use super::is_video_source;
+
+        let properties = |class: &str| {
+            HashMap::from([
+                ("media.class".to_owned(), class.to_owned()),
+                ("media.role".to_owned(), "Camera".to_owned()),
+            ])
+        };
+        assert!(is_video_source(&properties("Video/Source")));
+        assert!(!is_video_source(&properties("Stream/Input/Video")));
+        assert!(!is_video_source(&HashMap::new()));
+    }
+
+    // Whether a camera is present and the response code of `AccessCamera`.
+    // The tokio feature makes zbus expect a tokio UnixStream instead.
+    #[cfg(not(feature = "tokio"))]
+    struct MockCamera(bool, u32);
+
+    #[cfg(not(feature = "tokio"))]
+    #[zbus::dbus_interface(name = "org.freedesktop.portal.Camera")]
+    impl MockCamera {
+        async fn access_camera(
+            &self,
+            #[zbus(connection)] connection: &zbus::Connection,
+            options: HashMap<String, OwnedValue>,
+        ) -> zbus::fdo::Result<zbus::zvariant::OwnedObjectPath> {
+            let handle_token = <&str>::try_from(&options["handle_token"]).unwrap();
+            let path = crate::request_path(":1.42", handle_token).unwrap();
+            connection
+                .emit_signal(
+                    None::<zbus::names::BusName<'_>>,
+                    path.as_str(),
+                    "org.freedesktop.portal.Request",
+                    "Response",
+                    &(self.1, HashMap::<&str, Value<'_>>::new()),
+                )
+                .await?;
+            Ok(path)
+        }
+
+        #[dbus_interface(property)]
+        fn is_camera_present(&self) -> bool {
+            self.0
+        }
+    }
+
+    #[cfg(not(feature = "tokio"))]
+    async fn mock_connection(present: bool, code: u32) -> (zbus::Connection, zbus::Connection) {
+        let guid = zbus::Guid::generate();
+        let (server, client) = std::os::unix::net::UnixStream::pair().unwrap();
+        futures_util::try_join!(
+            zbus::ConnectionBuilder::unix_stream(server)
+                .server(&guid)
+                .p2p()
+                .serve_at(PATH, MockCamera(present, code))
+                .unwrap()
+                .build(),
+            zbus::ConnectionBuilder::unix_stream(client)
+                .p2p()
+                .unique_name(":1.42")
+                .unwrap()
+                .build(),
+        )
+        .unwrap()
+    }
+
+    #[cfg(not(feature = "tokio"))]
+    #[test]
+    fn access() {
+        zbus::block_on(async {
+            for (present, code, expected) in [
+                (true, 0, CameraAccess::Granted),
+                (true, 1, CameraAccess::Denied),
+                (false, 0, CameraAccess::NoCamera),
+            ] {
+                let (_server, client) = mock_connection(present, code).await;
+                let camera = Camera::with_connection(&client).await.unwrap();
+                assert_eq!(camera.access().await.unwrap(), expected);
+            }
+
+            let (_server, client) = mock_connection(true, 2).await;
+            let camera = Camera::with_connection(&client).await.unwrap();
+            assert!(matches!(
+                camera.access().await,
+                Err(Error::Response(ResponseError::Other))
+            ));
+        });
+    }
+
+    #[cfg(not(feature = "tokio"))]
+    #[test]
+    fn camera_present() {
+        use super::is_present_on;
+
+        zbus::block_on(async {
+            for present in [true, false] {
+                let (_server, client) = mock_connection(present, 0).await;
+                assert_eq!(is_present_on(&client).await.unwrap(), present);
+            }
+        });
+    }
+}
@@ -0,0 +1,192 @@
+use serde::{Deserialize, Serialize};
+use zbus::zvariant::{OwnedValue, Type};
+
+use crate::Error;
+
+/// A color, as used by the portals.
+///
+/// **Note** the values are normalized, in the `0.0..=1.0` range.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, Type)]
+pub struct Color([f64; 3]);
+
+impl Color {
+    /// Create a new color from its normalized components.
+    pub fn new(red: f64, green: f64, blue: f64) -> Self {
+        Self([red, green, blue])
+    }
+
+    /// Red.
+    pub fn red(&self) -> f64 {
+        self.0[0]
+    }
+
+    /// Green.
+    pub fn green(&self) -> f64 {
+        self.0[1]
+    }
+
+    /// Blue.
+    pub fn blue(&self) -> f64 {
+        self.0[2]
+    }
+
+    /// Create a new color from its 8-bit components.
+    ///
+    /// ```rust
+    /// use ashpd::desktop::Color;
+    ///
+    /// let color = Color::from_rgb8([255, 0, 51]);
+    /// assert_eq!(color, Color::new(1.0, 0.0, 0.2));
+    /// ```
+    pub fn from_rgb8(rgb: [u8; 3]) -> Self {
+        let [red, green, blue] = rgb.map(|c| f64::from(c) / 255.0);
+        Self::new(red, green, blue)
+    }
+
+    /// Parses a `#rrggbb` color, or the `#rgb` shorthand where each digit is
+    /// repeated, like CSS does.
+    ///
+    /// Fails with [`Error::ParseError`] if the leading `#` is missing, if it
+    /// doesn't have 3 or 6 digits or if they aren't hexadecimal.
+    ///
+    /// ```rust
+    /// use ashpd::desktop::Color;
+    ///
+    /// let color = Color::from_hex("#ff8000").unwrap();
+    /// assert_eq!(color, Color::from_rgb8([255, 128, 0]));
+    /// assert_eq!(Color::from_hex("#f80").unwrap(), Color::from_hex("#ff8800").unwrap());
+    /// ```
+    pub fn from_hex(hex: &str) -> Result<Self, Error> {
+        let digits = hex
+            .strip_prefix('#')
+            .ok_or(Error::ParseError("Missing # in hex color"))?;
+        if !digits.bytes().all(|c| c.is_ascii_hexdigit()) {
+            return Err(Error::ParseError("Invalid digit in hex color"));
+        }
+        let component = |digits: &str| u8::from_str_radix(digits, 16).unwrap();
+        let rgb = match digits.len() {
+            3 => [0, 1, 2].map(|i| component(&digits[i..=i]) * 0x11),
+            6 => [0, 2, 4].map(|i| component(&digits[i..i + 2])),
+            _ => return Err(Error::ParseError("Hex color must have 3 or 6 digits")),
+        };
+        Ok(Self::from_rgb8(rgb))
+    }
+}
+
+impl TryFrom<&OwnedValue> for Color {
+    type Error = Error;
+
+    fn try_from(value: &OwnedValue) -> Result<Self, Self::Error> {
+        let (red, green, blue) = <(f64, f64, f64)>::try_from(value.clone())?;
+        Ok(Self::new(red, green, blue))
+    }
+}
+
+#[cfg(feature = "gtk3")]
+impl From<Color> for gtk3::gdk::RGBA {
+    fn from(color: Color) -> Self {
+        gtk3::gdk::RGBA::new(color.red(), color.green(), color.blue(), 1.0)
+    }
+}
+
+#[cfg(feature = "gtk4")]
+impl From<Color> for gtk4::gdk::RGBA {
+    fn from(color: Color) -> Self {
+        gtk4::gdk::RGBA::builder()
+            .red(color.red() as f32)
+            .green(color.green() as f32)
+            .blue(color.blue() as f32)
+            .build()
+    }
+}
+
+#[cfg(feature = "rgb")]
+impl From<Color> for rgb::RGB<f64> {
+    fn from(color: Color) -> Self {
+        rgb::RGB::new(color.red(), color.green(), color.blue())
+    }
+}
+
+#[cfg(feature = "rgb")]
+/// Each normalized component is scaled to `0..=255` and rounded to the nearest
+/// integer, `0.5` rounding up.
+impl From<Color> for rgb::RGB8 {
+    fn from(color: Color) -> Self {
+        let to_u8 = |c: f64| (c.clamp(0.0, 1.0) * 255.0).round() as u8;
+        rgb::RGB8::new(
+            to_u8(color.red()),
+            to_u8(color.green()),
+            to_u8(color.blue()),
+        )
+    }
+}
+
+#[cfg(feature = "palette")]
+impl From<Color> for palette::Srgb<f64> {
+    fn from(color: Color) -> Self {
+        palette::Srgb::new(color.red(), color.green(), color.blue())
+    }
+}
+
+impl std::fmt::Display for Color {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "({}, {}, {})", self.red(), self.green(), self.blue())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use byteorder::LE;
+    use zbus::zvariant::{from_slice, to_bytes, EncodingContext as Context, OwnedValue, Value};
+
+    use super::Color;
+
+    #[test]
+    fn parse_tuple() {
+        let ctxt = Context::<LE>::new_dbus(0);
+        let encoded = to_bytes(ctxt, &(0.1, 0.2, 0.3)).unwrap();
+        let color: Color = from_slice(&encoded, ctxt).unwrap();
+        assert_eq!(color, Color::new(0.1, 0.2, 0.3));
+
+        let value = OwnedValue::from(Value::from((1.0, 0.5, 0.0)));
+        assert_eq!(Color::try_from(&value).unwrap(), Color::new(1.0, 0.5, 0.0));
+        assert!(Color::try_from(&OwnedValue::from(1u32)).is_err());
+    }
+
+    #[test]
+    fn parse_hex() {
+        use crate::Error;
+
+        let color = Color::from_hex("#FF8000").unwrap();
+        assert_eq!(color, Color::new(1.0, 128.0 / 255.0, 0.0));
+        assert_eq!(Color::from_hex("#ff8000").unwrap(), color);
+        assert_eq!(
+            Color::from_hex("#000000").unwrap(),
+            Color::new(0.0, 0.0, 0.0)
+        );
+
+        // Shorthand
+        assert_eq!(
+            Color::from_hex("#f80").unwrap(),
+            Color::from_rgb8([0xff, 0x88, 0x00])
+        );
+
+        for invalid in [
+            "", "#", "ff8000", "#ff800", "#ff80000", "#ff80", "#gg8000", "#+f8000", "#ff 800",
+            "#é80",
+        ] {
+            assert!(
+                matches!(Color::from_hex(invalid), Err(Error::ParseError(_))),
+                "{:?} should be rejected",
+                invalid
+            );
+        }
+    }
+
+    #[cfg(feature = "rgb")]
+    #[test]
+    fn color_to_rgb() {
+        let color = Color::new(1.0, 0.5, 0.0);
+        assert_eq!(rgb::RGB8::from(color), rgb::RGB8::new(255, 128, 0));
+    }
+}
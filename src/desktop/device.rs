@@ -21,10 +21,10 @@ use std::{fmt, str::FromStr};
 use serde::{Deserialize, Serialize};
 use zbus::zvariant::{SerializeDict, Type};
 
-use super::{HandleToken, DESTINATION, PATH};
+use super::{HandleToken, Pid, DESTINATION, PATH};
 use crate::{
     helpers::{call_basic_response_method, session_connection},
-    Error,
+    Error, Portal,
 };
 
 #[derive(SerializeDict, Type, Debug, Default)]
@@ -107,7 +107,7 @@ impl<'a> DeviceProxy<'a> {
         let connection = session_connection().await?;
 
         let proxy = zbus::ProxyBuilder::new_bare(&connection)
-            .interface("org.freedesktop.portal.Device")?
+            .interface(Portal::Device.as_interface_name())?
             .path(PATH)?
             .destination(DESTINATION)?
             .build()
@@ -135,13 +135,17 @@ impl<'a> DeviceProxy<'a> {
     ///
     /// See also [`AccessDevice`](https://flatpak.github.io/xdg-desktop-portal/index.html#gdbus-method-org-freedesktop-portal-Device.AccessDevice).
     #[doc(alias = "AccessDevice")]
-    pub async fn access_device(&self, pid: u32, devices: &[Device]) -> Result<(), Error> {
+    pub async fn access_device(
+        &self,
+        pid: impl Into<Pid>,
+        devices: &[Device],
+    ) -> Result<(), Error> {
         let options = AccessDeviceOptions::default();
         call_basic_response_method(
             self.inner(),
             &options.handle_token,
             "AccessDevice",
-            &(pid, devices, &options),
+            &(pid.into(), devices, &options),
         )
         .await
     }
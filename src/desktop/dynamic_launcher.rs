@@ -45,8 +45,8 @@ use zbus::zvariant::{self, SerializeDict, Type};
 
 use super::{HandleToken, Icon, DESTINATION, PATH};
 use crate::{
-    helpers::{call_method, call_request_method, session_connection},
-    Error, WindowIdentifier,
+    helpers::{call_method, call_request_method, check_window_kind, session_connection},
+    Error, Portal, WindowIdentifier,
 };
 
 #[bitflags]
@@ -77,11 +77,77 @@ pub enum IconType {
     Svg,
 }
 
+impl IconType {
+    /// Whether `bytes` look like an image of this type.
+    fn matches(&self, bytes: &[u8]) -> bool {
+        match self {
+            Self::Png => bytes.starts_with(b"\x89PNG\r\n\x1a\n"),
+            Self::Jpeg => bytes.starts_with(&[0xFF, 0xD8, 0xFF]),
+            // Past the optional XML declaration, comments and doctype
+            Self::Svg => bytes[..bytes.len().min(4096)]
+                .windows(4)
+                .any(|window| window == b"<svg"),
+        }
+    }
+}
+
+// The portal rejects the bigger PNG and JPEG icons
+const MAX_ICON_SIZE: u32 = 512;
+
 #[derive(Deserialize, Type)]
 #[zvariant(signature = "(vsu)")]
 pub struct LauncherIcon(Icon, IconType, u32);
 
 impl LauncherIcon {
+    /// Creates an icon from the content of an image, e.g. a downloaded PNG,
+    /// of `size` x `size` pixels.
+    ///
+    /// Fails with [`Error::InvalidArgument`] if `bytes` are empty or
+    /// aren't an image of `type_`, or if a PNG or JPEG image is bigger than
+    /// 512 x 512 pixels.
+    ///
+    /// ```rust,no_run
+    /// use ashpd::{
+    ///     desktop::dynamic_launcher::{
+    ///         DynamicLauncherProxy, IconType, LauncherIcon, PrepareInstallOptions,
+    ///     },
+    ///     WindowIdentifier,
+    /// };
+    ///
+    /// async fn run() -> ashpd::Result<()> {
+    ///     let bytes = std::fs::read("/tmp/favicon.png").unwrap();
+    ///     let icon = LauncherIcon::from_bytes(bytes, IconType::Png, 128)?;
+    ///     let proxy = DynamicLauncherProxy::new().await?;
+    ///     let (name, token) = proxy
+    ///         .prepare_install(
+    ///             &WindowIdentifier::default(),
+    ///             "My Web App",
+    ///             icon,
+    ///             PrepareInstallOptions::default(),
+    ///         )
+    ///         .await?;
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn from_bytes(bytes: Vec<u8>, type_: IconType, size: u32) -> Result<Self, Error> {
+        if bytes.is_empty() {
+            return Err(Error::InvalidArgument("The icon is empty".to_owned()));
+        }
+        if !type_.matches(&bytes) {
+            return Err(Error::InvalidArgument(format!(
+                "The icon is not a {:?} image",
+                type_
+            )));
+        }
+        if size == 0 || (type_ != IconType::Svg && size > MAX_ICON_SIZE) {
+            return Err(Error::InvalidArgument(format!(
+                "Invalid icon size {}, must be between 1 and {}",
+                size, MAX_ICON_SIZE
+            )));
+        }
+        Ok(Self(Icon::Bytes(bytes), type_, size))
+    }
+
     pub fn icon(&self) -> &Icon {
         &self.0
     }
@@ -95,6 +161,24 @@ impl LauncherIcon {
     }
 }
 
+/// The icon to send to the portal, e.g. to reuse the one of an installed
+/// launcher.
+impl From<LauncherIcon> for Icon {
+    fn from(icon: LauncherIcon) -> Self {
+        icon.0
+    }
+}
+
+/// Fails if `icon` is made of empty bytes, which the portal can't load.
+fn validate_icon(icon: &Icon) -> Result<(), Error> {
+    match icon {
+        Icon::Bytes(bytes) if bytes.is_empty() => {
+            Err(Error::InvalidArgument("The icon is empty".to_owned()))
+        }
+        _ => Ok(()),
+    }
+}
+
 #[derive(Debug, Default, SerializeDict, Type)]
 #[zvariant(signature = "dict")]
 pub struct PrepareInstallOptions {
@@ -146,7 +230,7 @@ impl<'a> DynamicLauncherProxy<'a> {
     pub async fn new() -> Result<DynamicLauncherProxy<'a>, Error> {
         let connection = session_connection().await?;
         let proxy = zbus::ProxyBuilder::new_bare(&connection)
-            .interface("org.freedesktop.portal.DynamicLauncher")?
+            .interface(Portal::DynamicLauncher.as_interface_name())?
             .path(PATH)?
             .destination(DESTINATION)?
             .build()
@@ -169,9 +253,12 @@ impl<'a> DynamicLauncherProxy<'a> {
         &self,
         parent_window: &WindowIdentifier,
         name: &str,
-        icon: Icon,
+        icon: impl Into<Icon>,
         options: PrepareInstallOptions,
     ) -> Result<(String, String), Error> {
+        check_window_kind(parent_window)?;
+        let icon = icon.into();
+        validate_icon(&icon)?;
         let response = call_request_method(
             self.inner(),
             &options.handle_token,
@@ -187,7 +274,13 @@ impl<'a> DynamicLauncherProxy<'a> {
     /// See also [`RequestInstallToken`](https://flatpak.github.io/xdg-desktop-portal/index.html#gdbus-method-org-freedesktop-portal-DynamicLauncher.RequestInstallToken).
     #[doc(alias = "RequestInstallToken")]
     #[doc(alias = "xdp_portal_dynamic_launcher_request_install_token")]
-    pub async fn request_install_token(&self, name: &str, icon: Icon) -> Result<String, Error> {
+    pub async fn request_install_token(
+        &self,
+        name: &str,
+        icon: impl Into<Icon>,
+    ) -> Result<String, Error> {
+        let icon = icon.into();
+        validate_icon(&icon)?;
         // No supported options for now
         let options: HashMap<&str, zvariant::Value<'_>> = HashMap::new();
         let token =
@@ -283,4 +376,45 @@ mod test {
         let icon = vec![IconType::Png];
         assert_eq!(serde_json::to_string(&icon).unwrap(), "[\"png\"]");
     }
+
+    #[test]
+    fn serialize_bytes_icon() {
+        use byteorder::LE;
+        use zvariant::{from_slice, to_bytes, EncodingContext as Context, OwnedValue};
+
+        let png = b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR".to_vec();
+        let icon = LauncherIcon::from_bytes(png.clone(), IconType::Png, 128).unwrap();
+        assert_eq!(icon.type_(), IconType::Png);
+        assert_eq!(icon.size(), 128);
+
+        let ctxt = Context::<LE>::new_dbus(0);
+        let encoded = to_bytes(ctxt, &Icon::from(icon)).unwrap();
+        let (kind, value): (String, OwnedValue) = from_slice(&encoded, ctxt).unwrap();
+        assert_eq!(kind, "bytes");
+        assert_eq!(Vec::<u8>::try_from(value).unwrap(), png);
+
+        let svg = br#"<?xml version="1.0"?><svg xmlns="http://www.w3.org/2000/svg"/>"#;
+        assert!(LauncherIcon::from_bytes(svg.to_vec(), IconType::Svg, 4096).is_ok());
+
+        let is_invalid =
+            |result: Result<LauncherIcon, Error>| matches!(result, Err(Error::InvalidArgument(_)));
+        assert!(is_invalid(LauncherIcon::from_bytes(
+            vec![],
+            IconType::Png,
+            128
+        )));
+        assert!(is_invalid(LauncherIcon::from_bytes(
+            png.clone(),
+            IconType::Jpeg,
+            128
+        )));
+        assert!(is_invalid(LauncherIcon::from_bytes(
+            png.clone(),
+            IconType::Png,
+            1024
+        )));
+        assert!(is_invalid(LauncherIcon::from_bytes(png, IconType::Png, 0)));
+        assert!(validate_icon(&Icon::Bytes(vec![])).is_err());
+        assert!(validate_icon(&Icon::from_names(&["dialog-symbolic"])).is_ok());
+    }
 }
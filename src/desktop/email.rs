@@ -24,15 +24,15 @@
 //! }
 //! ```
 
-use std::os::unix::prelude::AsRawFd;
+use std::os::unix::{io::OwnedFd, prelude::AsRawFd};
 
 use serde::Serialize;
 use zbus::zvariant::{Fd, SerializeDict, Type};
 
 use super::{HandleToken, DESTINATION, PATH};
 use crate::{
-    helpers::{call_basic_response_method, session_connection},
-    Error, WindowIdentifier,
+    helpers::{call_basic_response_method, check_window_kind, session_connection},
+    Error, Portal, WindowIdentifier,
 };
 
 #[derive(SerializeDict, Type, Debug, Default)]
@@ -48,6 +48,45 @@ struct EmailOptions {
     attachment_fds: Option<Vec<Fd>>,
 }
 
+impl EmailOptions {
+    /// Fails if one of the addresses isn't a plausible email address.
+    ///
+    /// A single address is sent with the `address` key, which is the only one
+    /// the older versions of the portal understand.
+    fn normalize(&mut self) -> Result<(), Error> {
+        let addresses = self.address.iter().chain(
+            [&self.addresses, &self.cc, &self.bcc]
+                .into_iter()
+                .flatten()
+                .flatten(),
+        );
+        for address in addresses {
+            if !is_valid_address(address) {
+                return Err(Error::InvalidArgument(format!(
+                    "{:?} is not a valid email address",
+                    address
+                )));
+            }
+        }
+        if self.address.is_none() && self.addresses.as_ref().is_some_and(|a| a.len() == 1) {
+            self.address = self.addresses.take().and_then(|mut a| a.pop());
+        }
+        Ok(())
+    }
+}
+
+/// Whether `address` looks like `local@domain`.
+fn is_valid_address(address: &str) -> bool {
+    match address.rsplit_once('@') {
+        Some((local, domain)) => {
+            !local.is_empty()
+                && !domain.is_empty()
+                && !address.contains(|c: char| c.is_whitespace() || c == ',')
+        }
+        None => false,
+    }
+}
+
 #[derive(Debug)]
 #[doc(alias = "org.freedesktop.portal.Email")]
 struct EmailProxy<'a>(zbus::Proxy<'a>);
@@ -57,7 +96,7 @@ impl<'a> EmailProxy<'a> {
     pub async fn new() -> Result<EmailProxy<'a>, Error> {
         let connection = session_connection().await?;
         let proxy = zbus::ProxyBuilder::new_bare(&connection)
-            .interface("org.freedesktop.portal.Email")?
+            .interface(Portal::Email.as_interface_name())?
             .path(PATH)?
             .destination(DESTINATION)?
             .build()
@@ -89,6 +128,7 @@ impl<'a> EmailProxy<'a> {
         identifier: &WindowIdentifier,
         options: EmailOptions,
     ) -> Result<(), Error> {
+        check_window_kind(identifier)?;
         call_basic_response_method(
             self.inner(),
             &options.handle_token,
@@ -104,6 +144,8 @@ impl<'a> EmailProxy<'a> {
 pub struct EmailRequest {
     identifier: WindowIdentifier,
     options: EmailOptions,
+    // Kept open until the email is composed
+    owned_attachments: Vec<OwnedFd>,
 }
 
 impl EmailRequest {
@@ -181,6 +223,11 @@ impl EmailRequest {
     }
 
     /// Attaches a file to the email.
+    ///
+    /// **Note** only the raw file descriptor of `attachment` is kept, it must
+    /// stay open until [`EmailRequest::build`] completes, otherwise another
+    /// file might get attached instead. Prefer
+    /// [`EmailRequest::attach_owned_fd`].
     #[must_use]
     pub fn attach(mut self, attachment: &impl AsRawFd) -> Self {
         self.add_attachment(attachment);
@@ -197,8 +244,138 @@ impl EmailRequest {
         };
     }
 
-    pub async fn build(self) -> Result<(), Error> {
+    /// Attaches a file to the email, taking ownership of its file descriptor
+    /// so it can't be closed before the portal received it.
+    ///
+    /// The file descriptor is closed once the request completes.
+    ///
+    /// ```rust,no_run
+    /// use std::{fs::File, os::unix::io::OwnedFd};
+    ///
+    /// use ashpd::desktop::email::EmailRequest;
+    ///
+    /// async fn run() -> ashpd::Result<()> {
+    ///     let file = File::open("/home/user/report.pdf").unwrap();
+    ///     EmailRequest::default()
+    ///         .address("test@example.com")
+    ///         .attach_owned_fd(OwnedFd::from(file))
+    ///         .build()
+    ///         .await
+    /// }
+    /// ```
+    #[must_use]
+    pub fn attach_owned_fd(mut self, attachment: OwnedFd) -> Self {
+        self.add_owned_attachment(attachment);
+        self
+    }
+
+    pub fn add_owned_attachment(&mut self, attachment: OwnedFd) {
+        self.add_attachment(&attachment);
+        self.owned_attachments.push(attachment);
+    }
+
+    /// Compose the email.
+    ///
+    /// Fails with [`Error::InvalidArgument`] if one of the addresses,
+    /// CC or BCC isn't a valid email address.
+    pub async fn build(mut self) -> Result<(), Error> {
+        self.options.normalize()?;
         let proxy = EmailProxy::new().await?;
         proxy.compose(&self.identifier, self.options).await
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use byteorder::LE;
+    use zbus::zvariant::{from_slice, to_bytes, EncodingContext as Context, OwnedValue};
+
+    use super::EmailRequest;
+    use crate::Error;
+
+    fn normalized(mut request: EmailRequest) -> Result<HashMap<String, OwnedValue>, Error> {
+        request.options.normalize()?;
+        let ctxt = Context::<LE>::new_dbus(0);
+        let encoded = to_bytes(ctxt, &request.options).unwrap();
+        Ok(from_slice(&encoded, ctxt).unwrap())
+    }
+
+    #[test]
+    fn single_recipient() {
+        let options = normalized(EmailRequest::default().addresses(&["a@example.com"])).unwrap();
+        assert_eq!(
+            String::try_from(options["address"].clone()).unwrap(),
+            "a@example.com"
+        );
+        assert!(!options.contains_key("addresses"));
+    }
+
+    #[test]
+    fn multiple_recipients() {
+        let options = normalized(
+            EmailRequest::default()
+                .addresses(&["a@example.com", "b@example.com"])
+                .cc(&["c@example.com"])
+                .bcc(&["d@example.com"]),
+        )
+        .unwrap();
+        assert!(!options.contains_key("address"));
+        assert_eq!(
+            Vec::<String>::try_from(options["addresses"].clone()).unwrap(),
+            ["a@example.com", "b@example.com"]
+        );
+        assert_eq!(
+            Vec::<String>::try_from(options["cc"].clone()).unwrap(),
+            ["c@example.com"]
+        );
+
+        // Already has an address
+        let options = normalized(
+            EmailRequest::default()
+                .address("a@example.com")
+                .addresses(&["b@example.com"]),
+        )
+        .unwrap();
+        assert!(options.contains_key("addresses"));
+    }
+
+    #[test]
+    fn owned_attachment() {
+        use std::os::unix::io::{AsRawFd, OwnedFd};
+
+        let file = std::fs::File::open(std::env::temp_dir()).unwrap();
+        let fd = OwnedFd::from(file);
+        let raw_fd = fd.as_raw_fd();
+        let request = EmailRequest::default().attach_owned_fd(fd);
+
+        let attachments = request.options.attachment_fds.as_ref().unwrap();
+        assert_eq!(attachments.len(), 1);
+        assert_eq!(attachments[0].as_raw_fd(), raw_fd);
+        // Still open as long as the request is around
+        assert_eq!(request.owned_attachments[0].as_raw_fd(), raw_fd);
+        assert_ne!(unsafe { libc::fcntl(raw_fd, libc::F_GETFD) }, -1);
+    }
+
+    #[test]
+    fn invalid_address() {
+        for address in [
+            "",
+            "example.com",
+            "@example.com",
+            "a@",
+            "a b@example.com",
+            "a@b,c@d",
+        ] {
+            assert!(matches!(
+                normalized(EmailRequest::default().address(address)),
+                Err(Error::InvalidArgument(_))
+            ));
+        }
+        assert!(matches!(
+            normalized(EmailRequest::default().bcc(&["a@example.com", "b"])),
+            Err(Error::InvalidArgument(_))
+        ));
+    }
+}
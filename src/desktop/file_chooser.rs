@@ -22,8 +22,8 @@
 //!                 .insert("utf8", "Unicode (UTF-8)")
 //!                 .insert("latin15", "Western"),
 //!         )
-//!         // A trick to have a checkbox
-//!         .choice(Choice::boolean("re-encode", "Re-encode", false))
+//!         // Read back with `files.choice_bool("re-encode")`
+//!         .choice(Choice::checkbox("re-encode", "Re-encode", false))
 //!         .filter(FileFilter::new("SVG Image").mimetype("image/svg+xml"))
 //!         .build()
 //!         .await?;
@@ -76,24 +76,199 @@
 //! }
 //! ```
 
-use std::{ffi::CString, os::unix::ffi::OsStrExt, path::Path};
+use std::{
+    collections::HashMap,
+    ffi::{CString, OsStr},
+    fmt,
+    future::Future,
+    os::unix::{ffi::OsStrExt, io::AsRawFd},
+    path::{Path, PathBuf},
+    str::FromStr,
+    time::Duration,
+};
 
-use serde::{Deserialize, Serialize};
-use serde_repr::Serialize_repr;
-use zbus::zvariant::{DeserializeDict, SerializeDict, Type};
+use enumflags2::BitFlags;
+use futures_util::{future, stream, Stream, StreamExt};
+use percent_encoding::percent_decode_str;
+use serde::{de, Deserialize, Deserializer, Serialize};
+use serde_repr::{Deserialize_repr, Serialize_repr};
+use zbus::zvariant::{OwnedValue, SerializeDict, Signature, Type, Value};
 
-use super::{HandleToken, DESTINATION, PATH};
+use super::{
+    request::{RequestBuilder, RequestOptions},
+    HandleToken,
+};
 use crate::{
-    helpers::{call_request_method, session_connection},
-    Error, WindowIdentifier,
+    documents::{DocumentFlags, Documents, OwnedDocumentID, Permission},
+    helpers::{ensure_window, read_file, unblock, write_file},
+    Error, Portal, WindowIdentifier,
 };
 
-#[derive(Clone, Serialize, Type, Debug)]
+#[derive(Clone, Serialize, Deserialize, Type, Debug, PartialEq, Eq, Hash)]
 /// A file filter, to limit the available file choices to a mimetype or a glob
 /// pattern.
 pub struct FileFilter(String, Vec<(FilterType, String)>);
 
-#[derive(Clone, Serialize_repr, Debug, Type)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+/// A mime type of the `type/subtype` form, `image/png` for example, to use
+/// with [`FileFilter::mime`].
+///
+/// The type must be one of the registered top-level types, and both the type
+/// and the subtype can be a `*` wildcard, like `image/*` or `*/*`.
+///
+/// ```rust
+/// use ashpd::desktop::file_chooser::Mime;
+///
+/// assert!("image/png".parse::<Mime>().is_ok());
+/// assert!("image/*".parse::<Mime>().is_ok());
+/// assert!("img/png".parse::<Mime>().is_err());
+/// ```
+pub struct Mime(String);
+
+// The registered top-level media types, along with `inode` used by the
+// shared-mime-info database for directories.
+const MIME_TOP_LEVEL_TYPES: &[&str] = &[
+    "application",
+    "audio",
+    "font",
+    "image",
+    "inode",
+    "message",
+    "model",
+    "multipart",
+    "text",
+    "video",
+];
+
+impl Mime {
+    /// The mime type as a string.
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl FromStr for Mime {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        // The characters allowed by RFC 6838 in the type and subtype names
+        let is_name = |name: &str| {
+            name.starts_with(|c: char| c.is_ascii_alphanumeric())
+                && name.len() <= 127
+                && name
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || "!#$&-^_.+".contains(c))
+        };
+        let (type_, subtype) = s.split_once('/').ok_or(Error::ParseError(
+            "Failed to parse mime type, missing subtype",
+        ))?;
+        let type_ = type_.to_ascii_lowercase();
+        let valid_type = match type_.as_str() {
+            "*" => subtype == "*",
+            type_ => MIME_TOP_LEVEL_TYPES.contains(&type_),
+        };
+        if !valid_type {
+            return Err(Error::ParseError("Failed to parse mime type, invalid type"));
+        }
+        if subtype != "*" && !is_name(subtype) {
+            return Err(Error::ParseError(
+                "Failed to parse mime type, invalid subtype",
+            ));
+        }
+        Ok(Self(format!("{}/{}", type_, subtype.to_ascii_lowercase())))
+    }
+}
+
+impl TryFrom<&str> for Mime {
+    type Error = Error;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl fmt::Display for Mime {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+// The mime types known by `FileFilter::from_mime`, with their label and
+// extensions.
+const KNOWN_MIME_TYPES: &[(&str, &str, &[&str])] = &[
+    ("application/json", "JSON document", &["json"]),
+    ("application/pdf", "PDF document", &["pdf"]),
+    ("application/zip", "Zip archive", &["zip"]),
+    ("audio/flac", "FLAC audio", &["flac"]),
+    ("audio/mpeg", "MP3 audio", &["mp3"]),
+    ("audio/ogg", "Ogg audio", &["ogg", "oga"]),
+    ("image/gif", "GIF image", &["gif"]),
+    ("image/jpeg", "JPEG image", &["jpg", "jpeg"]),
+    ("image/png", "PNG image", &["png"]),
+    ("image/svg+xml", "SVG image", &["svg"]),
+    ("image/webp", "WebP image", &["webp"]),
+    ("text/csv", "CSV document", &["csv"]),
+    ("text/html", "HTML document", &["html", "htm"]),
+    ("text/markdown", "Markdown document", &["md"]),
+    ("text/plain", "Plain text document", &["txt"]),
+    ("video/mp4", "MPEG-4 video", &["mp4"]),
+    ("video/webm", "WebM video", &["webm"]),
+];
+
+// The signatures of the formats of KNOWN_MIME_TYPES that have one, as
+// (offset, bytes, mime type).
+const MAGIC_NUMBERS: &[(usize, &[u8], &str)] = &[
+    (0, b"%PDF-", "application/pdf"),
+    (0, b"PK\x03\x04", "application/zip"),
+    (0, b"fLaC", "audio/flac"),
+    (0, b"ID3", "audio/mpeg"),
+    (0, b"OggS", "audio/ogg"),
+    (0, b"GIF87a", "image/gif"),
+    (0, b"GIF89a", "image/gif"),
+    (0, b"\xff\xd8\xff", "image/jpeg"),
+    (0, b"\x89PNG\r\n\x1a\n", "image/png"),
+    (8, b"WEBP", "image/webp"),
+    (4, b"ftyp", "video/mp4"),
+    (0, b"\x1a\x45\xdf\xa3", "video/webm"),
+];
+
+/// The mime type of the file at `path`, from its content or else from its
+/// extension.
+fn sniff_mime_type(path: &Path) -> Result<Option<String>, Error> {
+    use std::io::Read;
+
+    let mut file = std::fs::File::open(path).map_err(|err| Error::File(path.to_owned(), err))?;
+    if file
+        .metadata()
+        .map_err(|err| Error::File(path.to_owned(), err))?
+        .is_dir()
+    {
+        return Ok(Some("inode/directory".to_owned()));
+    }
+    let mut header = Vec::with_capacity(16);
+    file.by_ref()
+        .take(16)
+        .read_to_end(&mut header)
+        .map_err(|err| Error::File(path.to_owned(), err))?;
+    let from_content = MAGIC_NUMBERS
+        .iter()
+        .find(|(offset, magic, _)| header.get(*offset..*offset + magic.len()) == Some(magic));
+    if let Some((_, _, mime)) = from_content {
+        return Ok(Some((*mime).to_owned()));
+    }
+    let extension = path
+        .extension()
+        .and_then(OsStr::to_str)
+        .map(str::to_ascii_lowercase);
+    Ok(extension.and_then(|extension| {
+        KNOWN_MIME_TYPES
+            .iter()
+            .find(|(_, _, extensions)| extensions.contains(&extension.as_str()))
+            .map(|(mime, ..)| (*mime).to_owned())
+    }))
+}
+
+#[derive(Clone, Serialize_repr, Deserialize_repr, Debug, Type, PartialEq, Eq, Hash)]
 #[repr(u32)]
 enum FilterType {
     GlobPattern = 0,
@@ -110,13 +285,86 @@ impl FileFilter {
         Self(label.to_owned(), vec![])
     }
 
+    /// Create a new file filter matching the given file extensions.
+    ///
+    /// A `*.ext` glob pattern is added for each extension, a leading `.` is
+    /// ignored.
+    ///
+    /// ```rust
+    /// use ashpd::desktop::file_chooser::FileFilter;
+    ///
+    /// let filter = FileFilter::with_extensions("Images", &["png", "jpg", ".jpeg"]);
+    /// ```
+    pub fn with_extensions(label: &str, extensions: &[&str]) -> Self {
+        extensions.iter().fold(Self::new(label), |filter, ext| {
+            filter.glob(&format!("*.{}", ext.trim_start_matches('.')))
+        })
+    }
+
+    /// Create a new file filter matching the given mime types.
+    ///
+    /// ```rust
+    /// use ashpd::desktop::file_chooser::FileFilter;
+    ///
+    /// let filter = FileFilter::with_mime_types("Documents", &["application/pdf", "text/plain"]);
+    /// ```
+    pub fn with_mime_types(label: &str, mime_types: &[&str]) -> Self {
+        mime_types
+            .iter()
+            .fold(Self::new(label), |filter, mime_type| {
+                filter.mimetype(mime_type)
+            })
+    }
+
+    /// Create a new file filter for `mime`, e.g. `image/png`, with a
+    /// user-visible label and the glob patterns of its usual extensions.
+    ///
+    /// The label and the extensions come from a small built-in table of the
+    /// common formats. For other mime types, the filter only matches the mime
+    /// type and the label is the mime type itself, use [`FileFilter::new`] to
+    /// build a nicer one.
+    ///
+    /// ```rust
+    /// use ashpd::desktop::file_chooser::FileFilter;
+    ///
+    /// let filter = FileFilter::from_mime("image/png");
+    /// assert_eq!(filter, FileFilter::new("PNG image").mimetype("image/png").glob("*.png"));
+    /// ```
+    pub fn from_mime(mime: &str) -> Self {
+        match KNOWN_MIME_TYPES.iter().find(|(known, ..)| *known == mime) {
+            Some((_, label, extensions)) => extensions
+                .iter()
+                .fold(Self::new(label).mimetype(mime), |filter, ext| {
+                    filter.glob(&format!("*.{}", ext))
+                }),
+            None => Self::new(mime).mimetype(mime),
+        }
+    }
+
     /// Adds a mime type to the file filter.
+    ///
+    /// See [`FileFilter::mime`] to catch malformed mime types.
     #[must_use]
     pub fn mimetype(mut self, mimetype: &str) -> Self {
         self.1.push((FilterType::MimeType, mimetype.to_owned()));
         self
     }
 
+    /// Adds a validated mime type to the file filter.
+    ///
+    /// ```rust
+    /// use ashpd::desktop::file_chooser::FileFilter;
+    ///
+    /// # fn run() -> ashpd::Result<()> {
+    /// let filter = FileFilter::new("Images").mime("image/*".parse()?);
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[must_use]
+    pub fn mime(self, mime: Mime) -> Self {
+        self.mimetype(mime.as_str())
+    }
+
     /// Adds a glob pattern to the file filter.
     #[must_use]
     pub fn glob(mut self, pattern: &str) -> Self {
@@ -125,7 +373,7 @@ impl FileFilter {
     }
 }
 
-#[derive(Clone, Serialize, Deserialize, Type, Debug)]
+#[derive(Clone, Serialize, Deserialize, Type, Debug, PartialEq, Eq, Hash)]
 /// Presents the user with a choice to select from or as a checkbox.
 pub struct Choice(String, String, Vec<(String, String)>, String);
 
@@ -138,7 +386,20 @@ impl Choice {
     /// * `label` - user-visible name of the choice.
     /// * `state` - the initial state value.
     pub fn boolean(id: &str, label: &str, state: bool) -> Self {
-        Self::new(id, label, &state.to_string())
+        Self::checkbox(id, label, state).into()
+    }
+
+    /// Creates a checkbox choice, whose state is read back with
+    /// [`SelectedFiles::choice_bool`].
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - A unique identifier of the choice.
+    /// * `label` - user-visible name of the choice.
+    /// * `default` - Whether the checkbox is initially checked.
+    pub fn checkbox(id: &str, label: &str, default: bool) -> Checkbox {
+        let state = if default { "true" } else { "false" };
+        Checkbox(Self::new(id, label, state))
     }
 
     /// Creates a new choice.
@@ -180,6 +441,33 @@ impl Choice {
     }
 }
 
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+/// A [`Choice`] presented as a checkbox, created with [`Choice::checkbox`].
+pub struct Checkbox(Choice);
+
+impl Checkbox {
+    /// The choice's unique id
+    pub fn id(&self) -> &str {
+        self.0.id()
+    }
+
+    /// The user visible label of the choice.
+    pub fn label(&self) -> &str {
+        self.0.label()
+    }
+
+    /// Whether the checkbox is initially checked.
+    pub fn default(&self) -> bool {
+        self.0.initial_selection() == "true"
+    }
+}
+
+impl From<Checkbox> for Choice {
+    fn from(checkbox: Checkbox) -> Self {
+        checkbox.0
+    }
+}
+
 #[derive(SerializeDict, Type, Debug, Default)]
 #[zvariant(signature = "dict")]
 struct OpenFileOptions {
@@ -188,6 +476,7 @@ struct OpenFileOptions {
     modal: Option<bool>,
     multiple: Option<bool>,
     directory: Option<bool>,
+    writable: Option<bool>,
     filters: Vec<FileFilter>,
     current_filter: Option<FileFilter>,
     choices: Vec<Choice>,
@@ -218,16 +507,247 @@ struct SaveFilesOptions {
     files: Option<Vec<Vec<u8>>>,
 }
 
-#[derive(Debug, Type, DeserializeDict)]
+impl RequestOptions for OpenFileOptions {
+    fn handle_token(&self) -> &HandleToken {
+        &self.handle_token
+    }
+
+    fn set_handle_token(&mut self, handle_token: HandleToken) {
+        self.handle_token = handle_token;
+    }
+
+    fn modal_mut(&mut self) -> Option<&mut Option<bool>> {
+        Some(&mut self.modal)
+    }
+}
+
+impl RequestOptions for SaveFileOptions {
+    fn handle_token(&self) -> &HandleToken {
+        &self.handle_token
+    }
+
+    fn set_handle_token(&mut self, handle_token: HandleToken) {
+        self.handle_token = handle_token;
+    }
+
+    fn modal_mut(&mut self) -> Option<&mut Option<bool>> {
+        Some(&mut self.modal)
+    }
+}
+
+impl RequestOptions for SaveFilesOptions {
+    fn handle_token(&self) -> &HandleToken {
+        &self.handle_token
+    }
+
+    fn set_handle_token(&mut self, handle_token: HandleToken) {
+        self.handle_token = handle_token;
+    }
+
+    fn modal_mut(&mut self) -> Option<&mut Option<bool>> {
+        Some(&mut self.modal)
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
 /// A response of [`OpenFileRequest`], [`SaveFileRequest`] or
 /// [`SaveFilesRequest`].
-#[zvariant(signature = "dict")]
+///
+/// Iterating over a reference of [`SelectedFiles`] yields the selected files
+/// uris.
+///
+/// ```rust
+/// use std::collections::HashMap;
+///
+/// use ashpd::desktop::file_chooser::SelectedFiles;
+/// use byteorder::LE;
+/// use zbus::zvariant::{from_slice, to_bytes, EncodingContext, Value};
+///
+/// let ctxt = EncodingContext::<LE>::new_dbus(0);
+/// let mut response = HashMap::new();
+/// response.insert(
+///     "uris",
+///     Value::from(vec!["file:///home/user/a.txt", "file:///home/user/b.txt"]),
+/// );
+/// let encoded = to_bytes(ctxt, &response).unwrap();
+/// let files: SelectedFiles = from_slice(&encoded, ctxt).unwrap();
+///
+/// assert_eq!(files.len(), 2);
+/// for uri in &files {
+///     assert_eq!(uri.scheme(), "file");
+/// }
+/// ```
+///
+/// It can also be (de)serialized with human readable formats like JSON, for
+/// example to remember the recently opened files. The uris, choices,
+/// writable state and document ids are stored as plain fields.
+///
+/// ```rust
+/// use ashpd::desktop::file_chooser::SelectedFiles;
+///
+/// let json = r#"{"uris":["file:///home/user/a.txt"],"choices":[["encoding","utf8"]]}"#;
+/// let files: SelectedFiles = serde_json::from_str(json).unwrap();
+/// assert_eq!(files.uris()[0].path(), "/home/user/a.txt");
+/// assert_eq!(files.choices(), [("encoding".to_owned(), "utf8".to_owned())]);
+/// ```
 pub struct SelectedFiles {
     uris: Vec<url::Url>,
     choices: Option<Vec<(String, String)>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    writable: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    current_filter: Option<FileFilter>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    document_ids: Vec<OwnedDocumentID>,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    directory: bool,
+    #[serde(skip)]
+    extra: HashMap<String, OwnedValue>,
+}
+
+impl Type for SelectedFiles {
+    fn signature() -> Signature<'static> {
+        SelectedFilesDict::signature()
+    }
+}
+
+impl<'de> Deserialize<'de> for SelectedFiles {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        // The portal sends the results as a vardict, while the human readable
+        // formats use a plain struct.
+        if deserializer.is_human_readable() {
+            #[derive(Deserialize)]
+            struct Plain {
+                uris: Vec<url::Url>,
+                #[serde(default)]
+                choices: Option<Vec<(String, String)>>,
+                #[serde(default)]
+                writable: Option<bool>,
+                #[serde(default)]
+                current_filter: Option<FileFilter>,
+                #[serde(default)]
+                document_ids: Vec<OwnedDocumentID>,
+                #[serde(default)]
+                directory: bool,
+            }
+
+            let plain = Plain::deserialize(deserializer)?;
+            Ok(Self {
+                uris: plain.uris,
+                choices: plain.choices,
+                writable: plain.writable,
+                current_filter: plain.current_filter,
+                document_ids: plain.document_ids,
+                directory: plain.directory,
+                extra: HashMap::new(),
+            })
+        } else {
+            // Keep the results that aren't modeled, see SelectedFiles::extra
+            let mut results = HashMap::<String, OwnedValue>::deserialize(deserializer)?;
+            let uris = take_result::<Vec<String>, D::Error>(&mut results, "uris")?
+                .ok_or_else(|| de::Error::missing_field("uris"))?
+                .iter()
+                .map(|uri| uri.parse().map_err(de::Error::custom))
+                .collect::<Result<_, _>>()?;
+            let choices = take_result(&mut results, "choices")?;
+            let writable = take_result(&mut results, "writable")?;
+            let current_filter = take_result::<(String, Vec<(u32, String)>), D::Error>(
+                &mut results,
+                "current_filter",
+            )?
+            .map(|(label, filters)| {
+                let filters = filters
+                    .into_iter()
+                    .map(|(kind, filter)| match kind {
+                        0 => Ok((FilterType::GlobPattern, filter)),
+                        1 => Ok((FilterType::MimeType, filter)),
+                        _ => Err(de::Error::custom("Unknown file filter type")),
+                    })
+                    .collect::<Result<_, _>>()?;
+                Ok(FileFilter(label, filters))
+            })
+            .transpose()?;
+            Ok(Self {
+                uris,
+                choices,
+                writable,
+                current_filter,
+                document_ids: Vec::new(),
+                directory: false,
+                extra: results,
+            })
+        }
+    }
+}
+
+/// Removes the result at `key`, converting it to `T`.
+fn take_result<T, E>(
+    results: &mut HashMap<String, OwnedValue>,
+    key: &'static str,
+) -> Result<Option<T>, E>
+where
+    T: TryFrom<Value<'static>>,
+    E: de::Error,
+{
+    results
+        .remove(key)
+        .map(|value| {
+            T::try_from(Value::from(value))
+                .map_err(|_| E::custom(format!("Unexpected type of the `{}` result", key)))
+        })
+        .transpose()
+}
+
+#[derive(Type)]
+#[zvariant(signature = "dict")]
+struct SelectedFilesDict;
+
+/// Lists the selected files uris, separated by a comma.
+impl std::fmt::Display for SelectedFiles {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (i, uri) in self.uris.iter().enumerate() {
+            if i > 0 {
+                f.write_str(", ")?;
+            }
+            f.write_str(uri.as_str())?;
+        }
+        Ok(())
+    }
 }
 
 impl SelectedFiles {
+    #[cfg(feature = "mock")]
+    /// Creates a [`SelectedFiles`] with the selected `uris` and the value of
+    /// each choice as a tuple of (key, value), to fake the portal response in
+    /// tests.
+    ///
+    /// Requires the `mock` feature.
+    ///
+    /// ```rust
+    /// use ashpd::desktop::file_chooser::SelectedFiles;
+    ///
+    /// let files = SelectedFiles::new(
+    ///     vec!["file:///home/user/a.txt".parse().unwrap()],
+    ///     vec![("encoding".to_owned(), "utf8".to_owned())],
+    /// );
+    /// assert_eq!(files.uris()[0].path(), "/home/user/a.txt");
+    /// assert_eq!(files.choices()[0].1, "utf8");
+    /// ```
+    pub fn new(uris: Vec<url::Url>, choices: Vec<(String, String)>) -> Self {
+        Self {
+            uris,
+            choices: Some(choices),
+            writable: None,
+            current_filter: None,
+            document_ids: Vec::new(),
+            directory: false,
+            extra: HashMap::new(),
+        }
+    }
+
     /// The selected files uris.
     pub fn uris(&self) -> &[url::Url] {
         self.uris.as_slice()
@@ -237,81 +757,291 @@ impl SelectedFiles {
     pub fn choices(&self) -> &[(String, String)] {
         self.choices.as_deref().unwrap_or_default()
     }
-}
 
-#[doc(alias = "org.freedesktop.portal.FileChooser")]
-struct FileChooserProxy<'a>(zbus::Proxy<'a>);
+    /// The selected value of the choice with the given `id`, if any.
+    ///
+    /// For a boolean choice, added with [`Choice::boolean`], the value is
+    /// either `"true"` or `"false"`, see [`SelectedFiles::choice_bool`].
+    pub fn choice(&self, id: &str) -> Option<&str> {
+        self.choices()
+            .iter()
+            .find(|(key, _)| key == id)
+            .map(|(_, value)| value.as_str())
+    }
 
-impl<'a> FileChooserProxy<'a> {
-    /// Create a new instance of [`FileChooserProxy`].
-    pub async fn new() -> Result<FileChooserProxy<'a>, Error> {
-        let connection = session_connection().await?;
-        let proxy = zbus::ProxyBuilder::new_bare(&connection)
-            .interface("org.freedesktop.portal.FileChooser")?
-            .path(PATH)?
-            .destination(DESTINATION)?
-            .build()
-            .await?;
-        Ok(Self(proxy))
+    /// Whether the checkbox with the given `id`, added with
+    /// [`Choice::checkbox`], was checked.
+    ///
+    /// `None` if there is no such choice or if its value isn't a boolean.
+    pub fn choice_bool(&self, id: &str) -> Option<bool> {
+        match self.choice(id)? {
+            "true" => Some(true),
+            "false" => Some(false),
+            _ => None,
+        }
     }
 
-    /// Get a reference to the underlying Proxy.
-    pub fn inner(&self) -> &zbus::Proxy<'_> {
-        &self.0
+    /// The selected value of each choice, by id.
+    pub fn choices_map(&self) -> HashMap<&str, &str> {
+        self.choices()
+            .iter()
+            .map(|(key, value)| (key.as_str(), value.as_str()))
+            .collect()
     }
 
-    pub async fn open_file(
-        &self,
-        identifier: &WindowIdentifier,
-        title: &str,
-        options: OpenFileOptions,
-    ) -> Result<SelectedFiles, Error> {
-        call_request_method(
-            self.inner(),
-            &options.handle_token,
-            "OpenFile",
-            &(&identifier, title, &options),
-        )
-        .await
+    /// Whether the application was granted write access to the selected
+    /// files.
+    ///
+    /// `None` if the backend doesn't report it, in which case the files
+    /// should be assumed to be read-only for [`OpenFileRequest`].
+    pub fn writable(&self) -> Option<bool> {
+        self.writable
     }
 
-    pub async fn save_file(
-        &self,
-        identifier: &WindowIdentifier,
-        title: &str,
-        options: SaveFileOptions,
-    ) -> Result<SelectedFiles, Error> {
-        call_request_method(
-            self.inner(),
-            &options.handle_token,
-            "SaveFile",
-            &(&identifier, title, &options),
-        )
-        .await
+    /// The filter that was selected in the dialog when the user confirmed it,
+    /// for both [`OpenFileRequest`] and [`SaveFileRequest`].
+    ///
+    /// Useful to remember it for the next request with `current_filter`.
+    /// `None` if no filter was set or if the backend doesn't report it, which
+    /// is the case of older ones.
+    pub fn current_filter(&self) -> Option<&FileFilter> {
+        self.current_filter.as_ref()
     }
 
-    pub async fn save_files(
-        &self,
-        identifier: &WindowIdentifier,
-        title: &str,
-        options: SaveFilesOptions,
-    ) -> Result<SelectedFiles, Error> {
-        call_request_method(
-            self.inner(),
-            &options.handle_token,
-            "SaveFiles",
-            &(&identifier, title, &options),
+    /// The ids of the selected files in the document store, in the order of
+    /// [`SelectedFiles::uris`].
+    ///
+    /// Only set when the access to the files was persisted with
+    /// [`OpenFileRequest::persist_access`], empty otherwise.
+    pub fn document_ids(&self) -> &[OwnedDocumentID] {
+        &self.document_ids
+    }
+
+    /// The results sent by the portal that aren't modeled by
+    /// [`SelectedFiles`], like the ones added by a newer version of the
+    /// portal or by a specific backend.
+    ///
+    /// Always empty when deserialized from a human readable format.
+    pub fn extra(&self) -> &HashMap<String, OwnedValue> {
+        &self.extra
+    }
+
+    /// Whether the uris are directories, which is the case when they were
+    /// selected with [`OpenFileRequest::directory`].
+    pub fn is_directory(&self) -> bool {
+        self.directory
+    }
+
+    /// The paths of the selected directories, in the order of
+    /// [`SelectedFiles::uris`].
+    ///
+    /// Empty unless the directories were selected with
+    /// [`OpenFileRequest::directory`]. The uris that are not local
+    /// directories are skipped.
+    pub fn directories(&self) -> Vec<PathBuf> {
+        if !self.directory {
+            return Vec::new();
+        }
+        self.uris
+            .iter()
+            .filter_map(|uri| uri.to_file_path().ok())
+            .collect()
+    }
+
+    /// The number of selected files.
+    pub fn len(&self) -> usize {
+        self.uris.len()
+    }
+
+    /// Whether no file was selected.
+    pub fn is_empty(&self) -> bool {
+        self.uris.is_empty()
+    }
+
+    #[cfg(feature = "gtk4")]
+    /// The selected files as a [`gio::ListStore`](https://gtk-rs.org/gtk-rs-core/stable/latest/docs/gio/struct.ListStore.html)
+    /// of [`gio::File`](https://gtk-rs.org/gtk-rs-core/stable/latest/docs/gio/struct.File.html),
+    /// in the order of [`SelectedFiles::uris`], to be used as the model of a
+    /// list view.
+    ///
+    /// The uris that are not local files give remote files, which can only
+    /// be accessed if GVfs supports their scheme.
+    pub fn to_gio_list_store(&self) -> ::gtk4::gio::ListStore {
+        use ::gtk4::{gio, glib::StaticType};
+
+        let store = gio::ListStore::new(gio::File::static_type());
+        for uri in &self.uris {
+            store.append(&gio::File::for_uri(uri.as_str()));
+        }
+        store
+    }
+
+    /// The folder containing all the selected files.
+    ///
+    /// Useful with [`SaveFilesRequest`] to retrieve the folder the user picked.
+    /// Returns `None` if no file was selected, if one of the uris is not a
+    /// local file or if the files are not all in the same folder.
+    pub fn parent_folder(&self) -> Option<PathBuf> {
+        let mut parents = self.uris.iter().map(|uri| {
+            let path = uri.to_file_path().ok()?;
+            path.parent().map(Path::to_path_buf)
+        });
+        let parent = parents.next()??;
+        parents
+            .all(|p| p.as_ref() == Some(&parent))
+            .then_some(parent)
+    }
+
+    /// Write `data` to the selected files, one item per file in the order of
+    /// [`SelectedFiles::uris`].
+    ///
+    /// Meant to be used with the response of [`SaveFilesRequest`], the
+    /// returned stream yields a [`WriteProgress`] once each file is written,
+    /// which can be used to report the progress to the user. Writing stops
+    /// once either the files or the data run out.
+    ///
+    /// ```rust,no_run
+    /// use ashpd::desktop::file_chooser::SaveFilesRequest;
+    /// use futures_util::StreamExt;
+    ///
+    /// async fn run() -> ashpd::Result<()> {
+    ///     let files = SaveFilesRequest::default()
+    ///         .files(&["a.txt", "b.txt"])
+    ///         .build()
+    ///         .await?;
+    ///     let mut progress = files.write_all(["first", "second"]);
+    ///     while let Some(progress) = progress.next().await {
+    ///         let progress = progress?;
+    ///         println!("{}/{}", progress.index() + 1, progress.total());
+    ///     }
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn write_all<'a, I, D>(
+        &'a self,
+        data: I,
+    ) -> impl Stream<Item = Result<WriteProgress, Error>> + Unpin + 'a
+    where
+        I: IntoIterator<Item = D>,
+        I::IntoIter: 'a,
+        D: AsRef<[u8]> + 'a,
+    {
+        let total = self.uris.len();
+        Box::pin(stream::iter(self.uris.iter().zip(data).enumerate()).then(
+            move |(index, (uri, data))| async move {
+                let path = local_path(uri)?;
+                write_file(&path, data.as_ref()).await?;
+                Ok(WriteProgress { index, total, path })
+            },
+        ))
+    }
+
+    /// Opens the selected files for writing, creating them or truncating them
+    /// if they already exist, in the order of [`SelectedFiles::uris`].
+    ///
+    /// Meant to be used with the response of [`SaveFileRequest`] or
+    /// [`SaveFilesRequest`], the portal having granted access to the returned
+    /// locations. Fails with [`Error::IO`] if one of the uris is not a local
+    /// file, and with [`Error::File`] if one of the files can't be opened, for
+    /// example when the permission is denied.
+    ///
+    /// ```rust,no_run
+    /// use std::io::Write;
+    ///
+    /// use ashpd::desktop::file_chooser::SaveFileRequest;
+    ///
+    /// async fn run() -> ashpd::Result<()> {
+    ///     let files = SaveFileRequest::default()
+    ///         .current_name("report.txt")
+    ///         .build()
+    ///         .await?;
+    ///     let mut file = files.create_files()?.remove(0);
+    ///     file.write_all(b"Hello, World!")?;
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn create_files(&self) -> Result<Vec<std::fs::File>, Error> {
+        self.uris
+            .iter()
+            .map(|uri| {
+                let path = local_path(uri)?;
+                std::fs::File::create(&path).map_err(|err| Error::File(path, err))
+            })
+            .collect()
+    }
+
+    /// The mime type of each selected file, in the order of
+    /// [`SelectedFiles::uris`].
+    ///
+    /// The type is guessed from the first bytes of the files for the common
+    /// formats, or else from their extension, `inode/directory` being used
+    /// for the directories. It is `None` when it can't be told or when the
+    /// uri is not a local file. Fails with [`Error::File`] if one of the files
+    /// can't be read.
+    pub fn mime_types(&self) -> Result<Vec<Option<String>>, Error> {
+        self.uris
+            .iter()
+            .map(|uri| match uri.to_file_path() {
+                Ok(path) => sniff_mime_type(&path),
+                Err(_) => Ok(None),
+            })
+            .collect()
+    }
+}
+
+/// The path of `uri`, failing with [`Error::IO`] if it is not a local file.
+fn local_path(uri: &url::Url) -> Result<PathBuf, Error> {
+    uri.to_file_path().map_err(|_| {
+        std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            format!("{} is not a local file", uri),
         )
-        .await
+        .into()
+    })
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// The progress of [`SelectedFiles::write_all`].
+pub struct WriteProgress {
+    index: usize,
+    total: usize,
+    path: PathBuf,
+}
+
+impl WriteProgress {
+    /// The index of the file that was written.
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    /// The total number of files to write.
+    pub fn total(&self) -> usize {
+        self.total
+    }
+
+    /// The path of the file that was written.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl<'a> IntoIterator for &'a SelectedFiles {
+    type Item = &'a url::Url;
+    type IntoIter = std::slice::Iter<'a, url::Url>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.uris.iter()
     }
 }
 
 #[derive(Debug, Default)]
 #[doc(alias = "xdp_portal_open_file")]
 pub struct OpenFileRequest {
-    identifier: WindowIdentifier,
+    request: RequestBuilder<OpenFileOptions>,
     title: String,
-    options: OpenFileOptions,
+    persist_access: bool,
+    expected_extensions: Vec<String>,
+    default_filter_index: Option<usize>,
 }
 
 impl OpenFileRequest {
@@ -323,7 +1053,7 @@ impl OpenFileRequest {
     }
 
     pub fn set_identifier(&mut self, identifier: WindowIdentifier) {
-        self.identifier = identifier;
+        self.request.identifier = identifier;
     }
 
     /// Sets a title for the file chooser dialog.
@@ -345,18 +1075,24 @@ impl OpenFileRequest {
     }
 
     pub fn set_accept_label(&mut self, accept_label: &str) {
-        self.options.accept_label = Some(accept_label.to_owned());
+        self.request.options.accept_label = Some(accept_label.to_owned());
     }
 
     /// Sets whether the dialog should be a modal.
+    ///
+    /// By default, the dialog is modal if a parent window was set with
+    /// [`OpenFileRequest::identifier`], the option is left unset for the
+    /// backend to decide otherwise. `None` leaves it to the backend even with a
+    /// parent window, use `false` for a non-modal dialog.
     #[must_use]
-    pub fn modal(mut self, modal: bool) -> Self {
+    pub fn modal(mut self, modal: impl Into<Option<bool>>) -> Self {
         self.set_modal(modal);
         self
     }
 
-    pub fn set_modal(&mut self, modal: bool) {
-        self.options.modal = Some(modal);
+    /// Sets whether the dialog should be a modal, or unsets it with `None`.
+    pub fn set_modal(&mut self, modal: impl Into<Option<bool>>) {
+        self.request.set_modal(modal.into());
     }
 
     /// Sets whether to allow multiple files selection.
@@ -367,7 +1103,7 @@ impl OpenFileRequest {
     }
 
     pub fn set_multiple(&mut self, multiple: bool) {
-        self.options.multiple = Some(multiple);
+        self.request.options.multiple = Some(multiple);
     }
 
     /// Sets whether to select directories or not.
@@ -378,52 +1114,316 @@ impl OpenFileRequest {
     }
 
     pub fn set_directory(&mut self, directory: bool) {
-        self.options.directory = Some(directory);
+        self.request.options.directory = Some(directory);
+    }
+
+    /// Sets whether the access to the selected files should be remembered
+    /// across restarts of the application, off by default.
+    ///
+    /// Once the user picked the files, they get added permanently to the
+    /// document store and their ids are returned with
+    /// [`SelectedFiles::document_ids`]. The files then stay available to the
+    /// application, without asking the user again, until the documents are
+    /// deleted with [`Documents::delete`].
+    ///
+    /// **Note** the user only agreed to share the files for the current use,
+    /// only persist the access when the application needs it, e.g. for a list
+    /// of recent files, and let the user forget them. The permission is
+    /// granted for reading, and writing if the application was granted write
+    /// access with [`OpenFileRequest::writable`].
+    #[must_use]
+    pub fn persist_access(mut self, persist_access: bool) -> Self {
+        self.set_persist_access(persist_access);
+        self
+    }
+
+    pub fn set_persist_access(&mut self, persist_access: bool) {
+        self.persist_access = persist_access;
+    }
+
+    /// Requires the selected files to have the `extension`, or one of the
+    /// extensions when called several times, compared case-insensitively.
+    /// A leading `.` is ignored.
+    ///
+    /// The filters only restrict what the dialog shows by default, the user
+    /// can still pick any file. Once the user confirmed the dialog, the
+    /// request fails with [`Error::InvalidArgument`] if one of the files
+    /// doesn't match.
+    #[must_use]
+    pub fn expect_extension(mut self, extension: &str) -> Self {
+        self.add_expected_extension(extension);
+        self
+    }
+
+    pub fn add_expected_extension(&mut self, extension: &str) {
+        self.expected_extensions
+            .push(extension.trim_start_matches('.').to_lowercase());
+    }
+
+    /// Sets whether the application wants to write to the selected files.
+    ///
+    /// By default, files are opened for reading only. Requesting write access
+    /// lets the file chooser warn the user about it, and the response tells
+    /// whether it was granted with [`SelectedFiles::writable`].
+    ///
+    /// **Note** older backends ignore this option.
+    #[must_use]
+    pub fn writable(mut self, writable: bool) -> Self {
+        self.set_writable(writable);
+        self
+    }
+
+    pub fn set_writable(&mut self, writable: bool) {
+        self.request.options.writable = Some(writable);
     }
 
     /// Adds a files filter.
+    ///
+    /// The filters are sent in the order they are added, which is the order
+    /// the file chooser lists them in. Without a default filter, some
+    /// backends select the first one.
     #[must_use]
     pub fn filter(mut self, filter: FileFilter) -> Self {
-        self.options.filters.push(filter);
+        self.request.options.filters.push(filter);
         self
     }
 
     pub fn set_filters(&mut self, filters: &[FileFilter]) {
-        self.options.filters = filters.to_vec();
+        self.request.options.filters = filters.to_vec();
+    }
+
+    /// Specifies the default filter, or unsets it with `None`.
+    ///
+    /// Without a default filter, the `current_filter` option is left out and
+    /// the backend picks one.
+    #[must_use]
+    pub fn current_filter(mut self, current_filter: impl Into<Option<FileFilter>>) -> Self {
+        self.set_current_filter(current_filter);
+        self
+    }
+
+    pub fn set_current_filter(&mut self, current_filter: impl Into<Option<FileFilter>>) {
+        self.default_filter_index = None;
+        self.request.options.current_filter = current_filter.into();
     }
 
-    /// Specifies the default filter.
+    /// Specifies the default filter by its index in the added filters,
+    /// instead of passing it again to [`OpenFileRequest::current_filter`].
+    ///
+    /// The request fails with [`Error::InvalidArgument`] if there is
+    /// no filter at `index`.
+    ///
+    /// ```rust,no_run
+    /// use ashpd::desktop::file_chooser::{FileFilter, OpenFileRequest};
+    ///
+    /// async fn run() -> ashpd::Result<()> {
+    ///     let files = OpenFileRequest::default()
+    ///         .filter(FileFilter::new("Text").mimetype("text/plain"))
+    ///         .filter(FileFilter::new("Images").mimetype("image/*"))
+    ///         .default_filter_index(1)
+    ///         .build()
+    ///         .await?;
+    ///     Ok(())
+    /// }
+    /// ```
     #[must_use]
-    pub fn current_filter(mut self, current_filter: FileFilter) -> Self {
-        self.options.current_filter = Some(current_filter);
+    pub fn default_filter_index(mut self, index: usize) -> Self {
+        self.set_default_filter_index(index);
         self
     }
 
-    /// Adds a choice.
+    pub fn set_default_filter_index(&mut self, index: usize) {
+        self.default_filter_index = Some(index);
+    }
+
+    /// Sets the filter at [`OpenFileRequest::default_filter_index`] as the
+    /// current filter, if any.
+    fn resolve_default_filter(&mut self) -> Result<(), Error> {
+        let index = match self.default_filter_index {
+            Some(index) => index,
+            None => return Ok(()),
+        };
+        let filters = &self.request.options.filters;
+        let filter = filters.get(index).ok_or_else(|| {
+            Error::InvalidArgument(format!(
+                "No filter at index {}, only {} filters were added",
+                index,
+                filters.len()
+            ))
+        })?;
+        self.request.options.current_filter = Some(filter.clone());
+        Ok(())
+    }
+
+    /// Adds a choice, or a [`Checkbox`].
     #[must_use]
-    pub fn choice(mut self, choice: Choice) -> Self {
-        self.options.choices.push(choice);
+    pub fn choice(mut self, choice: impl Into<Choice>) -> Self {
+        self.request.options.choices.push(choice.into());
         self
     }
 
     pub fn set_choices(&mut self, choices: &[Choice]) {
-        self.options.choices = choices.to_vec();
+        self.request.options.choices = choices.to_vec();
     }
 
+    /// Sets how long to wait for the user to respond before closing the
+    /// dialog and failing with [`Error::Timeout`].
+    ///
+    /// By default, there is no timeout.
+    #[must_use]
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.set_timeout(timeout);
+        self
+    }
+
+    pub fn set_timeout(&mut self, timeout: Duration) {
+        self.request.timeout = Some(timeout);
+    }
+
+    /// Sets the token of the request instead of a random one, e.g. to find
+    /// the request in the application logs and the D-Bus traces.
+    ///
+    /// The token is used verbatim in the path of the request, see
+    /// [`request_path`](crate::request_path).
+    #[must_use]
+    pub fn handle_token(mut self, handle_token: HandleToken) -> Self {
+        self.set_handle_token(handle_token);
+        self
+    }
+
+    pub fn set_handle_token(&mut self, handle_token: HandleToken) {
+        self.request.options.set_handle_token(handle_token);
+    }
+
+    /// Show the file chooser dialog.
+    ///
+    /// Fails with [`Error::NoWindow`] if there is neither a parent window nor
+    /// a display to show the dialog on.
     pub async fn build(self) -> Result<SelectedFiles, Error> {
-        let proxy = FileChooserProxy::new().await?;
-        proxy
-            .open_file(&self.identifier, &self.title, self.options)
-            .await
+        self.build_until(future::pending::<()>()).await
+    }
+
+    async fn build_until(mut self, cancel: impl Future) -> Result<SelectedFiles, Error> {
+        ensure_window(&self.request.identifier)?;
+        self.request.resolve_modal();
+        self.resolve_default_filter()?;
+        let mut files: SelectedFiles = self
+            .request
+            .send_until(
+                Portal::FileChooser,
+                "OpenFile",
+                &(&self.request.identifier, &self.title, &self.request.options),
+                cancel,
+            )
+            .await?;
+        files.directory = self.request.options.directory == Some(true);
+        check_extensions(&files, &self.expected_extensions)?;
+        if self.persist_access {
+            files.document_ids = persist_access(&files).await?;
+        }
+        Ok(files)
+    }
+
+    /// Show the file chooser dialog, until `cancel` resolves.
+    ///
+    /// If `cancel` resolves before the user is done with the dialog, e.g.
+    /// when the parent window gets closed, the request is closed, dismissing
+    /// the dialog, and it fails with [`Error::Cancelled`]. See
+    /// [`OpenFileRequest::build`] otherwise.
+    ///
+    /// ```rust,no_run
+    /// use ashpd::desktop::file_chooser::OpenFileRequest;
+    /// use futures_channel::oneshot;
+    ///
+    /// async fn run(window_closed: oneshot::Receiver<()>) -> ashpd::Result<()> {
+    ///     match OpenFileRequest::default()
+    ///         .build_with_cancellation(window_closed)
+    ///         .await
+    ///     {
+    ///         Err(ashpd::Error::Cancelled) => println!("The window was closed"),
+    ///         files => println!("{:#?}", files?),
+    ///     }
+    ///     Ok(())
+    /// }
+    /// ```
+    pub async fn build_with_cancellation(
+        self,
+        cancel: impl Future,
+    ) -> Result<SelectedFiles, Error> {
+        self.build_until(cancel).await
+    }
+}
+
+/// Fails if one of `files` doesn't have one of the `extensions`, if any.
+fn check_extensions(files: &SelectedFiles, extensions: &[String]) -> Result<(), Error> {
+    if extensions.is_empty() {
+        return Ok(());
+    }
+    let unexpected = files.uris.iter().find(|uri| {
+        // The non-ASCII characters of the file name are percent-encoded
+        let name = uri
+            .path_segments()
+            .and_then(|mut segments| segments.next_back())
+            .map(|name| percent_decode_str(name).decode_utf8_lossy());
+        let extension = name
+            .as_deref()
+            .and_then(|name| name.rsplit_once('.'))
+            .map(|(_, extension)| extension.to_lowercase());
+        !extension.is_some_and(|extension| extensions.contains(&extension))
+    });
+    match unexpected {
+        Some(uri) => Err(Error::InvalidArgument(format!(
+            "{} doesn't have one of the expected extensions",
+            uri
+        ))),
+        None => Ok(()),
     }
 }
 
+/// Adds the selected files to the document store, permanently.
+async fn persist_access(files: &SelectedFiles) -> Result<Vec<OwnedDocumentID>, Error> {
+    let paths = files
+        .uris
+        .iter()
+        .map(local_path)
+        .collect::<Result<Vec<_>, Error>>()?;
+    let fds = unblock(move || {
+        paths
+            .into_iter()
+            .map(|path| std::fs::File::open(&path).map_err(|err| Error::File(path, err)))
+            .collect::<Result<Vec<_>, Error>>()
+    })
+    .await?;
+    let mut permissions = vec![Permission::Read];
+    if files.writable == Some(true) {
+        permissions.push(Permission::Write);
+    }
+    let proxy = Documents::new().await?;
+    let (document_ids, _) = proxy
+        .add_full(
+            &fds.iter().collect::<Vec<_>>(),
+            document_flags(files),
+            "",
+            &permissions,
+        )
+        .await?;
+    Ok(document_ids)
+}
+
+// The document portal rejects the folders exported without `ExportDirectory`
+fn document_flags(files: &SelectedFiles) -> BitFlags<DocumentFlags> {
+    let mut flags = DocumentFlags::ReuseExisting | DocumentFlags::Persistent;
+    if files.directory {
+        flags |= DocumentFlags::ExportDirectory;
+    }
+    flags
+}
+
 #[derive(Debug, Default)]
 #[doc(alias = "xdp_portal_save_files")]
 pub struct SaveFilesRequest {
-    identifier: WindowIdentifier,
+    request: RequestBuilder<SaveFilesOptions>,
     title: String,
-    options: SaveFilesOptions,
 }
 
 impl SaveFilesRequest {
@@ -435,7 +1435,7 @@ impl SaveFilesRequest {
     }
 
     pub fn set_identifier(&mut self, identifier: WindowIdentifier) {
-        self.identifier = identifier;
+        self.request.identifier = identifier;
     }
 
     /// Sets a title for the file chooser dialog.
@@ -457,29 +1457,35 @@ impl SaveFilesRequest {
     }
 
     pub fn set_accept_label(&mut self, accept_label: &str) {
-        self.options.accept_label = Some(accept_label.to_owned());
+        self.request.options.accept_label = Some(accept_label.to_owned());
     }
 
     /// Sets whether the dialog should be a modal.
+    ///
+    /// By default, the dialog is modal if a parent window was set with
+    /// [`SaveFilesRequest::identifier`], the option is left unset for the
+    /// backend to decide otherwise. `None` leaves it to the backend even with a
+    /// parent window, use `false` for a non-modal dialog.
     #[must_use]
-    pub fn modal(mut self, modal: bool) -> Self {
+    pub fn modal(mut self, modal: impl Into<Option<bool>>) -> Self {
         self.set_modal(modal);
         self
     }
 
-    pub fn set_modal(&mut self, modal: bool) {
-        self.options.modal = Some(modal);
+    /// Sets whether the dialog should be a modal, or unsets it with `None`.
+    pub fn set_modal(&mut self, modal: impl Into<Option<bool>>) {
+        self.request.set_modal(modal.into());
     }
 
-    /// Adds a choice.
+    /// Adds a choice, or a [`Checkbox`].
     #[must_use]
-    pub fn choice(mut self, choice: Choice) -> Self {
-        self.options.choices.push(choice);
+    pub fn choice(mut self, choice: impl Into<Choice>) -> Self {
+        self.request.options.choices.push(choice.into());
         self
     }
 
     pub fn set_choices(&mut self, choices: &[Choice]) {
-        self.options.choices = choices.to_vec();
+        self.request.options.choices = choices.to_vec();
     }
 
     /// Specifies the current folder path.
@@ -492,10 +1498,39 @@ impl SaveFilesRequest {
     pub fn set_current_folder(&mut self, current_folder: impl AsRef<Path>) {
         let cstr = CString::new(current_folder.as_ref().as_os_str().as_bytes())
             .expect("`current_folder` should not be null terminated");
-        self.options.current_folder = Some(cstr.into_bytes_with_nul());
+        self.request.options.current_folder = Some(cstr.into_bytes_with_nul());
+    }
+
+    /// Specifies the current folder from an open directory, for applications
+    /// that only have a file descriptor of it.
+    ///
+    /// **Note** the portal specifications have no file descriptor based
+    /// option for the current folder yet, so the path the descriptor refers to
+    /// is sent instead. The path has to be reachable by the portal, which is
+    /// the case of the document portal ones. The current folder is left unset
+    /// if the path can't be found.
+    #[must_use]
+    pub fn current_folder_fd(mut self, current_folder: &impl AsRawFd) -> Self {
+        self.set_current_folder_fd(current_folder);
+        self
+    }
+
+    pub fn set_current_folder_fd(&mut self, current_folder: &impl AsRawFd) {
+        if let Some(path) = fd_path(current_folder) {
+            self.set_current_folder(path);
+        }
     }
 
     /// Sets a list of files to save.
+    ///
+    /// Only the file names are used, the folder they get saved to is picked
+    /// by the user. The names are sent as raw, nul-terminated bytes, so names
+    /// containing spaces, unicode characters or invalid UTF-8 sequences are
+    /// preserved as they are.
+    ///
+    /// # Panics
+    ///
+    /// If one of the file names contains a nul byte.
     #[must_use]
     pub fn files(mut self, files: &[impl AsRef<Path>]) -> Self {
         self.set_files(files);
@@ -503,7 +1538,7 @@ impl SaveFilesRequest {
     }
 
     pub fn set_files(&mut self, files: &[impl AsRef<Path>]) {
-        self.options.files = Some(
+        self.request.options.files = Some(
             files
                 .iter()
                 .map(|s| {
@@ -515,10 +1550,60 @@ impl SaveFilesRequest {
         );
     }
 
-    pub async fn build(self) -> Result<SelectedFiles, Error> {
-        let proxy = FileChooserProxy::new().await?;
-        proxy
-            .save_files(&self.identifier, &self.title, self.options)
+    /// Sets how long to wait for the user to respond before closing the
+    /// dialog and failing with [`Error::Timeout`].
+    ///
+    /// By default, there is no timeout.
+    #[must_use]
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.set_timeout(timeout);
+        self
+    }
+
+    pub fn set_timeout(&mut self, timeout: Duration) {
+        self.request.timeout = Some(timeout);
+    }
+
+    /// Sets the token of the request instead of a random one, e.g. to find
+    /// the request in the application logs and the D-Bus traces.
+    ///
+    /// The token is used verbatim in the path of the request, see
+    /// [`request_path`](crate::request_path).
+    #[must_use]
+    pub fn handle_token(mut self, handle_token: HandleToken) -> Self {
+        self.set_handle_token(handle_token);
+        self
+    }
+
+    pub fn set_handle_token(&mut self, handle_token: HandleToken) {
+        self.request.options.set_handle_token(handle_token);
+    }
+
+    /// Show the file chooser dialog.
+    ///
+    /// Fails with [`Error::InvalidArgument`] if no files were set, as
+    /// the portal would otherwise present an empty dialog, or with
+    /// [`Error::NoWindow`] if there is neither a parent window nor a display
+    /// to show the dialog on.
+    pub async fn build(mut self) -> Result<SelectedFiles, Error> {
+        if self
+            .request
+            .options
+            .files
+            .as_deref()
+            .unwrap_or_default()
+            .is_empty()
+        {
+            return Err(Error::InvalidArgument("No files to save".to_owned()));
+        }
+        ensure_window(&self.request.identifier)?;
+        self.request.resolve_modal();
+        self.request
+            .send(
+                Portal::FileChooser,
+                "SaveFiles",
+                &(&self.request.identifier, &self.title, &self.request.options),
+            )
             .await
     }
 }
@@ -526,9 +1611,9 @@ impl SaveFilesRequest {
 #[derive(Debug, Default)]
 #[doc(alias = "xdp_portal_save_file")]
 pub struct SaveFileRequest {
-    identifier: WindowIdentifier,
+    request: RequestBuilder<SaveFileOptions>,
     title: String,
-    options: SaveFileOptions,
+    remember_folder: Option<PathBuf>,
 }
 
 impl SaveFileRequest {
@@ -541,7 +1626,7 @@ impl SaveFileRequest {
     }
 
     pub fn set_identifier(&mut self, identifier: WindowIdentifier) {
-        self.identifier = identifier;
+        self.request.identifier = identifier;
     }
 
     /// Sets a title for the file chooser dialog.
@@ -563,18 +1648,24 @@ impl SaveFileRequest {
     }
 
     pub fn set_accept_label(&mut self, accept_label: &str) {
-        self.options.accept_label = Some(accept_label.to_owned());
+        self.request.options.accept_label = Some(accept_label.to_owned());
     }
 
     /// Sets whether the dialog should be a modal.
+    ///
+    /// By default, the dialog is modal if a parent window was set with
+    /// [`SaveFileRequest::identifier`], the option is left unset for the
+    /// backend to decide otherwise. `None` leaves it to the backend even with a
+    /// parent window, use `false` for a non-modal dialog.
     #[must_use]
-    pub fn modal(mut self, modal: bool) -> Self {
+    pub fn modal(mut self, modal: impl Into<Option<bool>>) -> Self {
         self.set_modal(modal);
         self
     }
 
-    pub fn set_modal(&mut self, modal: bool) {
-        self.options.modal = Some(modal);
+    /// Sets whether the dialog should be a modal, or unsets it with `None`.
+    pub fn set_modal(&mut self, modal: impl Into<Option<bool>>) {
+        self.request.set_modal(modal.into());
     }
 
     /// Sets the current file name.
@@ -585,7 +1676,7 @@ impl SaveFileRequest {
     }
 
     pub fn set_current_name(&mut self, current_name: &str) {
-        self.options.current_name = Some(current_name.to_owned());
+        self.request.options.current_name = Some(current_name.to_owned());
     }
 
     /// Sets the current folder.
@@ -598,7 +1689,27 @@ impl SaveFileRequest {
     pub fn set_current_folder(&mut self, current_folder: impl AsRef<Path>) {
         let cstr = CString::new(current_folder.as_ref().as_os_str().as_bytes())
             .expect("`current_folder` should not be null terminated");
-        self.options.current_folder = Some(cstr.into_bytes_with_nul());
+        self.request.options.current_folder = Some(cstr.into_bytes_with_nul());
+    }
+
+    /// Specifies the current folder from an open directory, for applications
+    /// that only have a file descriptor of it.
+    ///
+    /// **Note** the portal specifications have no file descriptor based
+    /// option for the current folder yet, so the path the descriptor refers to
+    /// is sent instead. The path has to be reachable by the portal, which is
+    /// the case of the document portal ones. The current folder is left unset
+    /// if the path can't be found.
+    #[must_use]
+    pub fn current_folder_fd(mut self, current_folder: &impl AsRawFd) -> Self {
+        self.set_current_folder_fd(current_folder);
+        self
+    }
+
+    pub fn set_current_folder_fd(&mut self, current_folder: &impl AsRawFd) {
+        if let Some(path) = fd_path(current_folder) {
+            self.set_current_folder(path);
+        }
     }
 
     /// Sets the absolute path of the file.
@@ -611,46 +1722,1054 @@ impl SaveFileRequest {
     pub fn set_current_file(&mut self, current_file: impl AsRef<Path>) {
         let cstr = CString::new(current_file.as_ref().as_os_str().as_bytes())
             .expect("`current_file` should not be null terminated");
-        self.options.current_file = Some(cstr.into_bytes_with_nul());
+        self.request.options.current_file = Some(cstr.into_bytes_with_nul());
+    }
+
+    /// Remembers the folder the file was saved in across runs, storing it in
+    /// `state_file`, e.g. a file in the application's config directory.
+    ///
+    /// The dialog opens in the stored folder unless a current folder or file
+    /// is set, and the folder of the saved file is stored once the user
+    /// confirms the dialog. Failing to read or write `state_file` doesn't
+    /// fail the request, the dialog opens in the default folder instead.
+    #[must_use]
+    pub fn remember_folder(mut self, state_file: impl AsRef<Path>) -> Self {
+        self.set_remember_folder(state_file);
+        self
+    }
+
+    pub fn set_remember_folder(&mut self, state_file: impl AsRef<Path>) {
+        self.remember_folder = Some(state_file.as_ref().to_owned());
     }
 
     /// Adds a files filter.
     #[must_use]
     pub fn filter(mut self, filter: FileFilter) -> Self {
-        self.options.filters.push(filter);
+        self.request.options.filters.push(filter);
         self
     }
 
     pub fn set_filters(&mut self, filters: &[FileFilter]) {
-        self.options.filters = filters.to_vec();
+        self.request.options.filters = filters.to_vec();
     }
 
-    /// Sets the default filter.
+    /// Sets the default filter, or unsets it with `None`.
+    ///
+    /// Without a default filter, the `current_filter` option is left out and
+    /// the backend picks one.
     #[must_use]
-    pub fn current_filter(mut self, current_filter: FileFilter) -> Self {
+    pub fn current_filter(mut self, current_filter: impl Into<Option<FileFilter>>) -> Self {
         self.set_current_filter(current_filter);
         self
     }
 
-    pub fn set_current_filter(&mut self, current_filter: FileFilter) {
-        self.options.current_filter = Some(current_filter);
+    pub fn set_current_filter(&mut self, current_filter: impl Into<Option<FileFilter>>) {
+        self.request.options.current_filter = current_filter.into();
     }
 
-    /// Adds a choice.
+    /// Adds a choice, or a [`Checkbox`].
     #[must_use]
-    pub fn choice(mut self, choice: Choice) -> Self {
-        self.options.choices.push(choice);
+    pub fn choice(mut self, choice: impl Into<Choice>) -> Self {
+        self.request.options.choices.push(choice.into());
         self
     }
 
     pub fn set_choices(&mut self, choices: &[Choice]) {
-        self.options.choices = choices.to_vec();
+        self.request.options.choices = choices.to_vec();
     }
 
-    pub async fn build(self) -> Result<SelectedFiles, Error> {
-        let proxy = FileChooserProxy::new().await?;
-        proxy
-            .save_file(&self.identifier, &self.title, self.options)
-            .await
+    /// Sets how long to wait for the user to respond before closing the
+    /// dialog and failing with [`Error::Timeout`].
+    ///
+    /// By default, there is no timeout.
+    #[must_use]
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.set_timeout(timeout);
+        self
+    }
+
+    pub fn set_timeout(&mut self, timeout: Duration) {
+        self.request.timeout = Some(timeout);
+    }
+
+    /// Sets the token of the request instead of a random one, e.g. to find
+    /// the request in the application logs and the D-Bus traces.
+    ///
+    /// The token is used verbatim in the path of the request, see
+    /// [`request_path`](crate::request_path).
+    #[must_use]
+    pub fn handle_token(mut self, handle_token: HandleToken) -> Self {
+        self.set_handle_token(handle_token);
+        self
+    }
+
+    pub fn set_handle_token(&mut self, handle_token: HandleToken) {
+        self.request.options.set_handle_token(handle_token);
+    }
+
+    /// Show the file chooser dialog.
+    ///
+    /// Fails with [`Error::NoWindow`] if there is neither a parent window nor
+    /// a display to show the dialog on.
+    pub async fn build(mut self) -> Result<SelectedFiles, Error> {
+        ensure_window(&self.request.identifier)?;
+        self.request.resolve_modal();
+        self.restore_folder().await;
+        let files: SelectedFiles = self
+            .request
+            .send(
+                Portal::FileChooser,
+                "SaveFile",
+                &(&self.request.identifier, &self.title, &self.request.options),
+            )
+            .await?;
+        if let Some(state_file) = &self.remember_folder {
+            store_folder(state_file, &files).await;
+        }
+        Ok(files)
+    }
+
+    /// Sets the current folder to the one stored by
+    /// [`SaveFileRequest::remember_folder`], if any.
+    async fn restore_folder(&mut self) {
+        let options = &self.request.options;
+        if options.current_folder.is_some() || options.current_file.is_some() {
+            return;
+        }
+        let state_file = match &self.remember_folder {
+            Some(state_file) => state_file,
+            None => return,
+        };
+        match read_file(state_file).await {
+            Ok(folder) if !folder.is_empty() => {
+                let folder = PathBuf::from(OsStr::from_bytes(&folder));
+                self.set_current_folder(folder);
+            }
+            Ok(_) => (),
+            // Nothing was stored yet on the first run
+            Err(_err) => {
+                #[cfg(feature = "tracing")]
+                tracing::debug!("No remembered folder: {}", _err);
+            }
+        }
+    }
+}
+
+/// Stores the folder `files` were saved in to `state_file`.
+async fn store_folder(state_file: &Path, files: &SelectedFiles) {
+    let folder = match files.parent_folder() {
+        Some(folder) => folder,
+        None => return,
+    };
+    if let Err(_err) = write_file(state_file, folder.as_os_str().as_bytes()).await {
+        #[cfg(feature = "tracing")]
+        tracing::warn!("Failed to remember the folder {:?}: {}", folder, _err);
+    }
+}
+
+/// The path the file descriptor `fd` refers to.
+fn fd_path(fd: &impl AsRawFd) -> Option<PathBuf> {
+    let path = std::fs::read_link(format!("/proc/self/fd/{}", fd.as_raw_fd()));
+    #[cfg(feature = "tracing")]
+    if let Err(err) = &path {
+        tracing::warn!(
+            "Failed to find the path of the fd {}: {}",
+            fd.as_raw_fd(),
+            err
+        );
+    }
+    path.ok()
+}
+
+/// Check whether the file chooser portal is available.
+///
+/// See also [`is_portal_available`](crate::is_portal_available).
+pub async fn is_available() -> Result<bool, Error> {
+    crate::is_portal_available(Portal::FileChooser).await
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::HashMap, os::unix::ffi::OsStrExt};
+
+    use byteorder::LE;
+    use zbus::zvariant::{
+        from_slice, to_bytes, EncodingContext as Context, OwnedValue, SerializeDict, Type, Value,
+    };
+
+    use super::{
+        document_flags, FileFilter, FilterType, OpenFileRequest, SaveFileRequest, SaveFilesRequest,
+        SelectedFiles,
+    };
+    use crate::{documents::DocumentFlags, Error};
+
+    fn selected_files(uris: &[&str]) -> SelectedFiles {
+        SelectedFiles {
+            uris: uris.iter().map(|uri| uri.parse().unwrap()).collect(),
+            choices: None,
+            writable: None,
+            current_filter: None,
+            document_ids: Vec::new(),
+            directory: false,
+            extra: HashMap::new(),
+        }
+    }
+
+    #[test]
+    fn choice_by_id() {
+        let mut files = selected_files(&["file:///home/user/a.txt"]);
+        assert_eq!(files.choice("encoding"), None);
+        assert!(files.choices_map().is_empty());
+
+        files.choices = Some(vec![
+            ("encoding".to_owned(), "utf8".to_owned()),
+            ("reencode".to_owned(), "true".to_owned()),
+        ]);
+        assert_eq!(files.choice("encoding"), Some("utf8"));
+        assert_eq!(files.choice("reencode"), Some("true"));
+        assert_eq!(files.choice("charset"), None);
+        let choices = files.choices_map();
+        assert_eq!(choices.len(), 2);
+        assert_eq!(choices["encoding"], "utf8");
+    }
+
+    #[test]
+    fn checkbox_choice() {
+        use super::Choice;
+
+        let checkbox = Choice::checkbox("reencode", "Re-encode", true);
+        assert_eq!(checkbox.id(), "reencode");
+        assert_eq!(checkbox.label(), "Re-encode");
+        assert!(checkbox.default());
+        assert!(!Choice::checkbox("reencode", "Re-encode", false).default());
+        let choice = Choice::from(checkbox);
+        assert_eq!(choice.initial_selection(), "true");
+        assert_eq!(choice, Choice::boolean("reencode", "Re-encode", true));
+
+        let mut files = selected_files(&["file:///home/user/a.txt"]);
+        files.choices = Some(vec![
+            ("reencode".to_owned(), "true".to_owned()),
+            ("backup".to_owned(), "false".to_owned()),
+            ("encoding".to_owned(), "utf8".to_owned()),
+        ]);
+        assert_eq!(files.choice_bool("reencode"), Some(true));
+        assert_eq!(files.choice_bool("backup"), Some(false));
+        assert_eq!(files.choice_bool("encoding"), None);
+        assert_eq!(files.choice_bool("charset"), None);
+    }
+
+    #[cfg(feature = "gtk4")]
+    #[test]
+    fn gio_list_store() {
+        use ::gtk4::{gio, prelude::*};
+
+        let files = selected_files(&["file:///home/user/a.txt", "sftp://example.com/b.txt"]);
+        let store = files.to_gio_list_store();
+        assert_eq!(store.n_items(), 2);
+        let file = store.item(0).unwrap().downcast::<gio::File>().unwrap();
+        assert_eq!(file.path(), Some("/home/user/a.txt".into()));
+        let file = store.item(1).unwrap().downcast::<gio::File>().unwrap();
+        assert_eq!(file.uri(), "sftp://example.com/b.txt");
+
+        assert_eq!(selected_files(&[]).to_gio_list_store().n_items(), 0);
+    }
+
+    #[test]
+    fn display_selected_files() {
+        let files = selected_files(&["file:///home/user/a.txt", "file:///home/user/b%20c.txt"]);
+        assert_eq!(
+            files.to_string(),
+            "file:///home/user/a.txt, file:///home/user/b%20c.txt"
+        );
+        assert_eq!(selected_files(&[]).to_string(), "");
+    }
+
+    #[test]
+    fn serialize_save_files() {
+        let request = SaveFilesRequest::default().files(&["my report.pdf", "日本語.txt"]);
+
+        let ctxt = Context::<LE>::new_dbus(0);
+        let encoded = to_bytes(ctxt, &request.request.options).unwrap();
+        let mut decoded: HashMap<String, OwnedValue> = from_slice(&encoded, ctxt).unwrap();
+        let files = Vec::<Vec<u8>>::try_from(decoded.remove("files").unwrap()).unwrap();
+        assert_eq!(
+            files,
+            vec![
+                b"my report.pdf\0".to_vec(),
+                "日本語.txt\0".as_bytes().to_vec()
+            ]
+        );
+    }
+
+    #[test]
+    fn serialize_modal() {
+        let ctxt = Context::<LE>::new_dbus(0);
+
+        let mut request = OpenFileRequest::default();
+        let encoded = to_bytes(ctxt, &request.request.options).unwrap();
+        let decoded: HashMap<String, OwnedValue> = from_slice(&encoded, ctxt).unwrap();
+        assert!(!decoded.contains_key("modal"));
+
+        request.set_modal(false);
+        let encoded = to_bytes(ctxt, &request.request.options).unwrap();
+        let decoded: HashMap<String, OwnedValue> = from_slice(&encoded, ctxt).unwrap();
+        assert!(!bool::try_from(&decoded["modal"]).unwrap());
+
+        request.set_modal(None);
+        let encoded = to_bytes(ctxt, &request.request.options).unwrap();
+        let decoded: HashMap<String, OwnedValue> = from_slice(&encoded, ctxt).unwrap();
+        assert!(!decoded.contains_key("modal"));
+
+        let request = SaveFileRequest::default().modal(true).modal(None);
+        let encoded = to_bytes(ctxt, &request.request.options).unwrap();
+        let decoded: HashMap<String, OwnedValue> = from_slice(&encoded, ctxt).unwrap();
+        assert!(!decoded.contains_key("modal"));
+
+        let request = SaveFilesRequest::default().modal(true);
+        let encoded = to_bytes(ctxt, &request.request.options).unwrap();
+        let decoded: HashMap<String, OwnedValue> = from_slice(&encoded, ctxt).unwrap();
+        assert!(bool::try_from(&decoded["modal"]).unwrap());
+    }
+
+    #[test]
+    fn default_modal() {
+        use crate::WindowIdentifier;
+
+        let parent = || WindowIdentifier::try_from("x11:0x400").unwrap();
+
+        // Without a parent window
+        let mut request = OpenFileRequest::default();
+        request.request.resolve_modal();
+        assert_eq!(request.request.options.modal, None);
+
+        let mut request = OpenFileRequest::default().identifier(parent());
+        request.request.resolve_modal();
+        assert_eq!(request.request.options.modal, Some(true));
+        let mut request = SaveFileRequest::default().identifier(parent());
+        request.request.resolve_modal();
+        assert_eq!(request.request.options.modal, Some(true));
+        let mut request = SaveFilesRequest::default().identifier(parent());
+        request.request.resolve_modal();
+        assert_eq!(request.request.options.modal, Some(true));
+
+        // Explicitly set
+        let mut request = OpenFileRequest::default().identifier(parent()).modal(false);
+        request.request.resolve_modal();
+        assert_eq!(request.request.options.modal, Some(false));
+        let mut request = SaveFileRequest::default().modal(true);
+        request.request.resolve_modal();
+        assert_eq!(request.request.options.modal, Some(true));
+
+        // Explicitly unset
+        let mut request = OpenFileRequest::default().identifier(parent()).modal(None);
+        request.request.resolve_modal();
+        assert_eq!(request.request.options.modal, None);
+        let mut request = SaveFileRequest::default()
+            .identifier(parent())
+            .modal(true)
+            .modal(None);
+        request.request.resolve_modal();
+        assert_eq!(request.request.options.modal, None);
+    }
+
+    #[test]
+    fn serialize_shared_options() {
+        let ctxt = Context::<LE>::new_dbus(0);
+        let shared = |options: &[u8]| {
+            let mut decoded: HashMap<String, OwnedValue> = from_slice(options, ctxt).unwrap();
+            decoded.remove("handle_token").unwrap();
+            (
+                bool::try_from(&decoded["modal"]).unwrap(),
+                <&str>::try_from(&decoded["accept_label"])
+                    .unwrap()
+                    .to_owned(),
+            )
+        };
+
+        let mut open_file = OpenFileRequest::default();
+        open_file.set_identifier(Default::default());
+        open_file.set_modal(false);
+        open_file.set_accept_label("_Pick");
+        let mut save_file = SaveFileRequest::default();
+        save_file.set_identifier(Default::default());
+        save_file.set_modal(false);
+        save_file.set_accept_label("_Pick");
+        let mut save_files = SaveFilesRequest::default();
+        save_files.set_identifier(Default::default());
+        save_files.set_modal(false);
+        save_files.set_accept_label("_Pick");
+
+        let expected = (false, "_Pick".to_owned());
+        let encoded = to_bytes(ctxt, &open_file.request.options).unwrap();
+        assert_eq!(shared(&encoded), expected);
+        let encoded = to_bytes(ctxt, &save_file.request.options).unwrap();
+        assert_eq!(shared(&encoded), expected);
+        let encoded = to_bytes(ctxt, &save_files.request.options).unwrap();
+        assert_eq!(shared(&encoded), expected);
+    }
+
+    #[test]
+    fn save_no_files() {
+        let result = zbus::block_on(SaveFilesRequest::default().files(&[""; 0]).build());
+        assert!(matches!(result, Err(crate::Error::InvalidArgument(_))));
+    }
+
+    #[test]
+    fn parent_folder() {
+        let files = selected_files(&[
+            "file:///home/user/project/a.txt",
+            "file:///home/user/project/b%20c.txt",
+        ]);
+        assert_eq!(
+            files.parent_folder(),
+            Some(std::path::PathBuf::from("/home/user/project"))
+        );
+
+        let files = selected_files(&[
+            "file:///home/user/project/a.txt",
+            "file:///home/user/other/b.txt",
+        ]);
+        assert_eq!(files.parent_folder(), None);
+
+        let files = selected_files(&[
+            "file:///home/user/project/a.txt",
+            "https://example.org/project/b.txt",
+        ]);
+        assert_eq!(files.parent_folder(), None);
+
+        assert_eq!(selected_files(&[]).parent_folder(), None);
+    }
+
+    #[test]
+    fn filter_with_extensions() {
+        let filter = FileFilter::with_extensions("Images", &["png", "jpg", ".jpeg"]);
+        assert_eq!(filter.0, "Images");
+        let patterns = filter
+            .1
+            .iter()
+            .map(|(kind, pattern)| {
+                assert!(matches!(kind, FilterType::GlobPattern));
+                pattern.as_str()
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(patterns, ["*.png", "*.jpg", "*.jpeg"]);
+
+        let filter = FileFilter::with_mime_types("Text", &["text/plain"]);
+        assert!(matches!(
+            filter.1.as_slice(),
+            [(FilterType::MimeType, mime_type)] if mime_type == "text/plain"
+        ));
+    }
+
+    #[test]
+    fn selected_files_json_round_trip() {
+        let mut files = selected_files(&["file:///home/user/a.txt", "file:///tmp/b%20c.txt"]);
+        files.choices = Some(vec![("encoding".to_owned(), "utf8".to_owned())]);
+
+        let json = serde_json::to_string(&files).unwrap();
+        assert_eq!(
+            json,
+            r#"{"uris":["file:///home/user/a.txt","file:///tmp/b%20c.txt"],"choices":[["encoding","utf8"]]}"#
+        );
+
+        let restored: SelectedFiles = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored.uris(), files.uris());
+        assert_eq!(restored.choices(), files.choices());
+        assert_eq!(restored.writable(), None);
+    }
+
+    #[test]
+    fn write_all() {
+        use futures_util::StreamExt;
+
+        let dir = std::env::temp_dir().join(format!("ashpd-write-all-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let first = dir.join("a.txt");
+        let second = dir.join("b.txt");
+        let files = SelectedFiles {
+            uris: vec![
+                url::Url::from_file_path(&first).unwrap(),
+                url::Url::from_file_path(&second).unwrap(),
+            ],
+            choices: None,
+            writable: None,
+            current_filter: None,
+            document_ids: Vec::new(),
+            directory: false,
+            extra: HashMap::new(),
+        };
+
+        let progress = zbus::block_on(files.write_all(["first", "second"]).collect::<Vec<_>>());
+        let progress = progress
+            .into_iter()
+            .map(|p| p.unwrap())
+            .map(|p| (p.index(), p.total(), p.path().to_owned()))
+            .collect::<Vec<_>>();
+        assert_eq!(progress, [(0, 2, first.clone()), (1, 2, second.clone())]);
+        assert_eq!(std::fs::read_to_string(&first).unwrap(), "first");
+        assert_eq!(std::fs::read_to_string(&second).unwrap(), "second");
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn directories() {
+        let mut files = selected_files(&["file:///home/user/Music/", "https://example.com/"]);
+        assert!(files.directories().is_empty());
+
+        files.directory = true;
+        assert!(files.is_directory());
+        assert_eq!(
+            files.directories(),
+            [std::path::PathBuf::from("/home/user/Music")]
+        );
+    }
+
+    #[test]
+    fn persisted_document_flags() {
+        let mut files = selected_files(&["file:///home/user/a.txt"]);
+        assert_eq!(
+            document_flags(&files),
+            DocumentFlags::ReuseExisting | DocumentFlags::Persistent
+        );
+
+        files.directory = true;
+        assert_eq!(
+            document_flags(&files),
+            DocumentFlags::ReuseExisting
+                | DocumentFlags::Persistent
+                | DocumentFlags::ExportDirectory
+        );
+    }
+
+    #[test]
+    fn create_files() {
+        use std::io::Write;
+
+        let dir = std::env::temp_dir().join(format!("ashpd-create-files-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("report.txt");
+        std::fs::write(&path, "previous content").unwrap();
+        let files = selected_files(&[url::Url::from_file_path(&path).unwrap().as_str()]);
+
+        let mut created = files.create_files().unwrap();
+        assert_eq!(created.len(), 1);
+        created[0].write_all(b"new").unwrap();
+        drop(created);
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "new");
+
+        let missing = dir.join("missing").join("report.txt");
+        let files = selected_files(&[url::Url::from_file_path(&missing).unwrap().as_str()]);
+        assert!(matches!(files.create_files(), Err(Error::File(path, _)) if path == missing));
+        let files = selected_files(&["https://example.com/report.txt"]);
+        assert!(matches!(files.create_files(), Err(Error::IO(_))));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn expect_extension() {
+        let request = OpenFileRequest::default()
+            .expect_extension(".png")
+            .expect_extension("JPG");
+        assert_eq!(request.expected_extensions, ["png", "jpg"]);
+
+        let files = selected_files(&[
+            "file:///home/user/cat.png",
+            "file:///home/user/dog.JPG",
+            "file:///home/user/My%20Pictures/bird.jpg",
+        ]);
+        assert!(super::check_extensions(&files, &request.expected_extensions).is_ok());
+        assert!(super::check_extensions(&files, &[]).is_ok());
+
+        for uri in ["file:///home/user/notes.txt", "file:///home/user/png"] {
+            let files = selected_files(&["file:///home/user/cat.png", uri]);
+            assert!(matches!(
+                super::check_extensions(&files, &request.expected_extensions),
+                Err(Error::InvalidArgument(_))
+            ));
+        }
+
+        // A non-ASCII extension, percent-encoded in the uri
+        let request = OpenFileRequest::default().expect_extension("tëxt");
+        let resume = url::Url::from_file_path("/home/user/résumé.TËXT").unwrap();
+        assert_eq!(
+            resume.as_str(),
+            "file:///home/user/r%C3%A9sum%C3%A9.T%C3%8BXT"
+        );
+        let files = selected_files(&[resume.as_str()]);
+        assert!(super::check_extensions(&files, &request.expected_extensions).is_ok());
+        let files = selected_files(&["file:///home/user/r%C3%A9sum%C3%A9.txt"]);
+        assert!(super::check_extensions(&files, &request.expected_extensions).is_err());
+    }
+
+    #[test]
+    fn mime_types() {
+        let dir = std::env::temp_dir().join(format!("ashpd-mime-types-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        // A PNG named as a text file, the content wins
+        let png = dir.join("image.txt");
+        std::fs::write(&png, b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR").unwrap();
+        let notes = dir.join("notes.MD");
+        std::fs::write(&notes, "# Notes").unwrap();
+        let unknown = dir.join("data.bin");
+        std::fs::write(&unknown, [0, 1, 2]).unwrap();
+
+        let uri = |path: &std::path::Path| url::Url::from_file_path(path).unwrap().to_string();
+        let files = selected_files(&[
+            &uri(&png),
+            &uri(&notes),
+            &uri(&unknown),
+            &uri(&dir),
+            "https://example.com/image.png",
+        ]);
+        assert_eq!(
+            files.mime_types().unwrap(),
+            [
+                Some("image/png".to_owned()),
+                Some("text/markdown".to_owned()),
+                None,
+                Some("inode/directory".to_owned()),
+                None,
+            ]
+        );
+
+        let missing = dir.join("missing.png");
+        let files = selected_files(&[&uri(&missing)]);
+        assert!(matches!(files.mime_types(), Err(Error::File(path, _)) if path == missing));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn filter_from_mime() {
+        let filter = FileFilter::from_mime("image/png");
+        assert_eq!(filter.0, "PNG image");
+        assert_eq!(
+            filter.1,
+            [
+                (FilterType::MimeType, "image/png".to_owned()),
+                (FilterType::GlobPattern, "*.png".to_owned()),
+            ]
+        );
+
+        let filter = FileFilter::from_mime("image/jpeg");
+        assert_eq!(filter.0, "JPEG image");
+        assert_eq!(
+            filter.1,
+            [
+                (FilterType::MimeType, "image/jpeg".to_owned()),
+                (FilterType::GlobPattern, "*.jpg".to_owned()),
+                (FilterType::GlobPattern, "*.jpeg".to_owned()),
+            ]
+        );
+
+        let filter = FileFilter::from_mime("application/x-ashpd");
+        assert_eq!(filter.0, "application/x-ashpd");
+        assert_eq!(
+            filter.1,
+            [(FilterType::MimeType, "application/x-ashpd".to_owned())]
+        );
+    }
+
+    #[test]
+    fn parse_mime() {
+        use super::Mime;
+
+        let mime = "image/png".parse::<Mime>().unwrap();
+        assert_eq!(mime.as_str(), "image/png");
+        assert_eq!(
+            "application/vnd.oasis.opendocument.text"
+                .parse::<Mime>()
+                .unwrap()
+                .as_str(),
+            "application/vnd.oasis.opendocument.text"
+        );
+        assert_eq!(
+            Mime::try_from("Image/SVG+XML").unwrap().as_str(),
+            "image/svg+xml"
+        );
+        assert_eq!(
+            FileFilter::new("Images").mime(mime),
+            FileFilter::new("Images").mimetype("image/png")
+        );
+
+        assert_eq!("image/*".parse::<Mime>().unwrap().as_str(), "image/*");
+        assert_eq!("*/*".parse::<Mime>().unwrap().as_str(), "*/*");
+        assert!("*/png".parse::<Mime>().is_err());
+
+        for invalid in [
+            "img/png",
+            "image",
+            "image/",
+            "/png",
+            "image/png/x",
+            "image/ png",
+            "",
+        ] {
+            assert!(
+                matches!(invalid.parse::<Mime>(), Err(Error::ParseError(_))),
+                "{} should be invalid",
+                invalid
+            );
+        }
+    }
+
+    #[test]
+    fn filters_and_choices_set() {
+        use std::collections::HashSet;
+
+        use super::Choice;
+
+        let mut filters = HashSet::new();
+        assert!(filters.insert(
+            FileFilter::new("Images")
+                .glob("*.png")
+                .mimetype("image/jpeg")
+        ));
+        assert!(!filters.insert(
+            FileFilter::new("Images")
+                .glob("*.png")
+                .mimetype("image/jpeg")
+        ));
+        // The order of the patterns is part of the filter
+        assert!(filters.insert(
+            FileFilter::new("Images")
+                .mimetype("image/jpeg")
+                .glob("*.png")
+        ));
+        assert!(filters.insert(FileFilter::new("Images").mimetype("*.png")));
+        assert_eq!(filters.len(), 3);
+
+        let mut choices = HashSet::new();
+        assert!(choices.insert(Choice::boolean("encrypt", "Encrypt", false)));
+        assert!(!choices.insert(Choice::boolean("encrypt", "Encrypt", false)));
+        assert!(choices.insert(Choice::boolean("encrypt", "Encrypt", true)));
+        assert_eq!(choices.len(), 2);
+    }
+
+    #[test]
+    fn handle_token() {
+        let token = crate::desktop::HandleToken::try_from("my_app_open_42").unwrap();
+        let request = OpenFileRequest::default().handle_token(token);
+
+        let ctxt = Context::<LE>::new_dbus(0);
+        let encoded = to_bytes(ctxt, &request.request.options).unwrap();
+        let decoded: HashMap<String, OwnedValue> = from_slice(&encoded, ctxt).unwrap();
+        assert_eq!(
+            String::try_from(decoded["handle_token"].clone()).unwrap(),
+            "my_app_open_42"
+        );
+        assert_eq!(
+            crate::request_path(":1.42", "my_app_open_42")
+                .unwrap()
+                .as_str(),
+            "/org/freedesktop/portal/desktop/request/1_42/my_app_open_42"
+        );
+    }
+
+    #[test]
+    fn clear_current_filter() {
+        let ctxt = Context::<LE>::new_dbus(0);
+        let text = FileFilter::new("Text").mimetype("text/plain");
+
+        let mut request = OpenFileRequest::default()
+            .filter(text.clone())
+            .current_filter(text.clone());
+        let encoded = to_bytes(ctxt, &request.request.options).unwrap();
+        let decoded: HashMap<String, OwnedValue> = from_slice(&encoded, ctxt).unwrap();
+        assert!(decoded.contains_key("current_filter"));
+
+        request.set_current_filter(None);
+        let encoded = to_bytes(ctxt, &request.request.options).unwrap();
+        let decoded: HashMap<String, OwnedValue> = from_slice(&encoded, ctxt).unwrap();
+        assert!(!decoded.contains_key("current_filter"));
+        assert!(decoded.contains_key("filters"));
+
+        // It clears the default filter index as well
+        let mut request = OpenFileRequest::default()
+            .filter(text.clone())
+            .default_filter_index(0)
+            .current_filter(None);
+        request.resolve_default_filter().unwrap();
+        assert_eq!(request.request.options.current_filter, None);
+
+        let request = SaveFileRequest::default()
+            .current_filter(text)
+            .current_filter(None);
+        let encoded = to_bytes(ctxt, &request.request.options).unwrap();
+        let decoded: HashMap<String, OwnedValue> = from_slice(&encoded, ctxt).unwrap();
+        assert!(!decoded.contains_key("current_filter"));
+    }
+
+    #[test]
+    fn default_filter_index() {
+        let text = FileFilter::new("Text").mimetype("text/plain");
+        let images = FileFilter::new("Images").mimetype("image/*");
+        let mut request = OpenFileRequest::default()
+            .filter(text.clone())
+            .filter(images.clone())
+            .default_filter_index(1);
+        request.resolve_default_filter().unwrap();
+
+        let ctxt = Context::<LE>::new_dbus(0);
+        let encoded = to_bytes(ctxt, &request.request.options).unwrap();
+        let decoded: HashMap<String, OwnedValue> = from_slice(&encoded, ctxt).unwrap();
+        let filters =
+            Vec::<(String, Vec<(u32, String)>)>::try_from(decoded["filters"].clone()).unwrap();
+        assert_eq!(filters[0].0, "Text");
+        assert_eq!(filters[1].0, "Images");
+        let current_filter =
+            <(String, Vec<(u32, String)>)>::try_from(decoded["current_filter"].clone()).unwrap();
+        assert_eq!(current_filter, filters[1]);
+
+        // The last one set wins
+        let mut request = request.current_filter(text.clone());
+        request.resolve_default_filter().unwrap();
+        assert_eq!(request.request.options.current_filter, Some(text));
+
+        let mut request = OpenFileRequest::default()
+            .filter(images)
+            .default_filter_index(1);
+        assert!(matches!(
+            request.resolve_default_filter(),
+            Err(Error::InvalidArgument(_))
+        ));
+    }
+
+    #[test]
+    fn current_folder_fd() {
+        let dir = std::env::temp_dir().canonicalize().unwrap();
+        let fd = std::fs::File::open(&dir).unwrap();
+
+        let mut expected = dir.as_os_str().as_bytes().to_vec();
+        expected.push(0);
+        let request = SaveFilesRequest::default().current_folder_fd(&fd);
+        assert_eq!(
+            request.request.options.current_folder,
+            Some(expected.clone())
+        );
+        let request = SaveFileRequest::default().current_folder_fd(&fd);
+        assert_eq!(request.request.options.current_folder, Some(expected));
+    }
+
+    #[test]
+    fn save_file_current_filter() {
+        let ctxt = Context::<LE>::new_dbus(0);
+        let text = FileFilter::with_extensions("Text", &["txt"]);
+        let markdown = FileFilter::with_mime_types("Markdown", &["text/markdown"]);
+
+        let request = SaveFileRequest::default()
+            .filter(text.clone())
+            .filter(markdown.clone())
+            .current_filter(text);
+        let encoded = to_bytes(ctxt, &request.request.options).unwrap();
+        let decoded: HashMap<String, OwnedValue> = from_slice(&encoded, ctxt).unwrap();
+        assert!(decoded.contains_key("current_filter"));
+
+        // What the portal replies with once the user picked the other filter
+        #[derive(SerializeDict, Type)]
+        #[zvariant(signature = "dict")]
+        struct MockResponse {
+            uris: Vec<&'static str>,
+            current_filter: Option<FileFilter>,
+        }
+
+        let response = MockResponse {
+            uris: vec!["file:///home/user/notes.md"],
+            current_filter: Some(markdown.clone()),
+        };
+        let encoded = to_bytes(ctxt, &response).unwrap();
+        let files: SelectedFiles = from_slice(&encoded, ctxt).unwrap();
+        assert_eq!(files.current_filter(), Some(&markdown));
+        assert_eq!(files.uris()[0].path(), "/home/user/notes.md");
+
+        // Older backends don't send it
+        let response = MockResponse {
+            uris: vec!["file:///home/user/notes.md"],
+            current_filter: None,
+        };
+        let encoded = to_bytes(ctxt, &response).unwrap();
+        let files: SelectedFiles = from_slice(&encoded, ctxt).unwrap();
+        assert_eq!(files.current_filter(), None);
+    }
+
+    #[test]
+    fn remember_folder() {
+        let dir = std::env::temp_dir().join(format!("ashpd-remember-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let state_file = dir.join("last-folder");
+
+        zbus::block_on(async {
+            // Nothing stored yet
+            let mut request = SaveFileRequest::default().remember_folder(&state_file);
+            request.restore_folder().await;
+            assert_eq!(request.request.options.current_folder, None);
+
+            super::store_folder(
+                &state_file,
+                &selected_files(&["file:///home/user/Documents/report.txt"]),
+            )
+            .await;
+            assert_eq!(
+                std::fs::read_to_string(&state_file).unwrap(),
+                "/home/user/Documents"
+            );
+
+            let mut request = SaveFileRequest::default().remember_folder(&state_file);
+            request.restore_folder().await;
+            assert_eq!(
+                request.request.options.current_folder.as_deref(),
+                Some(&b"/home/user/Documents\0"[..])
+            );
+
+            // An explicit folder takes precedence
+            let mut request = SaveFileRequest::default()
+                .current_folder("/tmp")
+                .remember_folder(&state_file);
+            request.restore_folder().await;
+            assert_eq!(
+                request.request.options.current_folder.as_deref(),
+                Some(&b"/tmp\0"[..])
+            );
+        });
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    // Sends the `modal` option of the requests
+    #[cfg(not(feature = "tokio"))]
+    struct MockFileChooser(futures_channel::mpsc::UnboundedSender<Option<bool>>);
+
+    #[cfg(not(feature = "tokio"))]
+    #[zbus::dbus_interface(name = "org.freedesktop.portal.FileChooser")]
+    impl MockFileChooser {
+        // Picks a file named after the dialog title
+        async fn open_file(
+            &self,
+            #[zbus(connection)] connection: &zbus::Connection,
+            _parent_window: &str,
+            title: &str,
+            options: HashMap<String, OwnedValue>,
+        ) -> zbus::fdo::Result<zbus::zvariant::OwnedObjectPath> {
+            let modal = options
+                .get("modal")
+                .map(|modal| bool::try_from(modal).unwrap());
+            self.0.unbounded_send(modal).unwrap();
+            let handle_token = <&str>::try_from(&options["handle_token"]).unwrap();
+            let path = crate::request_path(":1.42", handle_token).unwrap();
+            let uri = format!("file:///tmp/{}.txt", title);
+            connection
+                .emit_signal(
+                    None::<zbus::names::BusName<'_>>,
+                    path.as_str(),
+                    "org.freedesktop.portal.Request",
+                    "Response",
+                    &(0u32, HashMap::from([("uris", Value::from(vec![uri]))])),
+                )
+                .await?;
+            Ok(path)
+        }
+    }
+
+    #[cfg(not(feature = "tokio"))]
+    async fn mock_connection() -> (
+        zbus::Connection,
+        zbus::Connection,
+        futures_channel::mpsc::UnboundedReceiver<Option<bool>>,
+    ) {
+        let (sender, modal) = futures_channel::mpsc::unbounded();
+        let guid = zbus::Guid::generate();
+        let (server, client) = std::os::unix::net::UnixStream::pair().unwrap();
+        let (server, client) = futures_util::try_join!(
+            zbus::ConnectionBuilder::unix_stream(server)
+                .server(&guid)
+                .p2p()
+                .serve_at(crate::desktop::PATH, MockFileChooser(sender))
+                .unwrap()
+                .build(),
+            zbus::ConnectionBuilder::unix_stream(client)
+                .p2p()
+                .unique_name(":1.42")
+                .unwrap()
+                .build(),
+        )
+        .unwrap();
+        (server, client, modal)
+    }
+
+    #[cfg(not(feature = "tokio"))]
+    #[test]
+    fn open_file_through_mock() {
+        zbus::block_on(async {
+            let (_server, client, _modal) = mock_connection().await;
+            let mut request = OpenFileRequest::default()
+                .identifier(crate::WindowIdentifier::try_from("x11:0x400").unwrap())
+                .title("report");
+            request.request.connection = Some(client);
+            let files = request.build().await.unwrap();
+            assert_eq!(
+                files
+                    .uris()
+                    .iter()
+                    .map(|uri| uri.as_str())
+                    .collect::<Vec<_>>(),
+                ["file:///tmp/report.txt"]
+            );
+        });
+    }
+
+    #[cfg(not(feature = "tokio"))]
+    #[test]
+    fn modal_through_mock() {
+        zbus::block_on(async {
+            let parent = || crate::WindowIdentifier::try_from("x11:0x400").unwrap();
+
+            for (mut request, expected) in [
+                (OpenFileRequest::default().identifier(parent()), Some(true)),
+                (
+                    OpenFileRequest::default().identifier(parent()).modal(false),
+                    Some(false),
+                ),
+                (
+                    OpenFileRequest::default().identifier(parent()).modal(None),
+                    None,
+                ),
+            ] {
+                let (_server, client, mut modal) = mock_connection().await;
+                request.request.connection = Some(client);
+                request.build().await.unwrap();
+                assert_eq!(modal.try_recv().unwrap(), expected);
+            }
+        });
+    }
+
+    #[test]
+    fn extra_results() {
+        let ctxt = Context::<LE>::new_dbus(0);
+        let filter = FileFilter::new("Text").mimetype("text/plain");
+        let mut response = HashMap::new();
+        response.insert("uris", Value::from(vec!["file:///home/user/a.txt"]));
+        response.insert("writable", Value::from(true));
+        response.insert(
+            "current_filter",
+            Value::from(("Text", vec![(1u32, "text/plain")])),
+        );
+        response.insert("x-backend", Value::from("gnome"));
+        let encoded = to_bytes(ctxt, &response).unwrap();
+        let files: SelectedFiles = from_slice(&encoded, ctxt).unwrap();
+
+        assert_eq!(files.uris()[0].path(), "/home/user/a.txt");
+        assert_eq!(files.writable(), Some(true));
+        assert_eq!(files.current_filter(), Some(&filter));
+        assert_eq!(files.extra().len(), 1);
+        assert_eq!(
+            String::try_from(files.extra()["x-backend"].clone()).unwrap(),
+            "gnome"
+        );
+
+        response.insert("uris", Value::from(true));
+        let encoded = to_bytes(ctxt, &response).unwrap();
+        assert!(from_slice::<_, SelectedFiles>(&encoded, ctxt).is_err());
     }
 }
@@ -1,15 +1,16 @@
 //! # Examples
 //!
 //! ```rust,no_run
-//! use ashpd::desktop::game_mode::GameMode;
+//! use ashpd::desktop::{game_mode::GameMode, Pid};
 //!
 //! async fn run() -> ashpd::Result<()> {
 //!     let proxy = GameMode::new().await?;
+//!     let pid = Pid::current();
 //!
-//!     println!("{:#?}", proxy.register(246612).await?);
-//!     println!("{:#?}", proxy.query_status(246612).await?);
-//!     println!("{:#?}", proxy.unregister(246612).await?);
-//!     println!("{:#?}", proxy.query_status(246612).await?);
+//!     println!("{:#?}", proxy.register(pid).await?);
+//!     println!("{:#?}", proxy.query_status(pid).await?);
+//!     println!("{:#?}", proxy.unregister(pid).await?);
+//!     println!("{:#?}", proxy.query_status(pid).await?);
 //!
 //!     Ok(())
 //! }
@@ -20,15 +21,17 @@ use std::{fmt::Debug, os::unix::io::AsRawFd};
 use serde_repr::Deserialize_repr;
 use zbus::zvariant::{Fd, Type};
 
-use super::{DESTINATION, PATH};
+use super::{Pid, DESTINATION, PATH};
 use crate::{
-    error::PortalError,
     helpers::{call_method, session_connection},
-    Error,
+    Error, Portal,
 };
 
 #[derive(Deserialize_repr, PartialEq, Eq, Debug, Type)]
 /// The status of the game mode.
+///
+/// A query failing inside GameMode is reported as
+/// [`Error::PortalOperationFailed`] instead.
 #[repr(i32)]
 pub enum Status {
     /// GameMode is inactive.
@@ -37,8 +40,20 @@ pub enum Status {
     Active = 1,
     /// GameMode is active and `pid` is registered.
     Registered = 2,
-    /// The query failed inside GameMode.
-    Rejected = -1,
+}
+
+impl Status {
+    /// Maps the status returned by the `QueryStatus` methods, `-1` meaning
+    /// the query failed.
+    fn from_code(code: i32) -> Result<Self, Error> {
+        match code {
+            0 => Ok(Self::Inactive),
+            1 => Ok(Self::Active),
+            2 => Ok(Self::Registered),
+            -1 => Err(Error::PortalOperationFailed(Portal::GameMode)),
+            _ => Err(Error::ParseError("Invalid GameMode status")),
+        }
+    }
 }
 
 #[derive(Deserialize_repr, PartialEq, Eq, Debug, Type)]
@@ -51,6 +66,15 @@ enum RegisterStatus {
     Rejected = -1,
 }
 
+impl RegisterStatus {
+    fn into_result(self) -> Result<(), Error> {
+        match self {
+            Self::Success => Ok(()),
+            Self::Rejected => Err(Error::PortalOperationFailed(Portal::GameMode)),
+        }
+    }
+}
+
 /// The interface lets sandboxed applications access GameMode from within the
 /// sandbox.
 ///
@@ -84,7 +108,7 @@ impl<'a> GameMode<'a> {
     pub async fn new() -> Result<GameMode<'a>, Error> {
         let connection = session_connection().await?;
         let proxy = zbus::ProxyBuilder::new_bare(&connection)
-            .interface("org.freedesktop.portal.GameMode")?
+            .interface(Portal::GameMode.as_interface_name())?
             .path(PATH)?
             .destination(DESTINATION)?
             .build()
@@ -109,8 +133,10 @@ impl<'a> GameMode<'a> {
     ///
     /// See also [`QueryStatus`](https://flatpak.github.io/xdg-desktop-portal/index.html#gdbus-method-org-freedesktop-portal-GameMode.QueryStatus).
     #[doc(alias = "QueryStatus")]
-    pub async fn query_status(&self, pid: u32) -> Result<Status, Error> {
-        call_method(self.inner(), "QueryStatus", &(pid)).await
+    pub async fn query_status(&self, pid: impl Into<Pid>) -> Result<Status, Error> {
+        call_method(self.inner(), "QueryStatus", &(pid.into()))
+            .await
+            .and_then(Status::from_code)
     }
 
     /// Query the GameMode status for a process.
@@ -139,6 +165,7 @@ impl<'a> GameMode<'a> {
             ),
         )
         .await
+        .and_then(Status::from_code)
     }
 
     /// Query the GameMode status for a process.
@@ -152,8 +179,18 @@ impl<'a> GameMode<'a> {
     ///
     /// See also [`QueryStatusByPid`](https://flatpak.github.io/xdg-desktop-portal/index.html#gdbus-method-org-freedesktop-portal-GameMode.QueryStatusByPid).
     #[doc(alias = "QueryStatusByPid")]
-    pub async fn query_status_by_pid(&self, target: u32, requester: u32) -> Result<Status, Error> {
-        call_method(self.inner(), "QueryStatusByPid", &(target, requester)).await
+    pub async fn query_status_by_pid(
+        &self,
+        target: impl Into<Pid>,
+        requester: impl Into<Pid>,
+    ) -> Result<Status, Error> {
+        call_method(
+            self.inner(),
+            "QueryStatusByPid",
+            &(target.into(), requester.into()),
+        )
+        .await
+        .and_then(Status::from_code)
     }
 
     /// Register a game with GameMode and thus request GameMode to be activated.
@@ -170,12 +207,10 @@ impl<'a> GameMode<'a> {
     ///
     /// See also [`RegisterGame`](https://flatpak.github.io/xdg-desktop-portal/index.html#gdbus-method-org-freedesktop-portal-GameMode.RegisterGame).
     #[doc(alias = "RegisterGame")]
-    pub async fn register(&self, pid: u32) -> Result<(), Error> {
-        let status = call_method(self.inner(), "RegisterGame", &(pid)).await?;
-        match status {
-            RegisterStatus::Success => Ok(()),
-            RegisterStatus::Rejected => Err(Error::Portal(PortalError::Failed)),
-        }
+    pub async fn register(&self, pid: impl Into<Pid>) -> Result<(), Error> {
+        let status: RegisterStatus =
+            call_method(self.inner(), "RegisterGame", &(pid.into())).await?;
+        status.into_result()
     }
 
     /// Register a game with GameMode.
@@ -195,7 +230,7 @@ impl<'a> GameMode<'a> {
         target: &impl AsRawFd,
         requester: &impl AsRawFd,
     ) -> Result<(), Error> {
-        let status = call_method(
+        let status: RegisterStatus = call_method(
             self.inner(),
             "RegisterGameByPIDFd",
             &(
@@ -204,10 +239,7 @@ impl<'a> GameMode<'a> {
             ),
         )
         .await?;
-        match status {
-            RegisterStatus::Success => Ok(()),
-            RegisterStatus::Rejected => Err(Error::Portal(PortalError::Failed)),
-        }
+        status.into_result()
     }
 
     /// Register a game with GameMode.
@@ -221,12 +253,18 @@ impl<'a> GameMode<'a> {
     ///
     /// See also [`RegisterGameByPid`](https://flatpak.github.io/xdg-desktop-portal/index.html#gdbus-method-org-freedesktop-portal-GameMode.RegisterGameByPid).
     #[doc(alias = "RegisterGameByPid")]
-    pub async fn register_by_pid(&self, target: u32, requester: u32) -> Result<(), Error> {
-        let status = call_method(self.inner(), "RegisterGameByPid", &(target, requester)).await?;
-        match status {
-            RegisterStatus::Success => Ok(()),
-            RegisterStatus::Rejected => Err(Error::Portal(PortalError::Failed)),
-        }
+    pub async fn register_by_pid(
+        &self,
+        target: impl Into<Pid>,
+        requester: impl Into<Pid>,
+    ) -> Result<(), Error> {
+        let status: RegisterStatus = call_method(
+            self.inner(),
+            "RegisterGameByPid",
+            &(target.into(), requester.into()),
+        )
+        .await?;
+        status.into_result()
     }
 
     /// Un-register a game from GameMode.
@@ -243,12 +281,10 @@ impl<'a> GameMode<'a> {
     ///
     /// See also [`UnregisterGame`](https://flatpak.github.io/xdg-desktop-portal/index.html#gdbus-method-org-freedesktop-portal-GameMode.UnregisterGame).
     #[doc(alias = "UnregisterGame")]
-    pub async fn unregister(&self, pid: u32) -> Result<(), Error> {
-        let status = call_method(self.inner(), "UnregisterGame", &(pid)).await?;
-        match status {
-            RegisterStatus::Success => Ok(()),
-            RegisterStatus::Rejected => Err(Error::Portal(PortalError::Failed)),
-        }
+    pub async fn unregister(&self, pid: impl Into<Pid>) -> Result<(), Error> {
+        let status: RegisterStatus =
+            call_method(self.inner(), "UnregisterGame", &(pid.into())).await?;
+        status.into_result()
     }
 
     /// Un-register a game from GameMode.
@@ -268,7 +304,7 @@ impl<'a> GameMode<'a> {
         target: &impl AsRawFd,
         requester: &impl AsRawFd,
     ) -> Result<(), Error> {
-        let status = call_method(
+        let status: RegisterStatus = call_method(
             self.inner(),
             "UnregisterGameByPIDFd",
             &(
@@ -277,10 +313,7 @@ impl<'a> GameMode<'a> {
             ),
         )
         .await?;
-        match status {
-            RegisterStatus::Success => Ok(()),
-            RegisterStatus::Rejected => Err(Error::Portal(PortalError::Failed)),
-        }
+        status.into_result()
     }
 
     /// Un-register a game from GameMode.
@@ -295,11 +328,45 @@ impl<'a> GameMode<'a> {
     ///
     /// See also [`UnregisterGameByPid`](https://flatpak.github.io/xdg-desktop-portal/index.html#gdbus-method-org-freedesktop-portal-GameMode.UnregisterGameByPid).
     #[doc(alias = "UnregisterGameByPid")]
-    pub async fn unregister_by_pid(&self, target: u32, requester: u32) -> Result<(), Error> {
-        let status = call_method(self.inner(), "UnregisterGameByPid", &(target, requester)).await?;
-        match status {
-            RegisterStatus::Success => Ok(()),
-            RegisterStatus::Rejected => Err(Error::Portal(PortalError::Failed)),
-        }
+    pub async fn unregister_by_pid(
+        &self,
+        target: impl Into<Pid>,
+        requester: impl Into<Pid>,
+    ) -> Result<(), Error> {
+        let status: RegisterStatus = call_method(
+            self.inner(),
+            "UnregisterGameByPid",
+            &(target.into(), requester.into()),
+        )
+        .await?;
+        status.into_result()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{RegisterStatus, Status};
+    use crate::{Error, Portal};
+
+    #[test]
+    fn status_from_code() {
+        assert_eq!(Status::from_code(0).unwrap(), Status::Inactive);
+        assert_eq!(Status::from_code(1).unwrap(), Status::Active);
+        assert_eq!(Status::from_code(2).unwrap(), Status::Registered);
+        assert!(matches!(
+            Status::from_code(-1),
+            Err(Error::PortalOperationFailed(Portal::GameMode))
+        ));
+        assert!(matches!(Status::from_code(3), Err(Error::ParseError(_))));
+    }
+
+    #[test]
+    fn register_status() {
+        let status = |code: i32| serde_json::from_str::<RegisterStatus>(&code.to_string()).unwrap();
+        assert!(status(0).into_result().is_ok());
+        assert!(matches!(
+            status(-1).into_result(),
+            Err(Error::PortalOperationFailed(Portal::GameMode))
+        ));
     }
 }
@@ -13,8 +13,9 @@ use zbus::{names::OwnedMemberName, zvariant::Type};
 /// where sender is the caller's unique name and token is the [`HandleToken`].
 ///
 /// A valid object path element must only contain the ASCII characters
-/// `[A-Z][a-z][0-9]_`
-#[derive(Serialize, Deserialize, Type)]
+/// `[A-Z][a-z][0-9]_`. As a D-Bus member name, the token can't be empty nor
+/// start with a digit either.
+#[derive(Clone, Serialize, Deserialize, Type)]
 pub struct HandleToken(OwnedMemberName);
 
 impl Display for HandleToken {
@@ -44,11 +45,16 @@ impl Default for HandleToken {
 }
 
 #[derive(Debug)]
-pub struct HandleInvalidCharacter(char);
+/// The error of an invalid [`HandleToken`], with the invalid character if
+/// any, `None` for an empty token.
+pub struct HandleInvalidCharacter(Option<char>);
 
 impl std::fmt::Display for HandleInvalidCharacter {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        f.write_fmt(format_args!("Invalid Character {}", self.0))
+        match self.0 {
+            Some(char) => f.write_fmt(format_args!("Invalid Character {}", char)),
+            None => f.write_str("Empty handle token"),
+        }
     }
 }
 
@@ -57,9 +63,14 @@ impl std::error::Error for HandleInvalidCharacter {}
 impl TryFrom<&str> for HandleToken {
     type Error = HandleInvalidCharacter;
     fn try_from(value: &str) -> Result<Self, Self::Error> {
+        match value.chars().next() {
+            None => return Err(HandleInvalidCharacter(None)),
+            Some(char) if char.is_ascii_digit() => return Err(HandleInvalidCharacter(Some(char))),
+            Some(_) => (),
+        }
         for char in value.chars() {
             if !char.is_ascii_alphanumeric() && char != '_' {
-                return Err(HandleInvalidCharacter(char));
+                return Err(HandleInvalidCharacter(Some(char)));
             }
         }
         Ok(Self(
@@ -94,6 +105,10 @@ mod test {
 
         assert_eq!(HandleToken::try_from("test_token").is_ok(), true);
 
+        assert!(HandleToken::try_from("").is_err());
+
+        assert!(HandleToken::try_from("1token").is_err());
+
         HandleToken::default(); // ensure we don't panic
     }
 }
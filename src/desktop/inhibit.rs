@@ -37,20 +37,34 @@
 //!     Ok(())
 //! }
 //! ```
+//!
+//! How to keep the screen on while playing a video
+//!
+//! ```rust,no_run
+//! use ashpd::desktop::inhibit;
+//!
+//! async fn run() -> ashpd::Result<()> {
+//!     let inhibition = inhibit::idle("playing a video").await?;
+//!     // Play the video, then release the inhibition by dropping the guard.
+//!     drop(inhibition);
+//!     Ok(())
+//! }
+//! ```
+
+use std::sync::atomic::{AtomicBool, Ordering};
 
 use enumflags2::{bitflags, BitFlags};
-use futures_util::TryFutureExt;
 use serde::Deserialize;
 use serde_repr::{Deserialize_repr, Serialize_repr};
 use zbus::zvariant::{DeserializeDict, OwnedObjectPath, SerializeDict, Type};
 
-use super::{HandleToken, Session, DESTINATION, PATH};
+use super::{request::Request, HandleToken, Session, DESTINATION, PATH};
 use crate::{
     helpers::{
-        call_basic_response_method, call_method, call_request_method, receive_signal,
+        call_basic_response_method, call_method, check_window_kind, close_detached, receive_signal,
         session_connection,
     },
-    Error, WindowIdentifier,
+    Error, Portal, WindowIdentifier,
 };
 
 #[derive(SerializeDict, Type, Debug, Default)]
@@ -93,15 +107,6 @@ pub enum InhibitFlags {
     Idle,
 }
 
-#[derive(Debug, DeserializeDict, Type)]
-/// A response to a [`InhibitProxy::create_monitor`] request.
-#[zvariant(signature = "dict")]
-struct CreateMonitor {
-    // TODO: investigate why this doesn't return an ObjectPath
-    // replace with an ObjectPath once https://github.com/flatpak/xdg-desktop-portal/pull/609's merged
-    session_handle: String,
-}
-
 #[derive(Debug, DeserializeDict, Type)]
 #[zvariant(signature = "dict")]
 struct State {
@@ -143,6 +148,94 @@ pub enum SessionState {
     Ending = 3,
 }
 
+/// An inhibition created with [`InhibitProxy::inhibit_session`], [`idle`] or
+/// [`suspend`].
+///
+/// The inhibition is released when the [`InhibitSession`] is dropped, which is
+/// the intended way of releasing it. Keep the guard around for as long as the
+/// inhibition is needed.
+///
+/// As `Drop` can't wait for the portal, releasing on drop is best-effort. Use
+/// [`InhibitSession::release`] to wait for the inhibition to be released.
+pub struct InhibitSession<'a>(Request<'a>, AtomicBool);
+
+impl<'a> InhibitSession<'a> {
+    /// Releases the inhibition right away, reporting a failure to do so.
+    ///
+    /// Dropping the guard releases it as well.
+    pub async fn release(self) -> Result<(), Error> {
+        self.1.store(true, Ordering::SeqCst);
+        self.0.close().await
+    }
+}
+
+impl<'a> Drop for InhibitSession<'a> {
+    fn drop(&mut self) {
+        if self.1.load(Ordering::SeqCst) {
+            return;
+        }
+        #[cfg(feature = "tracing")]
+        tracing::info!("Releasing the dropped inhibition {}", self.0.inner().path());
+        close_detached(self.0.inner());
+    }
+}
+
+impl<'a> std::fmt::Debug for InhibitSession<'a> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("InhibitSession")
+            .field(&self.0.inner().path().as_str())
+            .finish()
+    }
+}
+
+/// Inhibits the session from going idle, e.g. to keep the screen on while
+/// playing a video, until the returned guard is dropped.
+///
+/// # Arguments
+///
+/// * `reason` - User-visible reason for the inhibition, see
+///   [`InhibitProxy::inhibit`].
+pub async fn idle(reason: &str) -> Result<InhibitSession<'static>, Error> {
+    idle_with(&InhibitProxy::new().await?, reason).await
+}
+
+async fn idle_with(
+    proxy: &InhibitProxy<'_>,
+    reason: &str,
+) -> Result<InhibitSession<'static>, Error> {
+    proxy
+        .inhibit_session(
+            &WindowIdentifier::default(),
+            InhibitFlags::Idle.into(),
+            reason,
+        )
+        .await
+}
+
+/// Inhibits the system from suspending, e.g. while downloading a file, until
+/// the returned guard is dropped.
+///
+/// # Arguments
+///
+/// * `reason` - User-visible reason for the inhibition, see
+///   [`InhibitProxy::inhibit`].
+pub async fn suspend(reason: &str) -> Result<InhibitSession<'static>, Error> {
+    suspend_with(&InhibitProxy::new().await?, reason).await
+}
+
+async fn suspend_with(
+    proxy: &InhibitProxy<'_>,
+    reason: &str,
+) -> Result<InhibitSession<'static>, Error> {
+    proxy
+        .inhibit_session(
+            &WindowIdentifier::default(),
+            InhibitFlags::Suspend.into(),
+            reason,
+        )
+        .await
+}
+
 /// The interface lets sandboxed applications inhibit the user session from
 /// ending, suspending, idling or getting switched away.
 ///
@@ -155,8 +248,12 @@ impl<'a> InhibitProxy<'a> {
     /// Create a new instance of [`InhibitProxy`].
     pub async fn new() -> Result<InhibitProxy<'a>, Error> {
         let connection = session_connection().await?;
-        let proxy = zbus::ProxyBuilder::new_bare(&connection)
-            .interface("org.freedesktop.portal.Inhibit")?
+        Self::with_connection(&connection).await
+    }
+
+    async fn with_connection(connection: &zbus::Connection) -> Result<InhibitProxy<'a>, Error> {
+        let proxy = zbus::ProxyBuilder::new_bare(connection)
+            .interface(Portal::Inhibit.as_interface_name())?
             .path(PATH)?
             .destination(DESTINATION)?
             .build()
@@ -186,15 +283,16 @@ impl<'a> InhibitProxy<'a> {
         &self,
         identifier: &WindowIdentifier,
     ) -> Result<Session<'a>, Error> {
+        check_window_kind(identifier)?;
         let options = CreateMonitorOptions::default();
-        let body = &(&identifier, &options);
-        let (monitor, proxy): (CreateMonitor, Session) = futures_util::try_join!(
-            call_request_method(self.inner(), &options.handle_token, "CreateMonitor", body)
-                .into_future(),
-            Session::from_unique_name(&options.session_handle_token).into_future(),
-        )?;
-        assert_eq!(proxy.inner().path().as_str(), &monitor.session_handle);
-        Ok(proxy)
+        Session::create(
+            self.inner(),
+            "CreateMonitor",
+            &options.handle_token,
+            &options.session_handle_token,
+            &(&identifier, &options),
+        )
+        .await
     }
 
     /// Inhibits a session status changes.
@@ -203,7 +301,9 @@ impl<'a> InhibitProxy<'a> {
     ///
     /// * `identifier` - The application window identifier.
     /// * `flags` - The flags determine what changes are inhibited.
-    /// * `reason` - User-visible reason for the inhibition.
+    /// * `reason` - User-visible reason for the inhibition, shown when the
+    ///   user tries to do what is inhibited. Without one, they are left
+    ///   guessing what prevents it.
     ///
     /// # Specifications
     ///
@@ -216,6 +316,7 @@ impl<'a> InhibitProxy<'a> {
         flags: BitFlags<InhibitFlags>,
         reason: &str,
     ) -> Result<(), Error> {
+        check_window_kind(identifier)?;
         let options = InhibitOptions {
             reason: Some(reason.to_owned()),
             handle_token: Default::default(),
@@ -229,6 +330,54 @@ impl<'a> InhibitProxy<'a> {
         .await
     }
 
+    /// Inhibits a session status changes until the returned [`InhibitSession`]
+    /// is dropped.
+    ///
+    /// Fails with [`ResponseError::Cancelled`](crate::desktop::ResponseError::Cancelled)
+    /// wrapped in [`Error::Response`] if the user denied the inhibition, no
+    /// guard is returned then.
+    ///
+    /// # Arguments
+    ///
+    /// * `identifier` - The application window identifier.
+    /// * `flags` - The flags determine what changes are inhibited.
+    /// * `reason` - User-visible reason for the inhibition, shown when the
+    ///   user tries to do what is inhibited. Without one, they are left
+    ///   guessing what prevents it.
+    ///
+    /// # Specifications
+    ///
+    /// See also [`Inhibit`](https://flatpak.github.io/xdg-desktop-portal/index.html#gdbus-method-org-freedesktop-portal-Inhibit.Inhibit).
+    #[doc(alias = "xdp_portal_session_uninhibit")]
+    pub async fn inhibit_session<'s>(
+        &self,
+        identifier: &WindowIdentifier,
+        flags: BitFlags<InhibitFlags>,
+        reason: &str,
+    ) -> Result<InhibitSession<'s>, Error> {
+        check_window_kind(identifier)?;
+        let options = InhibitOptions {
+            reason: Some(reason.to_owned()),
+            handle_token: Default::default(),
+        };
+        call_basic_response_method(
+            self.inner(),
+            &options.handle_token,
+            "Inhibit",
+            &(&identifier, flags, &options),
+        )
+        .await?;
+        // The request stays around once answered, closing it releases the
+        // inhibition.
+        let request = Request::from_unique_name_with_connection(
+            self.inner().connection(),
+            self.inner().destination().to_owned(),
+            &options.handle_token,
+        )
+        .await?;
+        Ok(InhibitSession(request, AtomicBool::new(false)))
+    }
+
     /// Signal emitted when the session state changes.
     ///
     /// # Specifications
@@ -259,3 +408,118 @@ impl<'a> InhibitProxy<'a> {
         call_method(self.inner(), "QueryEndResponse", &(session)).await
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use byteorder::LE;
+    use zbus::zvariant::{from_slice, to_bytes, EncodingContext as Context, OwnedValue};
+
+    use super::InhibitOptions;
+    #[cfg(not(feature = "tokio"))]
+    use super::{idle_with, suspend_with, InhibitProxy};
+    #[cfg(not(feature = "tokio"))]
+    use crate::{desktop::ResponseError, Error};
+
+    #[cfg(not(feature = "tokio"))]
+    struct MockInhibit {
+        flags: futures_channel::mpsc::UnboundedSender<u32>,
+        code: u32,
+    }
+
+    #[cfg(not(feature = "tokio"))]
+    #[zbus::dbus_interface(name = "org.freedesktop.portal.Inhibit")]
+    impl MockInhibit {
+        // Records the flags and answers with the response code of the mock
+        async fn inhibit(
+            &self,
+            #[zbus(connection)] connection: &zbus::Connection,
+            _window: &str,
+            flags: u32,
+            options: HashMap<String, OwnedValue>,
+        ) -> zbus::fdo::Result<zbus::zvariant::OwnedObjectPath> {
+            self.flags.unbounded_send(flags).unwrap();
+            let handle_token = <&str>::try_from(&options["handle_token"]).unwrap();
+            let path = crate::request_path(":1.42", handle_token).unwrap();
+            connection
+                .emit_signal(
+                    None::<zbus::names::BusName<'_>>,
+                    path.as_str(),
+                    "org.freedesktop.portal.Request",
+                    "Response",
+                    &(self.code, HashMap::<&str, zbus::zvariant::Value<'_>>::new()),
+                )
+                .await?;
+            Ok(path)
+        }
+    }
+
+    #[cfg(not(feature = "tokio"))]
+    async fn mock_proxy(
+        code: u32,
+    ) -> (
+        zbus::Connection,
+        InhibitProxy<'static>,
+        futures_channel::mpsc::UnboundedReceiver<u32>,
+    ) {
+        let (flags, receiver) = futures_channel::mpsc::unbounded();
+        let guid = zbus::Guid::generate();
+        let (server, client) = std::os::unix::net::UnixStream::pair().unwrap();
+        let (server, client) = futures_util::try_join!(
+            zbus::ConnectionBuilder::unix_stream(server)
+                .server(&guid)
+                .p2p()
+                .serve_at(crate::desktop::PATH, MockInhibit { flags, code })
+                .unwrap()
+                .build(),
+            zbus::ConnectionBuilder::unix_stream(client)
+                .p2p()
+                .unique_name(":1.42")
+                .unwrap()
+                .build(),
+        )
+        .unwrap();
+        let proxy = InhibitProxy::with_connection(&client).await.unwrap();
+        (server, proxy, receiver)
+    }
+
+    #[test]
+    fn serialize_reason() {
+        let ctxt = Context::<LE>::new_dbus(0);
+        let options = InhibitOptions {
+            reason: Some("playing a video".to_owned()),
+            handle_token: Default::default(),
+        };
+        let encoded = to_bytes(ctxt, &options).unwrap();
+        let decoded: HashMap<String, OwnedValue> = from_slice(&encoded, ctxt).unwrap();
+        assert_eq!(
+            <&str>::try_from(&decoded["reason"]).unwrap(),
+            "playing a video"
+        );
+    }
+
+    #[cfg(not(feature = "tokio"))]
+    #[test]
+    fn single_flag() {
+        zbus::block_on(async {
+            let (_server, proxy, mut flags) = mock_proxy(0).await;
+            idle_with(&proxy, "playing a video").await.unwrap();
+            assert_eq!(flags.try_recv().unwrap(), 8);
+            suspend_with(&proxy, "downloading a file").await.unwrap();
+            assert_eq!(flags.try_recv().unwrap(), 4);
+        });
+    }
+
+    #[cfg(not(feature = "tokio"))]
+    #[test]
+    fn denied_inhibition() {
+        zbus::block_on(async {
+            let (_server, proxy, _flags) = mock_proxy(1).await;
+            assert!(matches!(
+                idle_with(&proxy, "playing a video").await,
+                Err(Error::Response(ResponseError::Cancelled))
+            ));
+        });
+    }
+}
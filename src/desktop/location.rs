@@ -28,20 +28,41 @@
 //!     Ok(())
 //! }
 //! ```
+//!
+//! Or just retrieve the current location.
+//!
+//! ```rust,no_run
+//! use ashpd::desktop::location::{self, Accuracy};
+//!
+//! async fn run() -> ashpd::Result<()> {
+//!     let location = location::once(Accuracy::City).await?;
+//!     println!("{}, {}", location.latitude(), location.longitude());
+//!
+//!     Ok(())
+//! }
+//! ```
 
-use std::fmt::Debug;
+use std::{fmt::Debug, time::Duration};
 
-use futures_util::TryFutureExt;
+use futures_util::{
+    future::{self, Either},
+    StreamExt,
+};
 use serde::Deserialize;
 use serde_repr::Serialize_repr;
 use zbus::zvariant::{DeserializeDict, OwnedObjectPath, SerializeDict, Type};
 
 use super::{HandleToken, Session, DESTINATION, PATH};
 use crate::{
-    helpers::{call_basic_response_method, call_method, receive_signal, session_connection},
-    Error, WindowIdentifier,
+    helpers::{
+        call_basic_response_method, check_window_kind, receive_signal, session_connection, sleep,
+    },
+    Error, Portal, PortalError, WindowIdentifier,
 };
 
+/// How long [`once`] waits for the location once the session started.
+const ONCE_TIMEOUT: Duration = Duration::from_secs(30);
+
 #[derive(Serialize_repr, PartialEq, Eq, Clone, Copy, Debug, Type)]
 #[doc(alias = "XdpLocationAccuracy")]
 #[repr(u32)]
@@ -203,7 +224,7 @@ impl<'a> LocationProxy<'a> {
     pub async fn new() -> Result<LocationProxy<'a>, Error> {
         let connection = session_connection().await?;
         let proxy = zbus::ProxyBuilder::new_bare(&connection)
-            .interface("org.freedesktop.portal.Location")?
+            .interface(Portal::Location.as_interface_name())?
             .path(PATH)?
             .destination(DESTINATION)?
             .build()
@@ -253,17 +274,13 @@ impl<'a> LocationProxy<'a> {
             accuracy,
             ..Default::default()
         };
-        let (path, proxy) = futures_util::try_join!(
-            call_method::<OwnedObjectPath, CreateSessionOptions>(
-                &self.0,
-                "CreateSession",
-                &(options)
-            )
-            .into_future(),
-            Session::from_unique_name(&options.session_handle_token).into_future(),
-        )?;
-        assert_eq!(proxy.inner().path(), &path.into_inner());
-        Ok(proxy)
+        Session::create_with_reply(
+            &self.0,
+            "CreateSession",
+            &options.session_handle_token,
+            &options,
+        )
+        .await
     }
 
     /// Start the location session.
@@ -285,6 +302,7 @@ impl<'a> LocationProxy<'a> {
         session: &Session<'_>,
         identifier: &WindowIdentifier,
     ) -> Result<(), Error> {
+        check_window_kind(identifier)?;
         let options = SessionStartOptions::default();
         call_basic_response_method(
             &self.0,
@@ -295,3 +313,83 @@ impl<'a> LocationProxy<'a> {
         .await
     }
 }
+
+/// Retrieves the current location with the given `accuracy`.
+///
+/// A location session is started, which might ask the user for the permission
+/// first, and closed as soon as the first location is received. It fails with
+/// [`Error::Timeout`] if no location is received within 30 seconds of the
+/// session being started.
+#[doc(alias = "xdp_portal_location_monitor_start")]
+pub async fn once(accuracy: Accuracy) -> Result<Location, Error> {
+    let proxy = LocationProxy::new().await?;
+    let session = proxy.create_session(None, None, Some(accuracy)).await?;
+    let location = first_location(&proxy, &session).await;
+    // Closing a session that failed to start isn't worth reporting over the
+    // actual failure
+    let closed = session.close().await;
+    let location = location?;
+    closed?;
+    Ok(location)
+}
+
+async fn first_location(
+    proxy: &LocationProxy<'_>,
+    session: &Session<'_>,
+) -> Result<Location, Error> {
+    // Listen before starting the session to not miss the first update
+    let stream = proxy
+        .inner()
+        .receive_signal("LocationUpdated")
+        .await
+        .map_err::<PortalError, _>(From::from)?;
+    proxy.start(session, &WindowIdentifier::default()).await?;
+
+    let session_path = session.inner().path().to_owned();
+    let location = stream
+        .filter_map(|message| future::ready(message.body::<Location>().ok()))
+        .filter(|location| future::ready(*location.0 == session_path))
+        .into_future();
+    let timeout = sleep(ONCE_TIMEOUT);
+    futures_util::pin_mut!(timeout);
+    match future::select(location, timeout).await {
+        Either::Left(((Some(location), _), _)) => Ok(location),
+        Either::Left(((None, _), _)) => Err(Error::NoResponse),
+        Either::Right(_) => Err(Error::Timeout),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use byteorder::LE;
+    use zbus::zvariant::{from_slice, to_bytes, EncodingContext as Context, ObjectPath};
+
+    use super::{Location, LocationInner};
+
+    #[test]
+    fn parse_location_updated() {
+        let ctxt = Context::<LE>::new_dbus(0);
+        let session =
+            ObjectPath::try_from("/org/freedesktop/portal/desktop/session/1_42/t").unwrap();
+        let inner = LocationInner {
+            accuracy: 10.0,
+            altitude: -f64::MAX,
+            speed: -1.0,
+            heading: 90.0,
+            description: String::new(),
+            latitude: 48.85,
+            longitude: 2.35,
+            timestamp: (1_700_000_000, 0),
+        };
+        let encoded = to_bytes(ctxt, &(&session, inner)).unwrap();
+        let location: Location = from_slice(&encoded, ctxt).unwrap();
+        assert_eq!(*location.0, session);
+        assert_eq!(location.latitude(), 48.85);
+        assert_eq!(location.longitude(), 2.35);
+        assert_eq!(location.altitude(), None);
+        assert_eq!(location.speed(), None);
+        assert_eq!(location.heading(), Some(90.0));
+        assert_eq!(location.description(), None);
+        assert_eq!(location.timestamp().as_secs(), 1_700_000_000);
+    }
+}
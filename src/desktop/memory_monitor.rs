@@ -14,7 +14,7 @@
 use super::{DESTINATION, PATH};
 use crate::{
     helpers::{receive_signal, session_connection},
-    Error,
+    Error, Portal,
 };
 
 /// The interface provides information about low system memory to sandboxed
@@ -33,7 +33,7 @@ impl<'a> MemoryMonitor<'a> {
     pub async fn new() -> Result<MemoryMonitor<'a>, Error> {
         let connection = session_connection().await?;
         let proxy = zbus::ProxyBuilder::new_bare(&connection)
-            .interface("org.freedesktop.portal.MemoryMonitor")?
+            .interface(Portal::MemoryMonitor.as_interface_name())?
             .path(PATH)?
             .destination(DESTINATION)?
             .build()
@@ -6,8 +6,12 @@ pub(crate) mod request;
 mod session;
 pub(crate) use self::handle_token::HandleToken;
 pub use self::{request::ResponseError, session::Session};
+mod color;
+pub use color::Color;
 mod icon;
 pub use icon::Icon;
+mod pid;
+pub use pid::{Pid, ThreadId};
 
 pub mod account;
 pub mod background;
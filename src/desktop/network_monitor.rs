@@ -25,7 +25,7 @@ use zbus::zvariant::{DeserializeDict, Type};
 use super::{DESTINATION, PATH};
 use crate::{
     helpers::{call_method, receive_signal, session_connection},
-    Error,
+    Error, Portal,
 };
 
 #[derive(DeserializeDict, Type, Debug)]
@@ -99,7 +99,7 @@ impl<'a> NetworkMonitor<'a> {
     pub async fn new() -> Result<NetworkMonitor<'a>, Error> {
         let connection = session_connection().await?;
         let proxy = zbus::ProxyBuilder::new_bare(&connection)
-            .interface("org.freedesktop.portal.NetworkMonitor")?
+            .interface(Portal::NetworkMonitor.as_interface_name())?
             .path(PATH)?
             .destination(DESTINATION)?
             .build()
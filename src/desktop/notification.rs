@@ -44,15 +44,16 @@
 //! }
 //! ```
 
-use std::{fmt, str::FromStr};
+use std::{fmt, future::ready, os::unix::prelude::AsRawFd, str::FromStr};
 
+use futures_util::{Stream, StreamExt};
 use serde::{self, Deserialize, Serialize};
-use zbus::zvariant::{OwnedValue, SerializeDict, Type};
+use zbus::zvariant::{Fd, OwnedValue, SerializeDict, Type, Value};
 
 use super::{Icon, DESTINATION, PATH};
 use crate::{
     helpers::{call_method, receive_signal, session_connection},
-    Error,
+    Error, Portal, PortalError,
 };
 
 #[derive(Debug, Clone, Serialize, PartialEq, Eq, Type)]
@@ -117,6 +118,40 @@ impl FromStr for Priority {
     }
 }
 
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// The sound to play when showing a notification.
+pub enum Sound {
+    /// The default notification sound of the desktop.
+    Default,
+    /// No sound at all.
+    Silent,
+    /// A sound file, kept open until the notification is sent.
+    File(Fd),
+    /// The content of a sound file.
+    Bytes(Vec<u8>),
+}
+
+impl Sound {
+    /// A sound read from `file`.
+    ///
+    /// **Note** only the file descriptor is stored, the file has to be kept
+    /// open until the notification is sent.
+    pub fn from_file(file: &impl AsRawFd) -> Self {
+        Self::File(Fd::from(file.as_raw_fd()))
+    }
+}
+
+impl From<Sound> for OwnedValue {
+    fn from(sound: Sound) -> Self {
+        match sound {
+            Sound::Default => Value::from("default").into(),
+            Sound::Silent => Value::from("silent").into(),
+            Sound::File(fd) => Value::from(("file", Value::from(fd))).into(),
+            Sound::Bytes(bytes) => Value::from(("bytes", Value::from(bytes))).into(),
+        }
+    }
+}
+
 #[derive(SerializeDict, Type, Debug)]
 /// A notification
 #[zvariant(signature = "dict")]
@@ -138,6 +173,8 @@ pub struct Notification {
     default_action_target: Option<OwnedValue>,
     /// Array of buttons to add to the notification.
     buttons: Option<Vec<Button>>,
+    /// The sound to play, either a [`Sound`] name or a serialized file.
+    sound: Option<OwnedValue>,
 }
 
 impl Notification {
@@ -155,6 +192,7 @@ impl Notification {
             default_action: None,
             default_action_target: None,
             buttons: None,
+            sound: None,
         }
     }
 
@@ -193,6 +231,16 @@ impl Notification {
         self
     }
 
+    /// Sets the sound to play when showing the notification.
+    ///
+    /// **Note** the sound is only supported by the version 2 of the portal,
+    /// older versions ignore it.
+    #[must_use]
+    pub fn sound(mut self, sound: Sound) -> Self {
+        self.sound = Some(sound.into());
+        self
+    }
+
     /// Adds a new button to the notification.
     #[must_use]
     pub fn button(mut self, button: Button) -> Self {
@@ -217,6 +265,8 @@ pub struct Button {
     action: String,
     /// Target parameter to send along when activating the action.
     target: Option<OwnedValue>,
+    /// What the button is used for, `im.reply-with-text` for example.
+    purpose: Option<String>,
 }
 
 impl Button {
@@ -232,6 +282,7 @@ impl Button {
             label: label.to_owned(),
             action: action.to_owned(),
             target: None,
+            purpose: None,
         }
     }
 
@@ -241,6 +292,17 @@ impl Button {
         self.target = Some(target);
         self
     }
+
+    /// Sets what the button is used for, `im.reply-with-text` or
+    /// `call.accept` for example, so the desktop can show it accordingly.
+    ///
+    /// **Note** the purpose is only supported by the version 2 of the portal,
+    /// older versions ignore it.
+    #[must_use]
+    pub fn purpose(mut self, purpose: &str) -> Self {
+        self.purpose = Some(purpose.to_owned());
+        self
+    }
 }
 
 #[derive(Debug, Deserialize, Type)]
@@ -290,8 +352,14 @@ impl<'a> NotificationProxy<'a> {
     /// Create a new instance of [`NotificationProxy`].
     pub async fn new() -> Result<NotificationProxy<'a>, Error> {
         let connection = session_connection().await?;
-        let proxy = zbus::ProxyBuilder::new_bare(&connection)
-            .interface("org.freedesktop.portal.Notification")?
+        Self::with_connection(&connection).await
+    }
+
+    async fn with_connection(
+        connection: &zbus::Connection,
+    ) -> Result<NotificationProxy<'a>, Error> {
+        let proxy = zbus::ProxyBuilder::new_bare(connection)
+            .interface(Portal::Notification.as_interface_name())?
             .path(PATH)?
             .destination(DESTINATION)?
             .build()
@@ -315,6 +383,46 @@ impl<'a> NotificationProxy<'a> {
         receive_signal(self.inner(), "ActionInvoked").await
     }
 
+    /// A stream of the actions invoked by the user on the notifications.
+    ///
+    /// The portal only sends the signal to the application that added the
+    /// notification, so the stream only yields the application's own
+    /// actions. Unlike [`NotificationProxy::receive_action_invoked`], the
+    /// stream is subscribed to once this method returns, so an action invoked
+    /// in the meantime is not missed.
+    ///
+    /// ```rust,no_run
+    /// use ashpd::desktop::notification::{Button, Notification, NotificationProxy};
+    /// use futures_util::StreamExt;
+    ///
+    /// async fn run() -> ashpd::Result<()> {
+    ///     let proxy = NotificationProxy::new().await?;
+    ///     let mut actions = proxy.on_action_invoked().await?;
+    ///     proxy
+    ///         .add_notification(
+    ///             "new-mail",
+    ///             Notification::new("New mail").button(Button::new("Archive", "archive")),
+    ///         )
+    ///         .await?;
+    ///     while let Some(action) = actions.next().await {
+    ///         println!("{} on {}", action.name(), action.id());
+    ///     }
+    ///     Ok(())
+    /// }
+    /// ```
+    ///
+    /// # Specifications
+    ///
+    /// See also [`ActionInvoked`](https://flatpak.github.io/xdg-desktop-portal/index.html#gdbus-signal-org-freedesktop-portal-Notification.ActionInvoked).
+    pub async fn on_action_invoked(&self) -> Result<impl Stream<Item = Action>, Error> {
+        let stream = self
+            .inner()
+            .receive_signal("ActionInvoked")
+            .await
+            .map_err::<PortalError, _>(From::from)?;
+        Ok(stream.filter_map(|message| ready(message.body::<Action>().ok())))
+    }
+
     /// Sends a notification.
     ///
     /// The ID can be used to later withdraw the notification.
@@ -354,3 +462,127 @@ impl<'a> NotificationProxy<'a> {
         call_method(self.inner(), "RemoveNotification", &(id)).await
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use byteorder::LE;
+    use zbus::zvariant::{
+        from_slice, to_bytes, EncodingContext as Context, OwnedValue, Str, Structure, Value,
+    };
+
+    use super::{Button, Notification, Priority, Sound};
+
+    fn encode(notification: &Notification) -> HashMap<String, OwnedValue> {
+        let ctxt = Context::<LE>::new_dbus(0);
+        let encoded = to_bytes(ctxt, notification).unwrap();
+        from_slice(&encoded, ctxt).unwrap()
+    }
+
+    #[test]
+    fn serialize_priority() {
+        let ctxt = Context::<LE>::new_dbus(0);
+        for (priority, name) in [
+            (Priority::Low, "low"),
+            (Priority::Normal, "normal"),
+            (Priority::High, "high"),
+            (Priority::Urgent, "urgent"),
+        ] {
+            let encoded = to_bytes(ctxt, &priority).unwrap();
+            assert_eq!(from_slice::<_, String>(&encoded, ctxt).unwrap(), name);
+        }
+
+        let decoded = encode(&Notification::new("Title").priority(Priority::Urgent));
+        assert_eq!(decoded["priority"].downcast_ref::<Str>().unwrap(), "urgent");
+    }
+
+    #[test]
+    fn serialize_buttons() {
+        let notification = Notification::new("New message")
+            .button(Button::new("Reply", "reply").purpose("im.reply-with-text"))
+            .button(Button::new("Open", "open").target(Value::U32(42).into()))
+            .sound(Sound::Silent);
+        let decoded = encode(&notification);
+        assert_eq!(decoded["sound"].downcast_ref::<Str>().unwrap(), "silent");
+
+        let buttons =
+            Vec::<HashMap<String, OwnedValue>>::try_from(decoded["buttons"].clone()).unwrap();
+        assert_eq!(buttons.len(), 2);
+        assert_eq!(buttons[0]["label"].downcast_ref::<Str>().unwrap(), "Reply");
+        assert_eq!(
+            buttons[0]["purpose"].downcast_ref::<Str>().unwrap(),
+            "im.reply-with-text"
+        );
+        assert!(!buttons[0].contains_key("target"));
+        assert_eq!(buttons[1]["action"].downcast_ref::<Str>().unwrap(), "open");
+        assert_eq!(buttons[1]["target"].downcast_ref::<u32>(), Some(&42));
+        assert!(!buttons[1].contains_key("purpose"));
+
+        let decoded = encode(&Notification::new("Title").sound(Sound::Bytes(vec![1, 2])));
+        let sound = decoded["sound"].downcast_ref::<Structure>().unwrap();
+        assert_eq!(sound.fields()[0].downcast_ref::<Str>().unwrap(), "bytes");
+    }
+
+    // The tokio feature makes zbus expect a tokio UnixStream instead
+    #[cfg(not(feature = "tokio"))]
+    struct MockNotification;
+
+    #[cfg(not(feature = "tokio"))]
+    #[zbus::dbus_interface(name = "org.freedesktop.portal.Notification")]
+    impl MockNotification {
+        async fn add_notification(
+            &self,
+            id: &str,
+            _notification: HashMap<String, OwnedValue>,
+            #[zbus(signal_context)] ctxt: zbus::SignalContext<'_>,
+        ) {
+            let parameter = OwnedValue::from(42u32);
+            Self::action_invoked(&ctxt, id, "open", &[parameter])
+                .await
+                .unwrap();
+        }
+
+        #[dbus_interface(signal)]
+        async fn action_invoked(
+            ctxt: &zbus::SignalContext<'_>,
+            id: &str,
+            action: &str,
+            parameter: &[OwnedValue],
+        ) -> zbus::Result<()>;
+    }
+
+    #[cfg(not(feature = "tokio"))]
+    #[test]
+    fn action_invoked() {
+        use futures_util::StreamExt;
+
+        use super::NotificationProxy;
+
+        zbus::block_on(async {
+            let guid = zbus::Guid::generate();
+            let (server, client) = std::os::unix::net::UnixStream::pair().unwrap();
+            let (_server, client) = futures_util::try_join!(
+                zbus::ConnectionBuilder::unix_stream(server)
+                    .server(&guid)
+                    .p2p()
+                    .serve_at(crate::desktop::PATH, MockNotification)
+                    .unwrap()
+                    .build(),
+                zbus::ConnectionBuilder::unix_stream(client).p2p().build(),
+            )
+            .unwrap();
+
+            let proxy = NotificationProxy::with_connection(&client).await.unwrap();
+            let mut actions = proxy.on_action_invoked().await.unwrap();
+            proxy
+                .add_notification("new-mail", Notification::new("New mail"))
+                .await
+                .unwrap();
+            let action = actions.next().await.unwrap();
+            assert_eq!(action.id(), "new-mail");
+            assert_eq!(action.name(), "open");
+            assert_eq!(action.parameter()[0].downcast_ref::<u32>(), Some(&42));
+        });
+    }
+}
@@ -15,6 +15,7 @@
 //!     let file = File::open("/home/bilelmoussaoui/adwaita-day.jpg").unwrap();
 //!     OpenFileRequest::default()
 //!         .ask(true)
+//!         .writable(true)
 //!         .build_file(&file)
 //!         .await?;
 //!     Ok(())
@@ -35,6 +36,22 @@
 //! }
 //! ```
 //!
+//! ## Open a web page
+//!
+//! ```rust,no_run
+//! use ashpd::desktop::open_uri::{OpenFileRequest, OpenOutcome};
+//!
+//! async fn run() -> ashpd::Result<()> {
+//!     let uri = url::Url::parse("https://github.com/bilelmoussaoui/ashpd").unwrap();
+//!     match OpenFileRequest::default().build_uri(&uri).await? {
+//!         OpenOutcome::Opened => println!("Opened {}", uri),
+//!         OpenOutcome::Cancelled => println!("Cancelled"),
+//!         OpenOutcome::NoHandler => println!("No application to open it"),
+//!     }
+//!     Ok(())
+//! }
+//! ```
+//!
 //! ## Open a directory
 //!
 //! ```rust,no_run
@@ -49,16 +66,16 @@
 //! }
 //! ```
 
-use std::os::unix::prelude::AsRawFd;
+use std::os::unix::{io::OwnedFd, prelude::AsRawFd};
 
 use url::Url;
 use zbus::zvariant::{Fd, SerializeDict, Type};
 
-use super::{HandleToken, DESTINATION, PATH};
-use crate::{
-    helpers::{call_basic_response_method, session_connection},
-    Error, WindowIdentifier,
+use super::{
+    request::{BasicResponse, RequestBuilder, RequestOptions},
+    HandleToken, ResponseError,
 };
+use crate::{Error, Portal, WindowIdentifier};
 
 #[derive(SerializeDict, Type, Debug, Default)]
 #[zvariant(signature = "dict")]
@@ -76,68 +93,46 @@ struct OpenFileOptions {
     activation_token: Option<String>,
 }
 
-#[derive(Debug)]
-struct OpenURIProxy<'a>(zbus::Proxy<'a>);
-
-impl<'a> OpenURIProxy<'a> {
-    pub async fn new() -> Result<OpenURIProxy<'a>, Error> {
-        let connection = session_connection().await?;
-        let proxy = zbus::ProxyBuilder::new_bare(&connection)
-            .interface("org.freedesktop.portal.OpenURI")?
-            .path(PATH)?
-            .destination(DESTINATION)?
-            .build()
-            .await?;
-        Ok(Self(proxy))
-    }
-
-    pub fn inner(&self) -> &zbus::Proxy<'_> {
-        &self.0
-    }
-
-    pub async fn open_directory(
-        &self,
-        identifier: &WindowIdentifier,
-        directory: &impl AsRawFd,
-        options: OpenDirOptions,
-    ) -> Result<(), Error> {
-        call_basic_response_method(
-            self.inner(),
-            &options.handle_token,
-            "OpenDirectory",
-            &(&identifier, Fd::from(directory.as_raw_fd()), &options),
-        )
-        .await
-    }
-
-    pub async fn open_file(
-        &self,
-        identifier: &WindowIdentifier,
-        file: &impl AsRawFd,
-        options: OpenFileOptions,
-    ) -> Result<(), Error> {
-        call_basic_response_method(
-            self.inner(),
-            &options.handle_token,
-            "OpenFile",
-            &(&identifier, Fd::from(file.as_raw_fd()), &options),
-        )
-        .await
-    }
-
-    pub async fn open_uri(
-        &self,
-        identifier: &WindowIdentifier,
-        uri: &url::Url,
-        options: OpenFileOptions,
-    ) -> Result<(), Error> {
-        call_basic_response_method(
-            self.inner(),
-            &options.handle_token,
-            "OpenURI",
-            &(&identifier, uri, &options),
-        )
-        .await
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+/// The outcome of opening a file, a URI or a directory.
+pub enum OpenOutcome {
+    /// An application was launched to handle it.
+    Opened,
+    /// The user cancelled the request.
+    Cancelled,
+    /// It couldn't be opened, when no application can handle it for example.
+    NoHandler,
+}
+
+impl OpenOutcome {
+    /// Maps the response of the request, keeping the other errors.
+    fn from_response(response: Result<BasicResponse, Error>) -> Result<Self, Error> {
+        match response {
+            Ok(_) => Ok(Self::Opened),
+            Err(Error::Response(ResponseError::Cancelled)) => Ok(Self::Cancelled),
+            Err(Error::Response(ResponseError::Other)) => Ok(Self::NoHandler),
+            Err(err) => Err(err),
+        }
+    }
+}
+
+impl RequestOptions for OpenDirOptions {
+    fn handle_token(&self) -> &HandleToken {
+        &self.handle_token
+    }
+
+    fn set_handle_token(&mut self, handle_token: HandleToken) {
+        self.handle_token = handle_token;
+    }
+}
+
+impl RequestOptions for OpenFileOptions {
+    fn handle_token(&self) -> &HandleToken {
+        &self.handle_token
+    }
+
+    fn set_handle_token(&mut self, handle_token: HandleToken) {
+        self.handle_token = handle_token;
     }
 }
 
@@ -145,8 +140,7 @@ impl<'a> OpenURIProxy<'a> {
 #[doc(alias = "org.freedesktop.portal.OpenURI")]
 #[doc(alias = "xdp_portal_open_uri")]
 pub struct OpenFileRequest {
-    identifier: WindowIdentifier,
-    options: OpenFileOptions,
+    request: RequestBuilder<OpenFileOptions>,
 }
 
 impl OpenFileRequest {
@@ -158,39 +152,123 @@ impl OpenFileRequest {
     }
 
     pub fn set_identifier(&mut self, identifier: WindowIdentifier) {
-        self.identifier = identifier;
+        self.request.identifier = identifier;
     }
 
+    /// Sets the token of the request instead of a random one, e.g. to find
+    /// the request in the application logs and the D-Bus traces.
+    ///
+    /// The token is used verbatim in the path of the request, see
+    /// [`request_path`](crate::request_path).
     #[must_use]
-    /// Whether the file should be writeable or not.
-    pub fn writeable(mut self, writeable: bool) -> Self {
-        self.set_writeable(writeable);
+    pub fn handle_token(mut self, handle_token: HandleToken) -> Self {
+        self.set_handle_token(handle_token);
         self
     }
 
+    pub fn set_handle_token(&mut self, handle_token: HandleToken) {
+        self.request.options.set_handle_token(handle_token);
+    }
+
+    #[must_use]
+    /// Whether the file should be writable or not, only used when opening a
+    /// file.
+    ///
+    /// Maps to the `writeable` option.
+    pub fn writable(mut self, writable: bool) -> Self {
+        self.set_writable(writable);
+        self
+    }
+
+    pub fn set_writable(&mut self, writable: bool) {
+        self.request.options.writeable = Some(writable);
+    }
+
+    #[must_use]
+    #[deprecated(note = "Use `OpenFileRequest::writable` instead")]
+    /// Whether the file should be writable or not.
+    pub fn writeable(self, writeable: bool) -> Self {
+        self.writable(writeable)
+    }
+
+    #[deprecated(note = "Use `OpenFileRequest::set_writable` instead")]
     pub fn set_writeable(&mut self, writeable: bool) {
-        self.options.writeable = Some(writeable);
+        self.set_writable(writeable);
     }
 
     #[must_use]
-    /// Whether to always ask the user which application to use or not.
+    /// Whether to always ask the user which application to use or not, by
+    /// showing the "Open With" dialog.
+    ///
+    /// Maps to the `ask` option.
     pub fn ask(mut self, ask: bool) -> Self {
-        self.options.ask = Some(ask);
+        self.set_ask(ask);
         self
     }
 
     pub fn set_ask(&mut self, ask: bool) {
-        self.options.ask = Some(ask);
+        self.request.options.ask = Some(ask);
     }
 
-    pub async fn build_file(self, file: &impl AsRawFd) -> Result<(), Error> {
-        let proxy = OpenURIProxy::new().await?;
-        proxy.open_file(&self.identifier, file, self.options).await
+    /// Open `file`.
+    ///
+    /// See [`OpenFileRequest::build_uri`] for the possible outcomes.
+    ///
+    /// **Note** only the raw file descriptor of `file` is sent, it must not be
+    /// closed before the returned future completes, see
+    /// [`OpenFileRequest::build_owned_fd`].
+    ///
+    /// # Specifications
+    ///
+    /// See also [`OpenFile`](https://flatpak.github.io/xdg-desktop-portal/index.html#gdbus-method-org-freedesktop-portal-OpenURI.OpenFile).
+    #[doc(alias = "OpenFile")]
+    pub async fn build_file(self, file: &impl AsRawFd) -> Result<OpenOutcome, Error> {
+        let response = self
+            .request
+            .send::<BasicResponse, _>(
+                Portal::OpenURI,
+                "OpenFile",
+                &(
+                    &self.request.identifier,
+                    Fd::from(file.as_raw_fd()),
+                    &self.request.options,
+                ),
+            )
+            .await;
+        OpenOutcome::from_response(response)
     }
 
-    pub async fn build_uri(self, uri: &Url) -> Result<(), Error> {
-        let proxy = OpenURIProxy::new().await?;
-        proxy.open_uri(&self.identifier, uri, self.options).await
+    /// Open a file, taking ownership of its file descriptor so it can't be
+    /// closed before the portal received it.
+    ///
+    /// The file descriptor is closed once the request completes. See
+    /// [`OpenFileRequest::build_uri`] for the possible outcomes.
+    #[doc(alias = "OpenFile")]
+    pub async fn build_owned_fd(self, fd: OwnedFd) -> Result<OpenOutcome, Error> {
+        self.build_file(&fd).await
+    }
+
+    /// Open `uri`.
+    ///
+    /// Returns [`OpenOutcome::Opened`] once an application was launched to
+    /// handle it, [`OpenOutcome::Cancelled`] if the user cancelled the request
+    /// and [`OpenOutcome::NoHandler`] if it couldn't be opened, when no
+    /// application can handle it for example.
+    ///
+    /// # Specifications
+    ///
+    /// See also [`OpenURI`](https://flatpak.github.io/xdg-desktop-portal/index.html#gdbus-method-org-freedesktop-portal-OpenURI.OpenURI).
+    #[doc(alias = "OpenURI")]
+    pub async fn build_uri(self, uri: &Url) -> Result<OpenOutcome, Error> {
+        let response = self
+            .request
+            .send::<BasicResponse, _>(
+                Portal::OpenURI,
+                "OpenURI",
+                &(&self.request.identifier, uri, &self.request.options),
+            )
+            .await;
+        OpenOutcome::from_response(response)
     }
 }
 
@@ -198,8 +276,7 @@ impl OpenFileRequest {
 #[doc(alias = "xdp_portal_open_directory")]
 #[doc(alias = "org.freedesktop.portal.OpenURI")]
 pub struct OpenDirectoryRequest {
-    identifier: WindowIdentifier,
-    options: OpenDirOptions,
+    request: RequestBuilder<OpenDirOptions>,
 }
 
 impl OpenDirectoryRequest {
@@ -211,13 +288,203 @@ impl OpenDirectoryRequest {
     }
 
     pub fn set_identifier(&mut self, identifier: WindowIdentifier) {
-        self.identifier = identifier;
+        self.request.identifier = identifier;
+    }
+
+    /// Sets the token of the request instead of a random one, e.g. to find
+    /// the request in the application logs and the D-Bus traces.
+    ///
+    /// The token is used verbatim in the path of the request, see
+    /// [`request_path`](crate::request_path).
+    #[must_use]
+    pub fn handle_token(mut self, handle_token: HandleToken) -> Self {
+        self.set_handle_token(handle_token);
+        self
+    }
+
+    pub fn set_handle_token(&mut self, handle_token: HandleToken) {
+        self.request.options.set_handle_token(handle_token);
+    }
+
+    /// Open `directory` in the file manager.
+    ///
+    /// See [`OpenFileRequest::build_uri`] for the possible outcomes.
+    ///
+    /// **Note** only the raw file descriptor of `directory` is sent, it must
+    /// not be closed before the returned future completes, see
+    /// [`OpenDirectoryRequest::build_owned_fd`].
+    ///
+    /// # Specifications
+    ///
+    /// See also [`OpenDirectory`](https://flatpak.github.io/xdg-desktop-portal/index.html#gdbus-method-org-freedesktop-portal-OpenURI.OpenDirectory).
+    #[doc(alias = "OpenDirectory")]
+    pub async fn build(self, directory: &impl AsRawFd) -> Result<OpenOutcome, Error> {
+        let response = self
+            .request
+            .send::<BasicResponse, _>(
+                Portal::OpenURI,
+                "OpenDirectory",
+                &(
+                    &self.request.identifier,
+                    Fd::from(directory.as_raw_fd()),
+                    &self.request.options,
+                ),
+            )
+            .await;
+        OpenOutcome::from_response(response)
+    }
+
+    /// Open a directory in the file manager, taking ownership of its file
+    /// descriptor so it can't be closed before the portal received it.
+    ///
+    /// The file descriptor is closed once the request completes. See
+    /// [`OpenFileRequest::build_uri`] for the possible outcomes.
+    #[doc(alias = "OpenDirectory")]
+    pub async fn build_owned_fd(self, directory: OwnedFd) -> Result<OpenOutcome, Error> {
+        self.build(&directory).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use byteorder::LE;
+    use zbus::zvariant::{from_slice, to_bytes, EncodingContext as Context, OwnedValue};
+
+    #[cfg(not(feature = "tokio"))]
+    use zbus::zvariant::Value;
+
+    use super::OpenFileRequest;
+    #[cfg(not(feature = "tokio"))]
+    use super::{OpenDirectoryRequest, OpenOutcome};
+
+    #[cfg(not(feature = "tokio"))]
+    struct MockOpenURI(u32);
+
+    #[cfg(not(feature = "tokio"))]
+    impl MockOpenURI {
+        // Answers the request with the response code of the mock
+        async fn respond(
+            &self,
+            connection: &zbus::Connection,
+            options: HashMap<String, OwnedValue>,
+        ) -> zbus::fdo::Result<zbus::zvariant::OwnedObjectPath> {
+            let handle_token = <&str>::try_from(&options["handle_token"]).unwrap();
+            let path = crate::request_path(":1.42", handle_token).unwrap();
+            connection
+                .emit_signal(
+                    None::<zbus::names::BusName<'_>>,
+                    path.as_str(),
+                    "org.freedesktop.portal.Request",
+                    "Response",
+                    &(self.0, HashMap::<&str, Value<'_>>::new()),
+                )
+                .await?;
+            Ok(path)
+        }
     }
 
-    pub async fn build(self, directory: &impl AsRawFd) -> Result<(), Error> {
-        let proxy = OpenURIProxy::new().await?;
-        proxy
-            .open_directory(&self.identifier, directory, self.options)
-            .await
+    #[cfg(not(feature = "tokio"))]
+    #[zbus::dbus_interface(name = "org.freedesktop.portal.OpenURI")]
+    impl MockOpenURI {
+        #[dbus_interface(name = "OpenURI")]
+        async fn open_uri(
+            &self,
+            #[zbus(connection)] connection: &zbus::Connection,
+            _parent_window: &str,
+            _uri: &str,
+            options: HashMap<String, OwnedValue>,
+        ) -> zbus::fdo::Result<zbus::zvariant::OwnedObjectPath> {
+            self.respond(connection, options).await
+        }
+
+        async fn open_file(
+            &self,
+            #[zbus(connection)] connection: &zbus::Connection,
+            _parent_window: &str,
+            _fd: zbus::zvariant::Fd,
+            options: HashMap<String, OwnedValue>,
+        ) -> zbus::fdo::Result<zbus::zvariant::OwnedObjectPath> {
+            self.respond(connection, options).await
+        }
+
+        async fn open_directory(
+            &self,
+            #[zbus(connection)] connection: &zbus::Connection,
+            _parent_window: &str,
+            _fd: zbus::zvariant::Fd,
+            options: HashMap<String, OwnedValue>,
+        ) -> zbus::fdo::Result<zbus::zvariant::OwnedObjectPath> {
+            self.respond(connection, options).await
+        }
+    }
+
+    #[cfg(not(feature = "tokio"))]
+    async fn mock_connection(code: u32) -> (zbus::Connection, zbus::Connection) {
+        let guid = zbus::Guid::generate();
+        let (server, client) = std::os::unix::net::UnixStream::pair().unwrap();
+        futures_util::try_join!(
+            zbus::ConnectionBuilder::unix_stream(server)
+                .server(&guid)
+                .p2p()
+                .serve_at(crate::desktop::PATH, MockOpenURI(code))
+                .unwrap()
+                .build(),
+            zbus::ConnectionBuilder::unix_stream(client)
+                .p2p()
+                .unique_name(":1.42")
+                .unwrap()
+                .build(),
+        )
+        .unwrap()
+    }
+
+    #[cfg(not(feature = "tokio"))]
+    #[test]
+    fn response_outcomes() {
+        zbus::block_on(async {
+            let uri = url::Url::parse("https://github.com/bilelmoussaoui/ashpd").unwrap();
+            let file = std::fs::File::open("/dev/null").unwrap();
+            let directory = std::fs::File::open("/tmp").unwrap();
+
+            for (code, expected) in [
+                (0, OpenOutcome::Opened),
+                (1, OpenOutcome::Cancelled),
+                (2, OpenOutcome::NoHandler),
+            ] {
+                let (_server, client) = mock_connection(code).await;
+                let mut request = OpenFileRequest::default();
+                request.request.connection = Some(client);
+                assert_eq!(request.build_uri(&uri).await.unwrap(), expected);
+
+                let (_server, client) = mock_connection(code).await;
+                let mut request = OpenFileRequest::default();
+                request.request.connection = Some(client);
+                assert_eq!(request.build_file(&file).await.unwrap(), expected);
+
+                let (_server, client) = mock_connection(code).await;
+                let mut request = OpenDirectoryRequest::default();
+                request.request.connection = Some(client);
+                assert_eq!(request.build(&directory).await.unwrap(), expected);
+            }
+        });
+    }
+
+    #[test]
+    fn serialize_options() {
+        let ctxt = Context::<LE>::new_dbus(0);
+
+        let request = OpenFileRequest::default();
+        let encoded = to_bytes(ctxt, &request.request.options).unwrap();
+        let decoded: HashMap<String, OwnedValue> = from_slice(&encoded, ctxt).unwrap();
+        assert!(!decoded.contains_key("ask"));
+        assert!(!decoded.contains_key("writeable"));
+
+        let request = request.ask(true).writable(false);
+        let encoded = to_bytes(ctxt, &request.request.options).unwrap();
+        let decoded: HashMap<String, OwnedValue> = from_slice(&encoded, ctxt).unwrap();
+        assert!(bool::try_from(&decoded["ask"]).unwrap());
+        assert!(!bool::try_from(&decoded["writeable"]).unwrap());
     }
 }
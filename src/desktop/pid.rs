@@ -0,0 +1,102 @@
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+use zbus::zvariant::Type;
+
+/// A process id, as returned by `getpid()`.
+///
+/// Portals expect the id of the process, not the one of a thread. Use
+/// [`Pid::current`] to get the id of the running process.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, Type)]
+pub struct Pid(u32);
+
+impl Pid {
+    /// The id of the running process.
+    pub fn current() -> Self {
+        Self(std::process::id())
+    }
+}
+
+impl From<u32> for Pid {
+    fn from(pid: u32) -> Self {
+        Self(pid)
+    }
+}
+
+impl From<Pid> for u32 {
+    fn from(pid: Pid) -> Self {
+        pid.0
+    }
+}
+
+impl fmt::Display for Pid {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+/// A thread id, as returned by `gettid()`.
+///
+/// Use [`ThreadId::current`] to get the id of the calling thread. For the main
+/// thread, it is the same as the process id.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, Type)]
+pub struct ThreadId(u32);
+
+impl ThreadId {
+    /// The id of the calling thread.
+    #[cfg(target_os = "linux")]
+    pub fn current() -> Self {
+        // SAFETY: gettid has no preconditions and always succeeds
+        Self(unsafe { libc::gettid() } as u32)
+    }
+}
+
+impl From<u32> for ThreadId {
+    fn from(tid: u32) -> Self {
+        Self(tid)
+    }
+}
+
+impl From<ThreadId> for u32 {
+    fn from(tid: ThreadId) -> Self {
+        tid.0
+    }
+}
+
+impl fmt::Display for ThreadId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use byteorder::LE;
+    use zbus::zvariant::{to_bytes, EncodingContext as Context, Type};
+
+    use super::{Pid, ThreadId};
+
+    #[test]
+    fn current_pid() {
+        assert_eq!(u32::from(Pid::current()), unsafe { libc::getpid() } as u32);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn current_thread_id() {
+        let tid = ThreadId::current();
+        assert_eq!(u32::from(tid), unsafe { libc::gettid() } as u32);
+        let other = std::thread::spawn(ThreadId::current).join().unwrap();
+        assert_ne!(tid, other);
+    }
+
+    #[test]
+    fn serialize_as_u32() {
+        assert_eq!(Pid::signature(), u32::signature());
+        let ctxt = Context::<LE>::new_dbus(0);
+        assert_eq!(
+            to_bytes(ctxt, &Pid::from(42)).unwrap(),
+            to_bytes(ctxt, &42u32).unwrap()
+        );
+    }
+}
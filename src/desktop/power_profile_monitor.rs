@@ -1,5 +1,50 @@
+use std::{fmt, str::FromStr};
+
+use zbus::DBusError;
+
 use super::{DESTINATION, PATH};
-use crate::{helpers::session_connection, Error};
+use crate::{
+    helpers::{app_id, session_connection, spawn_detached},
+    Error, Portal,
+};
+
+// power-profiles-daemon, on the system bus
+const POWER_PROFILES_DESTINATION: &str = "net.hadess.PowerProfiles";
+const POWER_PROFILES_PATH: &str = "/net/hadess/PowerProfiles";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// A system-wide power profile.
+pub enum PowerProfile {
+    /// Saves battery, at the expense of the performance.
+    PowerSaver,
+    /// The default one.
+    Balanced,
+    /// Maximum performance, at the expense of the battery life.
+    Performance,
+}
+
+impl fmt::Display for PowerProfile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::PowerSaver => f.write_str("power-saver"),
+            Self::Balanced => f.write_str("balanced"),
+            Self::Performance => f.write_str("performance"),
+        }
+    }
+}
+
+impl FromStr for PowerProfile {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "power-saver" => Ok(Self::PowerSaver),
+            "balanced" => Ok(Self::Balanced),
+            "performance" => Ok(Self::Performance),
+            _ => Err(Error::ParseError("Failed to parse power profile")),
+        }
+    }
+}
 
 /// The interface provides information about the user-selected system-wide power
 /// profile, to sandboxed applications.
@@ -20,7 +65,7 @@ impl<'a> PowerProfileMonitor<'a> {
     pub async fn new() -> Result<PowerProfileMonitor<'a>, Error> {
         let connection = session_connection().await?;
         let proxy = zbus::ProxyBuilder::new_bare(&connection)
-            .interface("org.freedesktop.portal.PowerProfileMonitor")?
+            .interface(Portal::PowerProfileMonitor.as_interface_name())?
             .path(PATH)?
             .destination(DESTINATION)?
             .build()
@@ -41,4 +86,212 @@ impl<'a> PowerProfileMonitor<'a> {
             .await
             .map_err(From::from)
     }
+
+    /// The active power profile.
+    ///
+    /// It isn't part of the portal, it is read from power-profiles-daemon on
+    /// the system bus. Fails with [`Error::Unsupported`] if it isn't running,
+    /// or if the sandbox doesn't allow talking to `net.hadess.PowerProfiles`.
+    pub async fn active_profile(&self) -> Result<PowerProfile, Error> {
+        let proxy = power_profiles(&system_connection().await?).await?;
+        active_profile(&proxy).await
+    }
+
+    /// Request the performance profile, until the returned
+    /// [`PowerProfileHold`] is dropped or released.
+    ///
+    /// Like [`PowerProfileMonitor::active_profile`], it relies on
+    /// power-profiles-daemon and fails with [`Error::Unsupported`] without
+    /// it.
+    ///
+    /// **Note** it is only a hint, a no-op on the many desktops and hardware
+    /// without a performance profile. The user changing the profile ends all
+    /// the holds as well.
+    ///
+    /// # Arguments
+    ///
+    /// * `reason` - Why the application needs the performance, like
+    ///   "Exporting the video".
+    pub async fn hold_performance(&self, reason: &str) -> Result<PowerProfileHold, Error> {
+        let proxy = power_profiles(&system_connection().await?).await?;
+        PowerProfileHold::new(proxy, PowerProfile::Performance, reason).await
+    }
+}
+
+/// A power profile requested with [`PowerProfileMonitor::hold_performance`].
+///
+/// The hold is released when dropped, unless it was already released with
+/// [`PowerProfileHold::release`]. As for [`Session`](crate::desktop::Session),
+/// releasing on drop is best-effort.
+#[derive(Debug)]
+pub struct PowerProfileHold {
+    proxy: zbus::Proxy<'static>,
+    cookie: u32,
+    released: bool,
+}
+
+impl PowerProfileHold {
+    async fn new(
+        proxy: zbus::Proxy<'static>,
+        profile: PowerProfile,
+        reason: &str,
+    ) -> Result<Self, Error> {
+        let app_id = app_id().unwrap_or_default();
+        let cookie = proxy
+            .call_method("HoldProfile", &(profile.to_string(), reason, app_id))
+            .await
+            .map_err(unavailable)?
+            .body::<u32>()?;
+        Ok(Self {
+            proxy,
+            cookie,
+            released: false,
+        })
+    }
+
+    /// Release the hold, letting the system go back to the profile selected
+    /// by the user.
+    pub async fn release(mut self) -> Result<(), Error> {
+        self.released = true;
+        self.proxy
+            .call_method("ReleaseProfile", &(self.cookie))
+            .await
+            .map_err(unavailable)?;
+        Ok(())
+    }
+}
+
+impl Drop for PowerProfileHold {
+    fn drop(&mut self) {
+        if self.released {
+            return;
+        }
+        let proxy = self.proxy.clone();
+        let cookie = self.cookie;
+        spawn_detached(async move {
+            // The daemon drops the holds of the disconnected clients anyway
+            let _ = proxy.call_method("ReleaseProfile", &(cookie)).await;
+        });
+    }
+}
+
+async fn system_connection() -> Result<zbus::Connection, Error> {
+    zbus::Connection::system()
+        .await
+        .map_err(|_| Error::Unsupported("power-profiles-daemon"))
+}
+
+async fn power_profiles(connection: &zbus::Connection) -> Result<zbus::Proxy<'static>, Error> {
+    let proxy = zbus::ProxyBuilder::new_bare(connection)
+        .interface(POWER_PROFILES_DESTINATION)?
+        .path(POWER_PROFILES_PATH)?
+        .destination(POWER_PROFILES_DESTINATION)?
+        // The properties are only read once
+        .cache_properties(zbus::CacheProperties::No)
+        .build()
+        .await?;
+    Ok(proxy)
+}
+
+async fn active_profile(proxy: &zbus::Proxy<'_>) -> Result<PowerProfile, Error> {
+    proxy
+        .get_property::<String>("ActiveProfile")
+        .await
+        .map_err(unavailable)?
+        .parse()
+}
+
+// Whether `err` means power-profiles-daemon isn't there for us
+fn unavailable(err: zbus::Error) -> Error {
+    let name = match &err {
+        zbus::Error::MethodError(name, _, _) => name.to_string(),
+        zbus::Error::FDO(err) => err.name().to_string(),
+        _ => return err.into(),
+    };
+    match name.as_str() {
+        "org.freedesktop.DBus.Error.ServiceUnknown"
+        | "org.freedesktop.DBus.Error.NameHasNoOwner"
+        | "org.freedesktop.DBus.Error.UnknownObject"
+        | "org.freedesktop.DBus.Error.UnknownInterface"
+        | "org.freedesktop.DBus.Error.AccessDenied" => Error::Unsupported("power-profiles-daemon"),
+        _ => err.into(),
+    }
+}
+
+// The tokio feature makes zbus expect a tokio UnixStream instead
+#[cfg(all(test, not(feature = "tokio")))]
+mod tests {
+    use super::{active_profile, power_profiles, PowerProfile, POWER_PROFILES_PATH};
+    use crate::Error;
+
+    struct MockPowerProfiles;
+
+    #[zbus::dbus_interface(name = "net.hadess.PowerProfiles")]
+    impl MockPowerProfiles {
+        #[dbus_interface(property)]
+        fn active_profile(&self) -> String {
+            "performance".to_owned()
+        }
+    }
+
+    struct MockOther;
+
+    #[zbus::dbus_interface(name = "org.example.Other")]
+    impl MockOther {}
+
+    #[test]
+    fn read_active_profile() {
+        zbus::block_on(async {
+            let guid = zbus::Guid::generate();
+            let (server, client) = std::os::unix::net::UnixStream::pair().unwrap();
+            let (_server, client) = futures_util::try_join!(
+                zbus::ConnectionBuilder::unix_stream(server)
+                    .server(&guid)
+                    .p2p()
+                    .serve_at(POWER_PROFILES_PATH, MockPowerProfiles)
+                    .unwrap()
+                    .build(),
+                zbus::ConnectionBuilder::unix_stream(client).p2p().build(),
+            )
+            .unwrap();
+            let proxy = power_profiles(&client).await.unwrap();
+            assert_eq!(
+                active_profile(&proxy).await.unwrap(),
+                PowerProfile::Performance
+            );
+
+            // Something else than power-profiles-daemon
+            let (server, client) = std::os::unix::net::UnixStream::pair().unwrap();
+            let (_server, client) = futures_util::try_join!(
+                zbus::ConnectionBuilder::unix_stream(server)
+                    .server(&guid)
+                    .p2p()
+                    .serve_at(POWER_PROFILES_PATH, MockOther)
+                    .unwrap()
+                    .build(),
+                zbus::ConnectionBuilder::unix_stream(client).p2p().build(),
+            )
+            .unwrap();
+            let proxy = power_profiles(&client).await.unwrap();
+            assert!(matches!(
+                active_profile(&proxy).await,
+                Err(Error::Unsupported(_))
+            ));
+        });
+    }
+
+    #[test]
+    fn parse_profile() {
+        for profile in [
+            PowerProfile::PowerSaver,
+            PowerProfile::Balanced,
+            PowerProfile::Performance,
+        ] {
+            assert_eq!(
+                profile.to_string().parse::<PowerProfile>().unwrap(),
+                profile
+            );
+        }
+        assert!("turbo".parse::<PowerProfile>().is_err());
+    }
 }
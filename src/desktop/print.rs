@@ -30,15 +30,17 @@
 //! }
 //! ```
 
-use std::{fmt, os::unix::prelude::AsRawFd, str::FromStr};
+use std::{collections::HashMap, fmt, os::unix::prelude::AsRawFd, str::FromStr};
 
-use serde::{Deserialize, Serialize};
-use zbus::zvariant::{DeserializeDict, Fd, SerializeDict, Type};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use zbus::zvariant::{DeserializeDict, Fd, OwnedValue, SerializeDict, Type};
 
 use super::{HandleToken, DESTINATION, PATH};
 use crate::{
-    helpers::{call_basic_response_method, call_request_method, session_connection},
-    Error, WindowIdentifier,
+    helpers::{
+        call_basic_response_method, call_request_method, check_window_kind, session_connection,
+    },
+    Error, Portal, WindowIdentifier,
 };
 
 #[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq, Type)]
@@ -167,8 +169,12 @@ impl FromStr for Quality {
     }
 }
 
-#[derive(SerializeDict, DeserializeDict, Type, Debug, Default)]
+#[derive(SerializeDict, DeserializeDict, Type, Debug, Default, Clone, PartialEq)]
 /// Print settings to set in the print dialog.
+///
+/// The settings returned by [`PrintProxy::prepare_print`] can be stored with
+/// [`Settings::to_dict`] and passed back to the next call once restored with
+/// [`Settings::from_dict`], for the user to not have to pick them again.
 #[zvariant(signature = "dict")]
 pub struct Settings {
     /// One of landscape, portrait, reverse_landscape or reverse_portrait.
@@ -250,6 +256,32 @@ pub struct Settings {
 }
 
 impl Settings {
+    /// The settings as a dictionary, as sent to the portal.
+    ///
+    /// Unlike the settings themselves, the dictionary can be stored in any
+    /// format supported by serde.
+    ///
+    /// ```rust
+    /// use ashpd::desktop::print::Settings;
+    ///
+    /// let settings = Settings::default().paper_format("iso_a4").use_color(true);
+    /// let saved = serde_json::to_string(&settings.to_dict().unwrap()).unwrap();
+    ///
+    /// let restored = Settings::from_dict(serde_json::from_str(&saved).unwrap()).unwrap();
+    /// assert_eq!(restored, settings);
+    /// ```
+    pub fn to_dict(&self) -> Result<HashMap<String, OwnedValue>, Error> {
+        to_dict(self)
+    }
+
+    /// Restores settings from a dictionary created by [`Settings::to_dict`].
+    ///
+    /// The unknown keys are ignored. Fails with [`Error::Zbus`] if a known key
+    /// holds a value of an unexpected type, e.g. a number as `paper-format`.
+    pub fn from_dict(dict: HashMap<String, OwnedValue>) -> Result<Self, Error> {
+        from_dict(dict)
+    }
+
     /// Sets the orientation.
     #[must_use]
     pub fn orientation(mut self, orientation: Orientation) -> Self {
@@ -448,8 +480,11 @@ impl Settings {
     }
 }
 
-#[derive(SerializeDict, DeserializeDict, Type, Debug, Default)]
+#[derive(SerializeDict, DeserializeDict, Type, Debug, Default, Clone, PartialEq)]
 /// Setup the printed pages.
+///
+/// Like [`Settings`], it can be stored with [`PageSetup::to_dict`] and restored
+/// with [`PageSetup::from_dict`].
 #[zvariant(signature = "dict")]
 pub struct PageSetup {
     /// the PPD name. It's the name to select a given driver.
@@ -476,6 +511,17 @@ pub struct PageSetup {
 }
 
 impl PageSetup {
+    /// The page setup as a dictionary, as sent to the portal.
+    pub fn to_dict(&self) -> Result<HashMap<String, OwnedValue>, Error> {
+        to_dict(self)
+    }
+
+    /// Restores a page setup from a dictionary created by
+    /// [`PageSetup::to_dict`].
+    pub fn from_dict(dict: HashMap<String, OwnedValue>) -> Result<Self, Error> {
+        from_dict(dict)
+    }
+
     /// Sets the ppdname.
     #[must_use]
     pub fn ppdname(mut self, ppdname: &str) -> Self {
@@ -547,6 +593,25 @@ impl PageSetup {
     }
 }
 
+// The dictionaries go through the body of a D-Bus message, where the settings
+// are (de)serialized as such.
+fn to_dict<T: Serialize + Type>(value: &T) -> Result<HashMap<String, OwnedValue>, Error> {
+    convert(value)
+}
+
+fn from_dict<T: DeserializeOwned + Type>(dict: HashMap<String, OwnedValue>) -> Result<T, Error> {
+    convert(&dict)
+}
+
+fn convert<T, R>(value: &T) -> Result<R, Error>
+where
+    T: Serialize + Type,
+    R: DeserializeOwned + Type,
+{
+    let message = zbus::MessageBuilder::method_call("/", "Convert")?.build(value)?;
+    Ok(message.body()?)
+}
+
 #[derive(SerializeDict, Type, Debug, Default)]
 /// Specified options for a [`PrintProxy::prepare_print`] request.
 #[zvariant(signature = "dict")]
@@ -617,7 +682,7 @@ impl<'a> PrintProxy<'a> {
     pub async fn new() -> Result<PrintProxy<'a>, Error> {
         let connection = session_connection().await?;
         let proxy = zbus::ProxyBuilder::new_bare(&connection)
-            .interface("org.freedesktop.portal.Print")?
+            .interface(Portal::Print.as_interface_name())?
             .path(PATH)?
             .destination(DESTINATION)?
             .build()
@@ -654,6 +719,7 @@ impl<'a> PrintProxy<'a> {
         page_setup: PageSetup,
         modal: bool,
     ) -> Result<PreparePrint, Error> {
+        check_window_kind(identifier)?;
         let options = PreparePrintOptions::default().modal(modal);
         call_request_method(
             self.inner(),
@@ -691,6 +757,7 @@ impl<'a> PrintProxy<'a> {
         token: Option<u32>,
         modal: bool,
     ) -> Result<(), Error> {
+        check_window_kind(identifier)?;
         let options = PrintOptions::default()
             .token(token.unwrap_or(0))
             .modal(modal);
@@ -703,3 +770,75 @@ impl<'a> PrintProxy<'a> {
         .await
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use zbus::zvariant::{OwnedValue, Value};
+
+    use super::{Orientation, PageSetup, Quality, Settings};
+
+    #[test]
+    fn settings_json_round_trip() {
+        let settings = Settings::default()
+            .orientation(Orientation::Landscape)
+            .paper_format("iso_a4")
+            .quality(Quality::High)
+            .use_color(false)
+            .output_uri(&"file:///tmp/out.pdf".parse().unwrap());
+        let dict = settings.to_dict().unwrap();
+        assert_eq!(dict.len(), 5);
+        assert_eq!(
+            dict["paper-format"],
+            OwnedValue::from(Value::from("iso_a4"))
+        );
+
+        let json = serde_json::to_string(&dict).unwrap();
+        let restored: HashMap<String, OwnedValue> = serde_json::from_str(&json).unwrap();
+        assert_eq!(Settings::from_dict(restored).unwrap(), settings);
+    }
+
+    #[test]
+    fn page_setup_json_round_trip() {
+        let page_setup = PageSetup::default()
+            .name("iso_a4")
+            .width(210.0)
+            .height(297.0)
+            .margin_top(6.35)
+            .orientation(Orientation::Portrait);
+
+        let json = serde_json::to_string(&page_setup.to_dict().unwrap()).unwrap();
+        let restored: HashMap<String, OwnedValue> = serde_json::from_str(&json).unwrap();
+        assert_eq!(PageSetup::from_dict(restored).unwrap(), page_setup);
+    }
+
+    #[test]
+    fn from_dict_ignores_unknown_keys() {
+        let dict = HashMap::from([
+            (
+                "paper-format".to_owned(),
+                OwnedValue::from(Value::from("na_letter")),
+            ),
+            (
+                "printer".to_owned(),
+                OwnedValue::from(Value::from("Office")),
+            ),
+        ]);
+        let settings = Settings::from_dict(dict).unwrap();
+        assert_eq!(settings.paper_format.as_deref(), Some("na_letter"));
+        assert_eq!(settings, Settings::default().paper_format("na_letter"));
+    }
+
+    #[test]
+    fn from_dict_mistyped_value() {
+        let dict = HashMap::from([(
+            "paper-format".to_owned(),
+            OwnedValue::from(Value::from(42u32)),
+        )]);
+        assert!(matches!(
+            Settings::from_dict(dict),
+            Err(crate::Error::Zbus(_))
+        ));
+    }
+}
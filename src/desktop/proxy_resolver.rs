@@ -17,7 +17,7 @@
 use super::{DESTINATION, PATH};
 use crate::{
     helpers::{call_method, session_connection},
-    Error,
+    Error, Portal,
 };
 
 /// The interface provides network proxy information to sandboxed applications.
@@ -36,7 +36,7 @@ impl<'a> ProxyResolver<'a> {
     pub async fn new() -> Result<ProxyResolver<'a>, Error> {
         let connection = session_connection().await?;
         let proxy = zbus::ProxyBuilder::new_bare(&connection)
-            .interface("org.freedesktop.portal.ProxyResolver")?
+            .interface(Portal::ProxyResolver.as_interface_name())?
             .path(PATH)?
             .destination(DESTINATION)?
             .build()
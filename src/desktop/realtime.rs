@@ -2,10 +2,10 @@
 //!
 //! Wrapper of the DBus interface: [`org.freedesktop.portal.Realtime`](https://flatpak.github.io/xdg-desktop-portal/index.html#gdbus-org.freedesktop.portal.Realtime).
 
-use super::{DESTINATION, PATH};
+use super::{Pid, ThreadId, DESTINATION, PATH};
 use crate::{
     helpers::{call_method, session_connection},
-    Error,
+    Error, Portal,
 };
 
 #[derive(Debug)]
@@ -17,7 +17,7 @@ impl<'a> Realtime<'a> {
     pub async fn new() -> Result<Realtime<'a>, Error> {
         let connection = session_connection().await?;
         let proxy = zbus::ProxyBuilder::new_bare(&connection)
-            .interface("org.freedesktop.portal.Realtime")?
+            .interface(Portal::Realtime.as_interface_name())?
             .path(PATH)?
             .destination(DESTINATION)?
             .build()
@@ -30,13 +30,18 @@ impl<'a> Realtime<'a> {
         &self.0
     }
 
+    /// Sets the `thread` of `process` to realtime with `priority`.
+    ///
+    /// Use [`Pid::current`] and [`ThreadId::current`] for the calling thread.
     #[doc(alias = "MakeThreadRealtimeWithPID")]
     pub async fn max_thread_realtime_with_pid(
         &self,
-        process: u64,
-        thread: u64,
+        process: impl Into<Pid>,
+        thread: impl Into<ThreadId>,
         priority: u32,
     ) -> Result<(), Error> {
+        let process = u64::from(u32::from(process.into()));
+        let thread = u64::from(u32::from(thread.into()));
         call_method(
             self.inner(),
             "MakeThreadRealtimeWithPID",
@@ -45,13 +50,19 @@ impl<'a> Realtime<'a> {
         .await
     }
 
+    /// Sets the `thread` of `process` to high priority with the nice level
+    /// `priority`.
+    ///
+    /// Use [`Pid::current`] and [`ThreadId::current`] for the calling thread.
     #[doc(alias = "MakeThreadHighPriorityWithPID")]
     pub async fn max_thread_high_priority_with_pid(
         &self,
-        process: u64,
-        thread: u64,
+        process: impl Into<Pid>,
+        thread: impl Into<ThreadId>,
         priority: i32,
     ) -> Result<(), Error> {
+        let process = u64::from(u32::from(process.into()));
+        let thread = u64::from(u32::from(thread.into()));
         call_method(
             self.inner(),
             "MakeThreadHighPriorityWithPID",
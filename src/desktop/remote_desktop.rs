@@ -72,20 +72,78 @@
 //!     Ok(())
 //! }
 //! ```
+//!
+//! [`RemoteDesktopScreencastRequest`] does the same in one go, making sure
+//! both the devices and the sources are selected on the same session so the
+//! user is only prompted once.
+//!
+//! ```rust,no_run
+//! use ashpd::desktop::{
+//!     remote_desktop::{DeviceType, KeyState, RemoteDesktop, RemoteDesktopScreencastRequest},
+//!     screencast::{CursorMode, SourceType},
+//! };
+//!
+//! async fn run() -> ashpd::Result<()> {
+//!     let (session, devices, streams, _) = RemoteDesktopScreencastRequest::default()
+//!         .device_types(DeviceType::Keyboard | DeviceType::Pointer)
+//!         .source_types(SourceType::Monitor.into())
+//!         .cursor_mode(CursorMode::Metadata)
+//!         .build()
+//!         .await?;
+//!     println!("{:#?}", devices);
+//!     println!("{:#?}", streams);
+//!
+//!     RemoteDesktop::new()
+//!         .await?
+//!         .notify_keyboard_keycode(&session, 13, KeyState::Pressed)
+//!         .await?;
+//!
+//!     Ok(())
+//! }
+//! ```
+//!
+//! Scripting the input is easier with the higher level helpers, typing text
+//! and clicking instead of sending each key code.
+//!
+//! ```rust,no_run
+//! use ashpd::{
+//!     desktop::remote_desktop::{DeviceType, PointerButton, RemoteDesktop},
+//!     WindowIdentifier,
+//! };
+//!
+//! async fn run() -> ashpd::Result<()> {
+//!     let proxy = RemoteDesktop::new().await?;
+//!     let session = proxy.create_session().await?;
+//!     proxy
+//!         .select_devices(&session, DeviceType::Keyboard | DeviceType::Pointer)
+//!         .await?;
+//!     proxy.start(&session, &WindowIdentifier::default()).await?;
+//!
+//!     proxy.click(&session, PointerButton::Left).await?;
+//!     proxy.type_text(&session, "Hello, World!\n").await?;
+//!
+//!     Ok(())
+//! }
+//! ```
 //! [select_sources]: crate::desktop::screencast::Screencast::select_sources
 //! [create_session]: crate::desktop::remote_desktop::RemoteDesktop::create_session
 
 use std::collections::HashMap;
 
 use enumflags2::{bitflags, BitFlags};
-use futures_util::TryFutureExt;
 use serde_repr::{Deserialize_repr, Serialize_repr};
 use zbus::zvariant::{DeserializeDict, SerializeDict, Type, Value};
 
-use super::{screencast::Stream, HandleToken, Session, DESTINATION, PATH};
+use super::{
+    screencast::{CursorMode, PersistMode, Screencast, SelectSourcesOptions, SourceType, Stream},
+    HandleToken, Session, DESTINATION, PATH,
+};
 use crate::{
-    helpers::{call_basic_response_method, call_method, call_request_method, session_connection},
-    Error, WindowIdentifier,
+    helpers::{
+        call_basic_response_method, call_method, call_request_method, check_window_kind,
+        session_connection,
+    },
+    Error, Portal, WindowIdentifier,
 };
 
 #[derive(Serialize_repr, Deserialize_repr, Copy, Clone, PartialEq, Eq, Debug, Type)]
@@ -118,6 +176,67 @@ pub enum DeviceType {
     Touchscreen,
 }
 
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+/// A pointer button, used by [`RemoteDesktop::click`].
+pub enum PointerButton {
+    /// The left, primary, button.
+    Left,
+    /// The right, secondary, button.
+    Right,
+    /// The middle button, usually the wheel.
+    Middle,
+}
+
+impl PointerButton {
+    /// The Linux evdev code of the button.
+    pub fn code(self) -> i32 {
+        match self {
+            Self::Left => 0x110,
+            Self::Right => 0x111,
+            Self::Middle => 0x112,
+        }
+    }
+}
+
+// Linux evdev key code of the left shift key.
+const KEY_LEFTSHIFT: i32 = 42;
+
+/// The Linux evdev key code typing `c` on a US keyboard layout, and whether
+/// shift has to be held while typing it.
+fn us_keycode(c: char) -> Option<(i32, bool)> {
+    const ROW_DIGITS: &str = "1234567890-=";
+    const ROW_DIGITS_SHIFTED: &str = "!@#$%^&*()_+";
+    const ROW_TOP: &str = "qwertyuiop[]";
+    const ROW_TOP_SHIFTED: &str = "QWERTYUIOP{}";
+    const ROW_HOME: &str = "asdfghjkl;'`";
+    const ROW_HOME_SHIFTED: &str = "ASDFGHJKL:\"~";
+    const ROW_BOTTOM: &str = "\\zxcvbnm,./";
+    const ROW_BOTTOM_SHIFTED: &str = "|ZXCVBNM<>?";
+
+    let rows = [
+        (ROW_DIGITS, ROW_DIGITS_SHIFTED, 2),
+        (ROW_TOP, ROW_TOP_SHIFTED, 16),
+        (ROW_HOME, ROW_HOME_SHIFTED, 30),
+        (ROW_BOTTOM, ROW_BOTTOM_SHIFTED, 43),
+    ];
+    match c {
+        '\n' => return Some((28, false)),
+        '\t' => return Some((15, false)),
+        ' ' => return Some((57, false)),
+        _ => (),
+    }
+    rows.iter().find_map(|(row, shifted_row, first)| {
+        if let Some(index) = row.chars().position(|key| key == c) {
+            Some((first + index as i32, false))
+        } else {
+            shifted_row
+                .chars()
+                .position(|key| key == c)
+                .map(|index| (first + index as i32, true))
+        }
+    })
+}
+
 #[derive(Serialize_repr, Deserialize_repr, PartialEq, Eq, Debug, Type)]
 #[doc(alias = "XdpDiscreteAxis")]
 #[repr(u32)]
@@ -141,16 +260,6 @@ struct CreateRemoteOptions {
     session_handle_token: HandleToken,
 }
 
-#[derive(DeserializeDict, Type, Debug)]
-/// A response to a [`RemoteDesktop::create_session`] request.
-#[zvariant(signature = "dict")]
-struct CreateSession {
-    // TODO: investigate why this doesn't return an ObjectPath
-    // replace with an ObjectPath once https://github.com/flatpak/xdg-desktop-portal/pull/609's merged
-    /// A string that will be used as the last element of the session handle.
-    session_handle: String,
-}
-
 #[derive(SerializeDict, Type, Debug, Default)]
 /// Specified options for a [`RemoteDesktop::select_devices`] request.
 #[zvariant(signature = "dict")]
@@ -185,6 +294,8 @@ struct SelectedDevices {
     devices: BitFlags<DeviceType>,
     /// The selected streams if a ScreenCast portal is used on the same session
     streams: Option<Vec<Stream>>,
+    /// The token to restore the session with, if it is persisted.
+    restore_token: Option<String>,
 }
 
 /// The interface lets sandboxed applications create remote desktop sessions.
@@ -198,8 +309,12 @@ impl<'a> RemoteDesktop<'a> {
     /// Create a new instance of [`RemoteDesktop`].
     pub async fn new() -> Result<RemoteDesktop<'a>, Error> {
         let connection = session_connection().await?;
-        let proxy = zbus::ProxyBuilder::new_bare(&connection)
-            .interface("org.freedesktop.portal.RemoteDesktop")?
+        Self::with_connection(&connection).await
+    }
+
+    async fn with_connection(connection: &zbus::Connection) -> Result<RemoteDesktop<'a>, Error> {
+        let proxy = zbus::ProxyBuilder::new_bare(connection)
+            .interface(Portal::RemoteDesktop.as_interface_name())?
             .path(PATH)?
             .destination(DESTINATION)?
             .build()
@@ -223,18 +338,14 @@ impl<'a> RemoteDesktop<'a> {
     #[doc(alias = "xdp_portal_create_remote_desktop_session")]
     pub async fn create_session(&self) -> Result<Session<'a>, Error> {
         let options = CreateRemoteOptions::default();
-        let (session, proxy) = futures_util::try_join!(
-            call_request_method::<CreateSession, _>(
-                self.inner(),
-                &options.handle_token,
-                "CreateSession",
-                &options
-            )
-            .into_future(),
-            Session::from_unique_name(&options.session_handle_token).into_future()
-        )?;
-        assert_eq!(proxy.inner().path().as_str(), &session.session_handle);
-        Ok(proxy)
+        Session::create(
+            self.inner(),
+            "CreateSession",
+            &options.handle_token,
+            &options.session_handle_token,
+            &options,
+        )
+        .await
     }
 
     /// Select input devices to remote control.
@@ -285,6 +396,7 @@ impl<'a> RemoteDesktop<'a> {
         session: &Session<'_>,
         identifier: &WindowIdentifier,
     ) -> Result<(BitFlags<DeviceType>, Vec<Stream>), Error> {
+        check_window_kind(identifier)?;
         let options = StartRemoteOptions::default();
         let response: SelectedDevices = call_request_method(
             self.inner(),
@@ -634,6 +746,88 @@ impl<'a> RemoteDesktop<'a> {
         .await
     }
 
+    /// Types `text` by pressing and releasing the matching keys.
+    ///
+    /// The key codes are the Linux evdev ones of a US keyboard layout, the
+    /// text is only typed as expected if the compositor uses that layout too.
+    /// Only the printable ASCII characters, new lines and tabs can be typed;
+    /// nothing is typed if `text` contains another character.
+    ///
+    /// **Note** only works if [`DeviceType::Keyboard`] access was provided
+    /// after starting the session.
+    ///
+    /// # Arguments
+    ///
+    /// * `session` - A [`Session`], created with
+    ///   [`create_session()`][`RemoteDesktop::create_session`] and started.
+    /// * `text` - The text to type.
+    pub async fn type_text(&self, session: &Session<'_>, text: &str) -> Result<(), Error> {
+        let keycodes = text
+            .chars()
+            .map(us_keycode)
+            .collect::<Option<Vec<_>>>()
+            .ok_or(Error::ParseError(
+                "Text contains characters missing from the US keyboard layout",
+            ))?;
+        for (keycode, shift) in keycodes {
+            if shift {
+                self.notify_keyboard_keycode(session, KEY_LEFTSHIFT, KeyState::Pressed)
+                    .await?;
+            }
+            self.notify_keyboard_keycode(session, keycode, KeyState::Pressed)
+                .await?;
+            self.notify_keyboard_keycode(session, keycode, KeyState::Released)
+                .await?;
+            if shift {
+                self.notify_keyboard_keycode(session, KEY_LEFTSHIFT, KeyState::Released)
+                    .await?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Clicks with `button`, pressing and releasing it.
+    ///
+    /// **Note** only works if [`DeviceType::Pointer`] access was provided after
+    /// starting the session.
+    ///
+    /// # Arguments
+    ///
+    /// * `session` - A [`Session`], created with
+    ///   [`create_session()`][`RemoteDesktop::create_session`] and started.
+    /// * `button` - The button to click with.
+    pub async fn click(&self, session: &Session<'_>, button: PointerButton) -> Result<(), Error> {
+        self.notify_pointer_button(session, button.code(), KeyState::Pressed)
+            .await?;
+        self.notify_pointer_button(session, button.code(), KeyState::Released)
+            .await
+    }
+
+    /// Moves the pointer to the (x, y) position, in the logical coordinate
+    /// space of `stream`.
+    ///
+    /// A shortcut for
+    /// [`notify_pointer_motion_absolute()`][`RemoteDesktop::notify_pointer_motion_absolute`].
+    /// It requires the session to record a stream, with the ScreenCast portal.
+    ///
+    /// # Arguments
+    ///
+    /// * `session` - A [`Session`], created with
+    ///   [`create_session()`][`RemoteDesktop::create_session`] and started.
+    /// * `stream` - The PipeWire stream node the coordinate is relative to.
+    /// * `x` - The x coordinate.
+    /// * `y` - The y coordinate.
+    pub async fn move_to(
+        &self,
+        session: &Session<'_>,
+        stream: u32,
+        x: f64,
+        y: f64,
+    ) -> Result<(), Error> {
+        self.notify_pointer_motion_absolute(session, stream, x, y)
+            .await
+    }
+
     /// Available source types.
     ///
     /// # Specifications
@@ -647,3 +841,352 @@ impl<'a> RemoteDesktop<'a> {
             .map_err(From::from)
     }
 }
+
+/// Creates a remote desktop session that records the screen as well.
+///
+/// The devices and the sources are selected on the same [`Session`] before
+/// starting it, which is the way to get both with a single dialog.
+#[derive(Debug, Default)]
+pub struct RemoteDesktopScreencastRequest {
+    identifier: WindowIdentifier,
+    devices: SelectDevicesOptions,
+    sources: SelectSourcesOptions,
+}
+
+impl RemoteDesktopScreencastRequest {
+    /// Sets a window identifier.
+    #[must_use]
+    pub fn identifier(mut self, identifier: WindowIdentifier) -> Self {
+        self.identifier = identifier;
+        self
+    }
+
+    /// Sets the device types to request remote controlling of. Default is
+    /// all.
+    #[must_use]
+    pub fn device_types(mut self, types: BitFlags<DeviceType>) -> Self {
+        self.set_device_types(types);
+        self
+    }
+
+    pub fn set_device_types(&mut self, types: BitFlags<DeviceType>) {
+        self.devices.types = Some(types);
+    }
+
+    /// Sets the types of content to record.
+    #[must_use]
+    pub fn source_types(mut self, types: BitFlags<SourceType>) -> Self {
+        self.set_source_types(types);
+        self
+    }
+
+    pub fn set_source_types(&mut self, types: BitFlags<SourceType>) {
+        self.sources.types = Some(types);
+    }
+
+    /// Sets how the cursor will be drawn on the screen cast stream.
+    #[must_use]
+    pub fn cursor_mode(mut self, cursor_mode: CursorMode) -> Self {
+        self.set_cursor_mode(cursor_mode);
+        self
+    }
+
+    pub fn set_cursor_mode(&mut self, cursor_mode: CursorMode) {
+        self.sources.cursor_mode = Some(cursor_mode);
+    }
+
+    /// Sets whether to allow selecting multiple sources.
+    #[must_use]
+    pub fn multiple(mut self, multiple: bool) -> Self {
+        self.set_multiple(multiple);
+        self
+    }
+
+    pub fn set_multiple(&mut self, multiple: bool) {
+        self.sources.multiple = Some(multiple);
+    }
+
+    /// Sets how long the permission should be remembered, see
+    /// [`Screencast::select_sources`].
+    #[must_use]
+    pub fn persist_mode(mut self, persist_mode: PersistMode) -> Self {
+        self.set_persist_mode(persist_mode);
+        self
+    }
+
+    pub fn set_persist_mode(&mut self, persist_mode: PersistMode) {
+        self.sources.persist_mode = Some(persist_mode);
+    }
+
+    /// Sets the token returned by a previous [`build`][Self::build] to restore
+    /// its session without prompting the user, or unsets it.
+    #[must_use]
+    pub fn restore_token<'a>(mut self, token: impl Into<Option<&'a str>>) -> Self {
+        self.set_restore_token(token);
+        self
+    }
+
+    pub fn set_restore_token<'a>(&mut self, token: impl Into<Option<&'a str>>) {
+        self.sources.restore_token = token.into().map(ToOwned::to_owned);
+    }
+
+    /// Creates the session, selects the devices and the sources then starts
+    /// it.
+    ///
+    /// # Returns
+    ///
+    /// The started [`Session`], to be kept around for as long as it is used,
+    /// along with the devices and the streams the user granted access to, and
+    /// the token to restore the session with if it is persisted, see
+    /// [`RemoteDesktopScreencastRequest::persist_mode`].
+    pub async fn build<'a>(
+        self,
+    ) -> Result<
+        (
+            Session<'a>,
+            BitFlags<DeviceType>,
+            Vec<Stream>,
+            Option<String>,
+        ),
+        Error,
+    > {
+        let connection = session_connection().await?;
+        self.build_with_connection(&connection).await
+    }
+
+    async fn build_with_connection<'a>(
+        self,
+        connection: &zbus::Connection,
+    ) -> Result<
+        (
+            Session<'a>,
+            BitFlags<DeviceType>,
+            Vec<Stream>,
+            Option<String>,
+        ),
+        Error,
+    > {
+        check_window_kind(&self.identifier)?;
+        let remote_desktop = RemoteDesktop::with_connection(connection).await?;
+        let screencast = Screencast::with_connection(connection).await?;
+        let session = remote_desktop.create_session().await?;
+
+        call_basic_response_method(
+            remote_desktop.inner(),
+            &self.devices.handle_token,
+            "SelectDevices",
+            &(&session, &self.devices),
+        )
+        .await?;
+        call_basic_response_method(
+            screencast.inner(),
+            &self.sources.handle_token,
+            "SelectSources",
+            &(&session, &self.sources),
+        )
+        .await?;
+
+        let options = StartRemoteOptions::default();
+        let response: SelectedDevices = call_request_method(
+            remote_desktop.inner(),
+            &options.handle_token,
+            "Start",
+            &(&session, &self.identifier, &options),
+        )
+        .await?;
+        Ok((
+            session,
+            response.devices,
+            response.streams.unwrap_or_default(),
+            response.restore_token,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::us_keycode;
+
+    #[test]
+    fn ascii_keycodes() {
+        assert_eq!(us_keycode('a'), Some((30, false)));
+        assert_eq!(us_keycode('A'), Some((30, true)));
+        assert_eq!(us_keycode('q'), Some((16, false)));
+        assert_eq!(us_keycode('m'), Some((50, false)));
+        assert_eq!(us_keycode('1'), Some((2, false)));
+        assert_eq!(us_keycode('0'), Some((11, false)));
+        assert_eq!(us_keycode('!'), Some((2, true)));
+        assert_eq!(us_keycode('?'), Some((53, true)));
+        assert_eq!(us_keycode('\\'), Some((43, false)));
+        assert_eq!(us_keycode('|'), Some((43, true)));
+        assert_eq!(us_keycode('"'), Some((40, true)));
+        assert_eq!(us_keycode('~'), Some((41, true)));
+        assert_eq!(us_keycode(' '), Some((57, false)));
+        assert_eq!(us_keycode('\n'), Some((28, false)));
+        assert_eq!(us_keycode('é'), None);
+
+        // Every printable ASCII character can be typed
+        assert!((' '..='~').all(|c| us_keycode(c).is_some()));
+    }
+
+    // The tokio feature makes zbus expect a tokio UnixStream instead
+    #[cfg(not(feature = "tokio"))]
+    mod mock {
+        use std::collections::HashMap;
+
+        use futures_channel::mpsc::UnboundedSender;
+        use zbus::zvariant::{OwnedObjectPath, OwnedValue, Value};
+
+        use crate::helpers::{request_path, session_path};
+
+        // A method call: its name, the session it was made on and its options
+        pub type Call = (&'static str, String, HashMap<String, OwnedValue>);
+
+        async fn respond(
+            connection: &zbus::Connection,
+            options: &HashMap<String, OwnedValue>,
+            results: HashMap<&str, Value<'_>>,
+        ) -> zbus::fdo::Result<OwnedObjectPath> {
+            let handle_token = <&str>::try_from(&options["handle_token"]).unwrap();
+            let path = request_path(":1.42", handle_token).unwrap();
+            connection
+                .emit_signal(
+                    None::<zbus::names::BusName<'_>>,
+                    path.as_str(),
+                    "org.freedesktop.portal.Request",
+                    "Response",
+                    &(0u32, results),
+                )
+                .await?;
+            Ok(path)
+        }
+
+        pub struct RemoteDesktop(pub UnboundedSender<Call>);
+
+        #[zbus::dbus_interface(name = "org.freedesktop.portal.RemoteDesktop")]
+        impl RemoteDesktop {
+            async fn create_session(
+                &self,
+                #[zbus(connection)] connection: &zbus::Connection,
+                options: HashMap<String, OwnedValue>,
+            ) -> zbus::fdo::Result<OwnedObjectPath> {
+                let token = <&str>::try_from(&options["session_handle_token"]).unwrap();
+                let session = session_path(":1.42", token).unwrap();
+                let results = HashMap::from([("session_handle", Value::from(session.as_str()))]);
+                respond(connection, &options, results).await
+            }
+
+            async fn select_devices(
+                &self,
+                #[zbus(connection)] connection: &zbus::Connection,
+                session: OwnedObjectPath,
+                options: HashMap<String, OwnedValue>,
+            ) -> zbus::fdo::Result<OwnedObjectPath> {
+                let path = respond(connection, &options, HashMap::new()).await;
+                self.0
+                    .unbounded_send(("SelectDevices", session.to_string(), options))
+                    .unwrap();
+                path
+            }
+
+            async fn start(
+                &self,
+                #[zbus(connection)] connection: &zbus::Connection,
+                session: OwnedObjectPath,
+                _parent_window: &str,
+                options: HashMap<String, OwnedValue>,
+            ) -> zbus::fdo::Result<OwnedObjectPath> {
+                let results = HashMap::from([
+                    ("devices", Value::from(1u32)),
+                    ("restore_token", Value::from("6b4e3f1c-token")),
+                ]);
+                let path = respond(connection, &options, results).await;
+                self.0
+                    .unbounded_send(("Start", session.to_string(), options))
+                    .unwrap();
+                path
+            }
+        }
+
+        pub struct ScreenCast(pub UnboundedSender<Call>);
+
+        #[zbus::dbus_interface(name = "org.freedesktop.portal.ScreenCast")]
+        impl ScreenCast {
+            async fn select_sources(
+                &self,
+                #[zbus(connection)] connection: &zbus::Connection,
+                session: OwnedObjectPath,
+                options: HashMap<String, OwnedValue>,
+            ) -> zbus::fdo::Result<OwnedObjectPath> {
+                let path = respond(connection, &options, HashMap::new()).await;
+                self.0
+                    .unbounded_send(("SelectSources", session.to_string(), options))
+                    .unwrap();
+                path
+            }
+        }
+    }
+
+    #[cfg(not(feature = "tokio"))]
+    #[test]
+    fn combined_session_selects() {
+        use super::{DeviceType, RemoteDesktopScreencastRequest, SourceType};
+        use crate::desktop::screencast::PersistMode;
+
+        zbus::block_on(async {
+            let (sender, mut calls) = futures_channel::mpsc::unbounded();
+            let guid = zbus::Guid::generate();
+            let (server, client) = std::os::unix::net::UnixStream::pair().unwrap();
+            let (_server, client) = futures_util::try_join!(
+                zbus::ConnectionBuilder::unix_stream(server)
+                    .server(&guid)
+                    .p2p()
+                    .serve_at(crate::desktop::PATH, mock::RemoteDesktop(sender.clone()))
+                    .unwrap()
+                    .serve_at(crate::desktop::PATH, mock::ScreenCast(sender))
+                    .unwrap()
+                    .build(),
+                zbus::ConnectionBuilder::unix_stream(client)
+                    .p2p()
+                    .unique_name(":1.42")
+                    .unwrap()
+                    .build(),
+            )
+            .unwrap();
+
+            let (session, devices, streams, restore_token) =
+                RemoteDesktopScreencastRequest::default()
+                    .device_types(DeviceType::Keyboard.into())
+                    .source_types(SourceType::Window.into())
+                    .persist_mode(PersistMode::ExplicitlyRevoked)
+                    .restore_token("f2ee988d-token")
+                    .build_with_connection(&client)
+                    .await
+                    .unwrap();
+            assert_eq!(devices, DeviceType::Keyboard);
+            assert!(streams.is_empty());
+            assert_eq!(restore_token.as_deref(), Some("6b4e3f1c-token"));
+
+            let session_path = session.inner().path().to_string();
+            let (method, path, devices) = calls.try_recv().unwrap();
+            assert_eq!(
+                (method, path.as_str()),
+                ("SelectDevices", session_path.as_str())
+            );
+            assert_eq!(u32::try_from(&devices["types"]).unwrap(), 1);
+            let (method, path, sources) = calls.try_recv().unwrap();
+            assert_eq!(
+                (method, path.as_str()),
+                ("SelectSources", session_path.as_str())
+            );
+            assert_eq!(u32::try_from(&sources["types"]).unwrap(), 2);
+            assert_eq!(u32::try_from(&sources["persist_mode"]).unwrap(), 2);
+            assert_eq!(
+                <&str>::try_from(&sources["restore_token"]).unwrap(),
+                "f2ee988d-token"
+            );
+            let (method, path, _) = calls.try_recv().unwrap();
+            assert_eq!((method, path.as_str()), ("Start", session_path.as_str()));
+        });
+    }
+}
@@ -1,21 +1,30 @@
 use std::{
     collections::HashMap,
     fmt::{self, Debug},
+    future::Future,
     marker::PhantomData,
+    time::Duration,
 };
 
+use futures_util::future;
 use serde::{
     de::{self, Error as SeError, Visitor},
     ser::SerializeTuple,
     Deserialize, Deserializer, Serialize,
 };
-use zbus::zvariant::{ObjectPath, OwnedValue, Signature, Type};
+use zbus::{
+    names::BusName,
+    zvariant::{ObjectPath, OwnedValue, Signature, Type},
+};
 
-use super::DESTINATION;
+use super::{DESTINATION, PATH};
 use crate::{
     desktop::HandleToken,
-    helpers::{call_method, receive_signal, session_connection},
-    Error,
+    helpers::{
+        call_method, call_request_method, check_window_kind, receive_signal, request_path,
+        session_connection, with_cancellation, with_timeout,
+    },
+    Error, Portal, WindowIdentifier,
 };
 
 /// A typical response returned by the [`Request::receive_response`] signal
@@ -31,6 +40,20 @@ where
     Err(ResponseError),
 }
 
+impl<T> Response<T>
+where
+    T: for<'de> Deserialize<'de> + Type,
+{
+    /// The response as a [`Result`], failing with [`Error::Response`] if the
+    /// request was cancelled or failed.
+    pub fn into_result(self) -> Result<T, Error> {
+        match self {
+            Self::Ok(response) => Ok(response),
+            Self::Err(err) => Err(err.into()),
+        }
+    }
+}
+
 impl<T> Type for Response<T>
 where
     T: for<'de> Deserialize<'de> + Type,
@@ -195,33 +218,38 @@ impl From<ResponseError> for ResponseType {
 pub(crate) struct Request<'a>(zbus::Proxy<'a>);
 
 impl<'a> Request<'a> {
-    pub async fn new<P>(path: P) -> Result<Request<'a>, Error>
+    /// Create a request on the service `destination` reached through
+    /// `connection`.
+    pub async fn with_connection<P>(
+        connection: &zbus::Connection,
+        destination: BusName<'a>,
+        path: P,
+    ) -> Result<Request<'a>, Error>
     where
         P: TryInto<ObjectPath<'a>>,
         P::Error: Into<zbus::Error>,
     {
-        let connection = session_connection().await?;
-        let proxy = zbus::ProxyBuilder::new_bare(&connection)
+        let proxy = zbus::ProxyBuilder::new_bare(connection)
             .interface("org.freedesktop.portal.Request")?
             .path(path)?
-            .destination(DESTINATION)?
+            .destination(destination)?
             .build()
             .await?;
         Ok(Self(proxy))
     }
 
-    pub async fn from_unique_name(handle_token: &HandleToken) -> Result<Request<'a>, Error> {
-        let connection = session_connection().await?;
+    /// The request the service `destination` creates for a method called
+    /// through `connection` with `handle_token`.
+    pub async fn from_unique_name_with_connection(
+        connection: &zbus::Connection,
+        destination: BusName<'a>,
+        handle_token: &HandleToken,
+    ) -> Result<Request<'a>, Error> {
         let unique_name = connection.unique_name().unwrap();
-        let unique_identifier = unique_name.trim_start_matches(':').replace('.', "_");
-        let path = ObjectPath::try_from(format!(
-            "/org/freedesktop/portal/desktop/request/{}/{}",
-            unique_identifier, handle_token
-        ))
-        .unwrap();
+        let path = request_path(unique_name, &handle_token.to_string())?.into_inner();
         #[cfg(feature = "tracing")]
         tracing::info!("Creating a org.freedesktop.portal.Request {}", path);
-        Self::new(path).await
+        Self::with_connection(connection, destination, path).await
     }
 
     /// Get a reference to the underlying Proxy.
@@ -264,3 +292,106 @@ impl<'a> Debug for Request<'a> {
             .finish()
     }
 }
+
+/// The options of a portal method call returning a [`Request`].
+pub(crate) trait RequestOptions: Serialize + Type + Debug {
+    /// The token used to build the path of the [`Request`].
+    fn handle_token(&self) -> &HandleToken;
+
+    /// Use `handle_token` instead of the random one.
+    fn set_handle_token(&mut self, handle_token: HandleToken);
+
+    /// The `modal` option, for the dialogs that have one.
+    fn modal_mut(&mut self) -> Option<&mut Option<bool>> {
+        None
+    }
+}
+
+/// The state shared by the request builders: the parent window, the options
+/// and how to reach the portal.
+///
+/// It takes care of creating the proxy, calling the method and waiting for
+/// the response so the builders only have to provide the portal specific
+/// options.
+#[derive(Debug, Default)]
+pub(crate) struct RequestBuilder<O> {
+    pub identifier: WindowIdentifier,
+    pub options: O,
+    pub timeout: Option<Duration>,
+    /// A connection to use instead of the session bus one, to test against a
+    /// mock portal.
+    pub connection: Option<zbus::Connection>,
+    /// Whether `modal` was unset on purpose, to leave it to the backend even
+    /// with a parent window.
+    modal_unset: bool,
+}
+
+impl<O: RequestOptions> RequestBuilder<O> {
+    /// Sets the `modal` option, `None` leaving it to the backend.
+    pub fn set_modal(&mut self, modal: Option<bool>) {
+        if let Some(option) = self.options.modal_mut() {
+            *option = modal;
+            self.modal_unset = modal.is_none();
+        }
+    }
+
+    /// Makes the dialog modal if it has a parent window and `modal` wasn't
+    /// set, leaves it unset otherwise.
+    pub fn resolve_modal(&mut self) {
+        if self.modal_unset || matches!(self.identifier, WindowIdentifier::None) {
+            return;
+        }
+        if let Some(modal @ None) = self.options.modal_mut() {
+            *modal = Some(true);
+        }
+    }
+
+    /// Call `method_name` on `portal` and wait for its
+    /// response.
+    ///
+    /// `body` usually includes [`RequestBuilder::identifier`] and ends with
+    /// [`RequestBuilder::options`].
+    pub async fn send<R, B>(
+        &self,
+        portal: Portal,
+        method_name: &'static str,
+        body: &B,
+    ) -> Result<R, Error>
+    where
+        R: for<'de> Deserialize<'de> + Type + Debug,
+        B: Serialize + Type + Debug,
+    {
+        self.send_until(portal, method_name, body, future::pending::<()>())
+            .await
+    }
+
+    /// Like [`RequestBuilder::send`], but closes the request and fails with
+    /// [`Error::Cancelled`] once `cancel` resolves.
+    pub async fn send_until<R, B>(
+        &self,
+        portal: Portal,
+        method_name: &'static str,
+        body: &B,
+        cancel: impl Future,
+    ) -> Result<R, Error>
+    where
+        R: for<'de> Deserialize<'de> + Type + Debug,
+        B: Serialize + Type + Debug,
+    {
+        check_window_kind(&self.identifier)?;
+        let connection = match &self.connection {
+            Some(connection) => connection.clone(),
+            None => session_connection().await?,
+        };
+        let proxy = zbus::ProxyBuilder::new_bare(&connection)
+            .interface(portal.as_interface_name())?
+            .path(PATH)?
+            .destination(DESTINATION)?
+            .build()
+            .await?;
+        let handle_token = self.options.handle_token();
+        let response = call_request_method(&proxy, handle_token, method_name, body);
+        let response = with_cancellation(&proxy, cancel, handle_token, response);
+        with_timeout(&proxy, self.timeout, handle_token, response).await
+    }
+}
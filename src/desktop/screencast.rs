@@ -43,15 +43,17 @@ use std::{
 };
 
 use enumflags2::{bitflags, BitFlags};
-use futures_util::TryFutureExt;
 use serde::Deserialize;
 use serde_repr::{Deserialize_repr, Serialize_repr};
 use zbus::zvariant::{DeserializeDict, OwnedFd, SerializeDict, Type, Value};
 
 use super::{HandleToken, Session, DESTINATION, PATH};
 use crate::{
-    helpers::{call_basic_response_method, call_method, call_request_method, session_connection},
-    Error, WindowIdentifier,
+    helpers::{
+        call_basic_response_method, call_method, call_request_method, check_window_kind,
+        session_connection,
+    },
+    Error, Portal, WindowIdentifier,
 };
 
 #[bitflags]
@@ -76,6 +78,9 @@ pub enum SourceType {
 #[repr(u32)]
 #[doc(alias = "XdpCursorMode")]
 /// A bit flag for the possible cursor modes.
+///
+/// The backends don't all support every mode, check
+/// [`Screencast::available_cursor_modes`] before picking one.
 pub enum CursorMode {
     #[doc(alias = "XDP_CURSOR_MODE_HIDDEN")]
     /// The cursor is not part of the screen cast stream.
@@ -92,6 +97,11 @@ pub enum CursorMode {
 #[derive(Serialize_repr, PartialEq, Eq, Debug, Copy, Clone, Type)]
 #[doc(alias = "XdpPersistMode")]
 #[repr(u32)]
+/// How long the permission to record the selected sources is remembered.
+///
+/// When persisted, [`Screencast::start`] returns a restore token that can be
+/// passed to [`Screencast::select_sources`] to record the same sources again
+/// without asking the user.
 pub enum PersistMode {
     #[doc(alias = "XDP_PERSIST_MODE_NONE")]
     /// Do not persist.
@@ -123,17 +133,19 @@ struct CreateSessionOptions {
 #[derive(SerializeDict, Type, Debug, Default)]
 /// Specified options for a [`Screencast::select_sources`] request.
 #[zvariant(signature = "dict")]
-struct SelectSourcesOptions {
+pub(crate) struct SelectSourcesOptions {
     /// A string that will be used as the last element of the handle.
-    handle_token: HandleToken,
+    pub(crate) handle_token: HandleToken,
     /// What types of content to record.
-    types: Option<BitFlags<SourceType>>,
+    pub(crate) types: Option<BitFlags<SourceType>>,
     /// Whether to allow selecting multiple sources.
-    multiple: Option<bool>,
+    pub(crate) multiple: Option<bool>,
     /// Determines how the cursor will be drawn in the screen cast stream.
-    cursor_mode: Option<CursorMode>,
-    restore_token: Option<String>,
-    persist_mode: Option<PersistMode>,
+    pub(crate) cursor_mode: Option<CursorMode>,
+    /// The token of a previous session to restore.
+    pub(crate) restore_token: Option<String>,
+    /// How long the permission should be remembered.
+    pub(crate) persist_mode: Option<PersistMode>,
 }
 
 impl SelectSourcesOptions {
@@ -158,14 +170,18 @@ impl SelectSourcesOptions {
         self
     }
 
+    /// Sets how long the permission should be remembered.
     #[must_use]
     pub fn persist_mode(mut self, persist_mode: PersistMode) -> Self {
         self.persist_mode = Some(persist_mode);
         self
     }
 
-    pub fn set_restore_token(&mut self, token: &str) {
-        self.restore_token = Some(token.to_owned());
+    /// Sets the token of a previous session to restore, or unsets it.
+    #[must_use]
+    pub fn restore_token<'a>(mut self, token: impl Into<Option<&'a str>>) -> Self {
+        self.restore_token = token.into().map(ToOwned::to_owned);
+        self
     }
 }
 
@@ -177,16 +193,6 @@ struct StartCastOptions {
     handle_token: HandleToken,
 }
 
-#[derive(DeserializeDict, Type, Debug)]
-/// A response to a [`Screencast::create_session`] request.
-#[zvariant(signature = "dict")]
-struct CreateSession {
-    // TODO: investigate why this doesn't return an ObjectPath
-    // replace with an ObjectPath once https://github.com/flatpak/xdg-desktop-portal/pull/609's merged
-    /// A string that will be used as the last element of the session handle.
-    session_handle: String,
-}
-
 #[derive(DeserializeDict, Type)]
 /// A response to a [`Screencast::start`] request.
 #[zvariant(signature = "dict")]
@@ -274,8 +280,14 @@ impl<'a> Screencast<'a> {
     /// Create a new instance of [`Screencast`].
     pub async fn new() -> Result<Screencast<'a>, Error> {
         let connection = session_connection().await?;
-        let proxy = zbus::ProxyBuilder::new_bare(&connection)
-            .interface("org.freedesktop.portal.ScreenCast")?
+        Self::with_connection(&connection).await
+    }
+
+    pub(crate) async fn with_connection(
+        connection: &zbus::Connection,
+    ) -> Result<Screencast<'a>, Error> {
+        let proxy = zbus::ProxyBuilder::new_bare(connection)
+            .interface(Portal::ScreenCast.as_interface_name())?
             .path(PATH)?
             .destination(DESTINATION)?
             .build()
@@ -297,18 +309,14 @@ impl<'a> Screencast<'a> {
     #[doc(alias = "xdp_portal_create_screencast_session")]
     pub async fn create_session(&self) -> Result<Session<'a>, Error> {
         let options = CreateSessionOptions::default();
-        let (session, proxy) = futures_util::try_join!(
-            call_request_method::<CreateSession, _>(
-                self.inner(),
-                &options.handle_token,
-                "CreateSession",
-                &options
-            )
-            .into_future(),
-            Session::from_unique_name(&options.session_handle_token).into_future(),
-        )?;
-        assert_eq!(proxy.inner().path().as_str(), &session.session_handle);
-        Ok(proxy)
+        Session::create(
+            self.inner(),
+            "CreateSession",
+            &options.handle_token,
+            &options.session_handle_token,
+            &options,
+        )
+        .await
     }
 
     /// Open a file descriptor to the PipeWire remote where the screen cast
@@ -351,6 +359,22 @@ impl<'a> Screencast<'a> {
     ///   stream.
     /// * `types` - Sets the types of content to record.
     /// * `multiple`- Sets whether to allow selecting multiple sources.
+    /// * `restore_token` - The token returned by [`Screencast::start`] for a
+    ///   previous session, to record the same sources again.
+    /// * `persist_mode` - Sets how long the permission should be remembered.
+    ///
+    /// # Restoring a session
+    ///
+    /// With a [`PersistMode`] other than [`PersistMode::DoNot`], the portal may
+    /// return a restore token once the session is started. Passing it back here
+    /// for a later session lets the portal skip the sources selection dialog,
+    /// as long as the sources are still available and the user didn't revoke
+    /// the permission. Otherwise, the user is asked again as usual, the token
+    /// is only a hint.
+    ///
+    /// A token can only be used once: each started session returns a new one,
+    /// which replaces the previous one and has to be stored again. Persisting
+    /// a restored session requires passing a `persist_mode` again as well.
     ///
     /// # Specifications
     ///
@@ -365,14 +389,12 @@ impl<'a> Screencast<'a> {
         restore_token: Option<&str>,
         persist_mode: PersistMode,
     ) -> Result<(), Error> {
-        let mut options = SelectSourcesOptions::default()
+        let options = SelectSourcesOptions::default()
             .cursor_mode(cursor_mode)
             .multiple(multiple)
             .types(types)
-            .persist_mode(persist_mode);
-        if let Some(token) = restore_token {
-            options.set_restore_token(token);
-        }
+            .persist_mode(persist_mode)
+            .restore_token(restore_token);
         call_basic_response_method(
             self.inner(),
             &options.handle_token,
@@ -397,7 +419,11 @@ impl<'a> Screencast<'a> {
     ///
     /// # Return
     ///
-    /// A list of [`Stream`] and an optional restore token.
+    /// A list of [`Stream`] and an optional restore token, set when the
+    /// sources were selected with a [`PersistMode`] other than
+    /// [`PersistMode::DoNot`] and the portal accepted to persist them. It is to
+    /// be stored by the application to restore the session later on with
+    /// [`Screencast::select_sources`].
     ///
     /// # Specifications
     ///
@@ -408,6 +434,7 @@ impl<'a> Screencast<'a> {
         session: &Session<'_>,
         identifier: &WindowIdentifier,
     ) -> Result<(Vec<Stream>, Option<String>), Error> {
+        check_window_kind(identifier)?;
         let options = StartCastOptions::default();
         let streams: Streams = call_request_method(
             self.inner(),
@@ -445,3 +472,75 @@ impl<'a> Screencast<'a> {
             .map_err(From::from)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use byteorder::LE;
+    use zbus::zvariant::{from_slice, to_bytes, EncodingContext as Context, OwnedValue, Value};
+
+    use super::{CursorMode, PersistMode, SelectSourcesOptions, SourceType, Streams};
+
+    #[test]
+    fn serialize_persist_options() {
+        let ctxt = Context::<LE>::new_dbus(0);
+
+        let options = SelectSourcesOptions::default().cursor_mode(CursorMode::Hidden);
+        let encoded = to_bytes(ctxt, &options).unwrap();
+        let decoded: HashMap<String, OwnedValue> = from_slice(&encoded, ctxt).unwrap();
+        assert!(!decoded.contains_key("persist_mode"));
+        assert!(!decoded.contains_key("restore_token"));
+
+        let options = options
+            .types(SourceType::Monitor.into())
+            .persist_mode(PersistMode::ExplicitlyRevoked)
+            .restore_token("6b4e3f1c-token");
+        let encoded = to_bytes(ctxt, &options).unwrap();
+        let decoded: HashMap<String, OwnedValue> = from_slice(&encoded, ctxt).unwrap();
+        assert_eq!(u32::try_from(&decoded["persist_mode"]).unwrap(), 2);
+        assert_eq!(
+            <&str>::try_from(&decoded["restore_token"]).unwrap(),
+            "6b4e3f1c-token"
+        );
+        assert_eq!(u32::try_from(&decoded["types"]).unwrap(), 1);
+    }
+
+    #[test]
+    fn serialize_cursor_mode() {
+        let ctxt = Context::<LE>::new_dbus(0);
+
+        for (cursor_mode, value) in [
+            (CursorMode::Hidden, 1),
+            (CursorMode::Embedded, 2),
+            (CursorMode::Metadata, 4),
+        ] {
+            let options = SelectSourcesOptions::default().cursor_mode(cursor_mode);
+            let encoded = to_bytes(ctxt, &options).unwrap();
+            let decoded: HashMap<String, OwnedValue> = from_slice(&encoded, ctxt).unwrap();
+            assert_eq!(u32::try_from(&decoded["cursor_mode"]).unwrap(), value);
+        }
+    }
+
+    #[test]
+    fn parse_restore_token() {
+        let ctxt = Context::<LE>::new_dbus(0);
+        let streams: Vec<(u32, HashMap<&str, Value<'_>>)> = vec![(
+            42,
+            HashMap::from([
+                ("position", Value::from((0, 0))),
+                ("size", Value::from((1920, 1080))),
+            ]),
+        )];
+        let response = HashMap::from([
+            ("streams", Value::from(streams)),
+            ("restore_token", Value::from("6b4e3f1c-token")),
+        ]);
+        let encoded = to_bytes(ctxt, &response).unwrap();
+        let streams: Streams = from_slice(&encoded, ctxt).unwrap();
+        assert_eq!(streams.restore_token.as_deref(), Some("6b4e3f1c-token"));
+        assert_eq!(streams.streams.len(), 1);
+        assert_eq!(streams.streams[0].pipe_wire_node_id(), 42);
+        assert_eq!(streams.streams[0].size(), Some((1920, 1080)));
+    }
+}
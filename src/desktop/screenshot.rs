@@ -20,6 +20,46 @@
 //! }
 //! ```
 //!
+//! ## Taking a screenshot of every monitor
+//!
+//! The portal returns a single image covering all the monitors. The monitors
+//! layout, as reported by a screen cast session, tells which part of it
+//! belongs to which monitor.
+//!
+//! ```rust,no_run
+//! use ashpd::{
+//!     desktop::{
+//!         screencast::{CursorMode, PersistMode, Screencast, SourceType},
+//!         screenshot::ScreenshotRequest,
+//!     },
+//!     WindowIdentifier,
+//! };
+//!
+//! async fn run() -> ashpd::Result<()> {
+//!     let proxy = Screencast::new().await?;
+//!     let session = proxy.create_session().await?;
+//!     proxy
+//!         .select_sources(
+//!             &session,
+//!             CursorMode::Hidden,
+//!             SourceType::Monitor.into(),
+//!             true,
+//!             None,
+//!             PersistMode::DoNot,
+//!         )
+//!         .await?;
+//!     let (streams, _) = proxy.start(&session, &WindowIdentifier::default()).await?;
+//!
+//!     let (uri, monitors) = ScreenshotRequest::default()
+//!         .build_with_monitors(&streams)
+//!         .await?;
+//!     for monitor in monitors {
+//!         println!("{}: {:?} of {}", monitor.name(), monitor.region(), uri);
+//!     }
+//!     Ok(())
+//! }
+//! ```
+//!
 //! ## Picking a color
 //!
 //! ```rust,no_run
@@ -32,15 +72,24 @@
 //!     Ok(())
 //! }
 //! ```
-use std::fmt::Debug;
+use std::{
+    fmt::Debug,
+    path::Path,
+    time::{Duration, SystemTime},
+};
 
+use percent_encoding::percent_decode_str;
 use url::Url;
 use zbus::zvariant::{DeserializeDict, SerializeDict, Type};
 
-use super::{HandleToken, DESTINATION, PATH};
+use super::{
+    request::{RequestBuilder, RequestOptions},
+    screencast::{SourceType, Stream},
+    Color, HandleToken,
+};
 use crate::{
-    helpers::{call_request_method, session_connection},
-    Error, WindowIdentifier,
+    helpers::{ensure_window, read_file, write_file},
+    Error, Portal, WindowIdentifier,
 };
 
 #[derive(SerializeDict, Type, Debug, Default)]
@@ -51,6 +100,20 @@ struct ScreenshotOptions {
     interactive: Option<bool>,
 }
 
+impl RequestOptions for ScreenshotOptions {
+    fn handle_token(&self) -> &HandleToken {
+        &self.handle_token
+    }
+
+    fn set_handle_token(&mut self, handle_token: HandleToken) {
+        self.handle_token = handle_token;
+    }
+
+    fn modal_mut(&mut self) -> Option<&mut Option<bool>> {
+        Some(&mut self.modal)
+    }
+}
+
 #[derive(DeserializeDict, Type)]
 #[zvariant(signature = "dict")]
 struct ScreenshotResponse {
@@ -63,10 +126,83 @@ impl Debug for ScreenshotResponse {
     }
 }
 
+/// A screenshot along with the time it was taken at, see
+/// [`ScreenshotRequest::build_full`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Screenshot {
+    uri: Url,
+    taken_at: SystemTime,
+}
+
+impl Screenshot {
+    /// The portal doesn't report when the screenshot was taken, the
+    /// modification time of the file is used instead, or `received_at` if
+    /// it is not a local file.
+    fn new(response: ScreenshotResponse, received_at: SystemTime) -> Self {
+        let taken_at = response
+            .uri
+            .to_file_path()
+            .ok()
+            .and_then(|path| std::fs::metadata(path).ok())
+            .and_then(|metadata| metadata.modified().ok())
+            .unwrap_or(received_at);
+        Self {
+            uri: response.uri,
+            taken_at,
+        }
+    }
+
+    /// The location of the screenshot.
+    pub fn uri(&self) -> &Url {
+        &self.uri
+    }
+
+    /// When the screenshot was taken.
+    pub fn taken_at(&self) -> SystemTime {
+        self.taken_at
+    }
+
+    /// The file name of the screenshot, percent-decoded, like
+    /// `Screenshot from 2022-10-10.png`.
+    ///
+    /// Useful as the suggested name of a "save as" dialog. Invalid UTF-8 is
+    /// replaced by `U+FFFD`. Returns [`None`] if the location has no file
+    /// name.
+    pub fn file_name(&self) -> Option<String> {
+        let segment = self.uri.path_segments()?.next_back()?;
+        let file_name = percent_decode_str(segment).decode_utf8_lossy();
+        (!file_name.is_empty()).then(|| file_name.into_owned())
+    }
+
+    /// The extension of [`Screenshot::file_name`], without the leading dot,
+    /// like `png`.
+    pub fn extension(&self) -> Option<String> {
+        let file_name = self.file_name()?;
+        Path::new(&file_name)
+            .extension()
+            .map(|extension| extension.to_string_lossy().into_owned())
+    }
+}
+
 #[derive(SerializeDict, Type, Debug, Default)]
 #[zvariant(signature = "dict")]
 struct ColorOptions {
     handle_token: HandleToken,
+    modal: Option<bool>,
+}
+
+impl RequestOptions for ColorOptions {
+    fn handle_token(&self) -> &HandleToken {
+        &self.handle_token
+    }
+
+    fn set_handle_token(&mut self, handle_token: HandleToken) {
+        self.handle_token = handle_token;
+    }
+
+    fn modal_mut(&mut self) -> Option<&mut Option<bool>> {
+        Some(&mut self.modal)
+    }
 }
 
 #[derive(DeserializeDict, Clone, Copy, PartialEq, Type)]
@@ -75,23 +211,74 @@ struct ColorOptions {
 /// **Note** the values are normalized.
 #[zvariant(signature = "dict")]
 pub struct ColorResponse {
-    color: [f64; 3],
+    color: Color,
 }
 
 impl ColorResponse {
+    #[cfg(feature = "mock")]
+    /// Creates a [`ColorResponse`], to fake the portal response in tests.
+    ///
+    /// Requires the `mock` feature.
+    ///
+    /// ```rust
+    /// use ashpd::desktop::screenshot::ColorResponse;
+    ///
+    /// let color = ColorResponse::new(1.0, 0.5, 0.0);
+    /// assert_eq!(color.green(), 0.5);
+    /// ```
+    pub fn new(red: f64, green: f64, blue: f64) -> Self {
+        Self {
+            color: Color::new(red, green, blue),
+        }
+    }
+
+    #[cfg(feature = "mock")]
+    /// Creates a [`ColorResponse`] from its 8-bit components, to fake the
+    /// portal response in tests.
+    ///
+    /// Requires the `mock` feature.
+    pub fn from_rgb8(rgb: [u8; 3]) -> Self {
+        Self {
+            color: Color::from_rgb8(rgb),
+        }
+    }
+
+    #[cfg(feature = "mock")]
+    /// Creates a [`ColorResponse`] from a `#rrggbb` or `#rgb` color, e.g. a
+    /// stored one, see [`Color::from_hex`].
+    ///
+    /// Requires the `mock` feature.
+    ///
+    /// ```rust
+    /// use ashpd::desktop::screenshot::ColorResponse;
+    ///
+    /// let color = ColorResponse::from_hex("#ff0000").unwrap();
+    /// assert_eq!(color.red(), 1.0);
+    /// ```
+    pub fn from_hex(hex: &str) -> Result<Self, Error> {
+        Ok(Self {
+            color: Color::from_hex(hex)?,
+        })
+    }
+
     /// Red.
     pub fn red(&self) -> f64 {
-        self.color[0]
+        self.color.red()
     }
 
     /// Green.
     pub fn green(&self) -> f64 {
-        self.color[1]
+        self.color.green()
     }
 
     /// Blue.
     pub fn blue(&self) -> f64 {
-        self.color[2]
+        self.color.blue()
+    }
+
+    /// The picked color.
+    pub fn color(&self) -> Color {
+        self.color
     }
 
     /// Creates a new builder-pattern struct instance to construct
@@ -103,21 +290,45 @@ impl ColorResponse {
     }
 }
 
+impl From<ColorResponse> for Color {
+    fn from(color: ColorResponse) -> Self {
+        color.color
+    }
+}
+
 #[cfg(feature = "gtk3")]
 impl From<ColorResponse> for gtk3::gdk::RGBA {
     fn from(color: ColorResponse) -> Self {
-        gtk3::gdk::RGBA::new(color.red(), color.green(), color.blue(), 1.0)
+        color.color.into()
     }
 }
 
 #[cfg(feature = "gtk4")]
 impl From<ColorResponse> for gtk4::gdk::RGBA {
     fn from(color: ColorResponse) -> Self {
-        gtk4::gdk::RGBA::builder()
-            .red(color.red() as f32)
-            .green(color.green() as f32)
-            .blue(color.blue() as f32)
-            .build()
+        color.color.into()
+    }
+}
+
+#[cfg(feature = "rgb")]
+impl From<ColorResponse> for rgb::RGB<f64> {
+    fn from(color: ColorResponse) -> Self {
+        color.color.into()
+    }
+}
+
+#[cfg(feature = "rgb")]
+/// See the conversion of [`Color`] into [`rgb::RGB8`].
+impl From<ColorResponse> for rgb::RGB8 {
+    fn from(color: ColorResponse) -> Self {
+        color.color.into()
+    }
+}
+
+#[cfg(feature = "palette")]
+impl From<ColorResponse> for palette::Srgb<f64> {
+    fn from(color: ColorResponse) -> Self {
+        color.color.into()
     }
 }
 
@@ -142,109 +353,95 @@ impl std::fmt::Display for ColorResponse {
     }
 }
 
-#[derive(Debug)]
-#[doc(alias = "org.freedesktop.portal.Screenshot")]
-struct ScreenshotProxy<'a>(zbus::Proxy<'a>);
+#[derive(Debug, Default)]
+#[doc(alias = "xdp_portal_pick_color")]
+/// A [builder-pattern] type to construct [`ColorResponse`].
+///
+/// [builder-pattern]: https://doc.rust-lang.org/1.0.0/style/ownership/builders.html
+pub struct ColorRequest {
+    request: RequestBuilder<ColorOptions>,
+}
 
-impl<'a> ScreenshotProxy<'a> {
-    /// Create a new instance of [`ScreenshotProxy`].
-    pub async fn new() -> Result<ScreenshotProxy<'a>, Error> {
-        let connection = session_connection().await?;
-        let proxy = zbus::ProxyBuilder::new_bare(&connection)
-            .interface("org.freedesktop.portal.Screenshot")?
-            .path(PATH)?
-            .destination(DESTINATION)?
-            .build()
-            .await?;
-        Ok(Self(proxy))
+impl ColorRequest {
+    #[must_use]
+    /// Sets a window identifier.
+    pub fn identifier(mut self, identifier: WindowIdentifier) -> Self {
+        self.set_identifier(identifier);
+        self
     }
 
-    /// Get a reference to the underlying Proxy.
-    pub fn inner(&self) -> &zbus::Proxy<'_> {
-        &self.0
+    pub fn set_identifier(&mut self, identifier: WindowIdentifier) {
+        self.request.identifier = identifier;
     }
 
-    /// Obtains the color of a single pixel.
-    ///
-    /// # Arguments
-    ///
-    /// * `identifier` - Identifier for the application window.
+    /// Sets whether the dialog should be a modal.
     ///
-    /// # Specifications
+    /// By default, the dialog is modal if a parent window was set with
+    /// [`ColorRequest::identifier`], the option is left unset for the
+    /// backend to decide otherwise. `None` leaves it to the backend even with a
+    /// parent window, use `false` for a non-modal dialog.
     ///
-    /// See also [`PickColor`](https://flatpak.github.io/xdg-desktop-portal/index.html#gdbus-method-org-freedesktop-portal-Screenshot.PickColor).
-    #[doc(alias = "PickColor")]
-    #[doc(alias = "xdp_portal_pick_color")]
-    pub async fn pick_color(
-        &self,
-        identifier: &WindowIdentifier,
-        options: ColorOptions,
-    ) -> Result<ColorResponse, Error> {
-        call_request_method(
-            self.inner(),
-            &options.handle_token,
-            "PickColor",
-            &(&identifier, &options),
-        )
-        .await
+    /// **Note** this is not part of the `PickColor` specifications yet,
+    /// backends that don't support it ignore the option.
+    #[must_use]
+    pub fn modal(mut self, modal: impl Into<Option<bool>>) -> Self {
+        self.set_modal(modal);
+        self
     }
 
-    /// Takes a screenshot.
-    ///
-    /// # Arguments
-    ///
-    /// * `identifier` - Identifier for the application window.
-    /// * `interactive` - Sets whether the dialog should offer customization
-    ///   before a screenshot or not.
-    /// * `modal` - Sets whether the dialog should be a modal.
-    ///
-    /// # Returns
-    ///
-    /// The screenshot URI.
-    ///
-    /// # Specifications
+    /// Sets whether the dialog should be a modal, or unsets it with `None`.
+    pub fn set_modal(&mut self, modal: impl Into<Option<bool>>) {
+        self.request.set_modal(modal.into());
+    }
+
+    /// Sets how long to wait for the user to respond before closing the
+    /// dialog and failing with [`Error::Timeout`].
     ///
-    /// See also [`Screenshot`](https://flatpak.github.io/xdg-desktop-portal/index.html#gdbus-method-org-freedesktop-portal-Screenshot.Screenshot).
-    #[doc(alias = "Screenshot")]
-    #[doc(alias = "xdp_portal_take_screenshot")]
-    pub async fn screenshot(
-        &self,
-        identifier: &WindowIdentifier,
-        options: ScreenshotOptions,
-    ) -> Result<url::Url, Error> {
-        let response: ScreenshotResponse = call_request_method(
-            self.inner(),
-            &options.handle_token,
-            "Screenshot",
-            &(&identifier, &options),
-        )
-        .await?;
-        Ok(response.uri)
+    /// By default, there is no timeout.
+    #[must_use]
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.set_timeout(timeout);
+        self
     }
-}
 
-#[derive(Debug, Default)]
-#[doc(alias = "xdp_portal_pick_color")]
-/// A [builder-pattern] type to construct [`ColorResponse`].
-///
-/// [builder-pattern]: https://doc.rust-lang.org/1.0.0/style/ownership/builders.html
-pub struct ColorRequest {
-    identifier: WindowIdentifier,
-    options: ColorOptions,
-}
+    pub fn set_timeout(&mut self, timeout: Duration) {
+        self.request.timeout = Some(timeout);
+    }
 
-impl ColorRequest {
+    /// Sets the token of the request instead of a random one, e.g. to find
+    /// the request in the application logs and the D-Bus traces.
+    ///
+    /// The token is used verbatim in the path of the request, see
+    /// [`request_path`](crate::request_path).
     #[must_use]
-    /// Sets a window identifier.
-    pub fn identifier(mut self, identifier: WindowIdentifier) -> Self {
-        self.identifier = identifier;
+    pub fn handle_token(mut self, handle_token: HandleToken) -> Self {
+        self.set_handle_token(handle_token);
         self
     }
 
+    pub fn set_handle_token(&mut self, handle_token: HandleToken) {
+        self.request.options.set_handle_token(handle_token);
+    }
+
     /// Build the [`ColorResponse`].
-    pub async fn build(self) -> Result<ColorResponse, Error> {
-        let proxy = ScreenshotProxy::new().await?;
-        proxy.pick_color(&self.identifier, self.options).await
+    ///
+    /// Fails with [`Error::NoWindow`] if there is neither a parent window nor
+    /// a display to show the color picker on.
+    ///
+    /// # Specifications
+    ///
+    /// See also [`PickColor`](https://flatpak.github.io/xdg-desktop-portal/index.html#gdbus-method-org-freedesktop-portal-Screenshot.PickColor).
+    #[doc(alias = "PickColor")]
+    pub async fn build(mut self) -> Result<ColorResponse, Error> {
+        ensure_window(&self.request.identifier)?;
+        self.request.resolve_modal();
+        self.request
+            .send(
+                Portal::Screenshot,
+                "PickColor",
+                &(&self.request.identifier, &self.request.options),
+            )
+            .await
     }
 }
 
@@ -254,35 +451,44 @@ impl ColorRequest {
 ///
 /// [builder-pattern]: https://doc.rust-lang.org/1.0.0/style/ownership/builders.html
 pub struct ScreenshotRequest {
-    options: ScreenshotOptions,
-    identifier: WindowIdentifier,
+    request: RequestBuilder<ScreenshotOptions>,
 }
 
 impl ScreenshotRequest {
     #[must_use]
     /// Sets a window identifier.
     pub fn identifier(mut self, identifier: WindowIdentifier) -> Self {
-        self.identifier = identifier;
+        self.set_identifier(identifier);
         self
     }
 
     pub fn set_identifier(&mut self, identifier: WindowIdentifier) {
-        self.identifier = identifier;
+        self.request.identifier = identifier;
     }
 
     /// Sets whether the dialog should be a modal.
+    ///
+    /// By default, the dialog is modal if a parent window was set with
+    /// [`ScreenshotRequest::identifier`], the option is left unset for the
+    /// backend to decide otherwise. `None` leaves it to the backend even with a
+    /// parent window, use `false` for a non-modal dialog.
     #[must_use]
-    pub fn modal(mut self, modal: bool) -> Self {
+    pub fn modal(mut self, modal: impl Into<Option<bool>>) -> Self {
         self.set_modal(modal);
         self
     }
 
-    pub fn set_modal(&mut self, modal: bool) {
-        self.options.modal = Some(modal);
+    /// Sets whether the dialog should be a modal, or unsets it with `None`.
+    pub fn set_modal(&mut self, modal: impl Into<Option<bool>>) {
+        self.request.set_modal(modal.into());
     }
 
     /// Sets whether the dialog should offer customization before a screenshot
     /// or not.
+    ///
+    /// **Note** a non-interactive screenshot doesn't show any dialog once the
+    /// user has granted the permission, which is then remembered by the
+    /// permission store.
     #[must_use]
     pub fn interactive(mut self, interactive: bool) -> Self {
         self.set_interactive(interactive);
@@ -290,12 +496,540 @@ impl ScreenshotRequest {
     }
 
     pub fn set_interactive(&mut self, interactive: bool) {
-        self.options.interactive = Some(interactive);
+        self.request.options.interactive = Some(interactive);
+    }
+
+    /// Sets how long to wait for the user to respond before closing the
+    /// dialog and failing with [`Error::Timeout`].
+    ///
+    /// By default, there is no timeout.
+    #[must_use]
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.set_timeout(timeout);
+        self
+    }
+
+    pub fn set_timeout(&mut self, timeout: Duration) {
+        self.request.timeout = Some(timeout);
+    }
+
+    /// Sets the token of the request instead of a random one, e.g. to find
+    /// the request in the application logs and the D-Bus traces.
+    ///
+    /// The token is used verbatim in the path of the request, see
+    /// [`request_path`](crate::request_path).
+    #[must_use]
+    pub fn handle_token(mut self, handle_token: HandleToken) -> Self {
+        self.set_handle_token(handle_token);
+        self
+    }
+
+    pub fn set_handle_token(&mut self, handle_token: HandleToken) {
+        self.request.options.set_handle_token(handle_token);
     }
 
     /// Build the [`Url`].
+    ///
+    /// Use [`ScreenshotRequest::build_full`] for the file name and extension
+    /// of the screenshot as well.
+    ///
+    /// Fails with [`Error::NoWindow`] for interactive screenshots if there is
+    /// neither a parent window nor a display to show the dialog on.
+    ///
+    /// # Specifications
+    ///
+    /// See also [`Screenshot`](https://flatpak.github.io/xdg-desktop-portal/index.html#gdbus-method-org-freedesktop-portal-Screenshot.Screenshot).
+    #[doc(alias = "Screenshot")]
     pub async fn build(self) -> Result<Url, Error> {
-        let proxy = ScreenshotProxy::new().await?;
-        proxy.screenshot(&self.identifier, self.options).await
+        Ok(self.send().await?.uri)
+    }
+
+    /// Build the [`Screenshot`], telling when it was taken and its file name
+    /// as well.
+    ///
+    /// **Note** the portal doesn't report the geometry of the screenshot, use
+    /// [`ScreenshotRequest::build_with_monitors`] for the layout of the
+    /// monitors it covers.
+    ///
+    /// ```rust,no_run
+    /// use ashpd::desktop::screenshot::ScreenshotRequest;
+    ///
+    /// async fn run() -> ashpd::Result<()> {
+    ///     let screenshot = ScreenshotRequest::default().build_full().await?;
+    ///     println!("{} taken at {:?}", screenshot.uri(), screenshot.taken_at());
+    ///     if let Some(file_name) = screenshot.file_name() {
+    ///         println!("Suggested name: {}", file_name);
+    ///     }
+    ///     Ok(())
+    /// }
+    /// ```
+    pub async fn build_full(self) -> Result<Screenshot, Error> {
+        let response = self.send().await?;
+        Ok(Screenshot::new(response, SystemTime::now()))
+    }
+
+    async fn send(mut self) -> Result<ScreenshotResponse, Error> {
+        if self.request.options.interactive == Some(true) {
+            ensure_window(&self.request.identifier)?;
+        }
+        self.request.resolve_modal();
+        self.request
+            .send(
+                Portal::Screenshot,
+                "Screenshot",
+                &(&self.request.identifier, &self.request.options),
+            )
+            .await
+    }
+}
+
+impl ScreenshotRequest {
+    /// Build the [`Url`] along with the layout of the monitors it covers.
+    ///
+    /// The portal provides a single image of all the monitors, so this doesn't
+    /// crop it. Instead, the monitors are taken from the `streams` of a screen
+    /// cast session, which are the only source of their geometry, and
+    /// [`MonitorInfo::region`] tells which pixels of the image belong to each
+    /// of them. Streams that aren't monitors or lack a position or size are
+    /// skipped.
+    pub async fn build_with_monitors(
+        self,
+        streams: &[Stream],
+    ) -> Result<(Url, Vec<MonitorInfo>), Error> {
+        let uri = self.build().await?;
+        let monitors = streams
+            .iter()
+            .filter_map(MonitorInfo::from_stream)
+            .collect();
+        Ok((uri, monitors))
+    }
+
+    /// Takes the screenshot and saves it to `path`.
+    ///
+    /// The image is first written next to `path` then moved over it, so
+    /// `path` is either left untouched or replaced by the complete
+    /// screenshot. The file saved by the portal is kept. Fails with
+    /// [`Error::IO`] if the portal saved the screenshot to a non-local
+    /// location and with [`Error::File`] if it can't be read or written.
+    ///
+    /// ```rust,no_run
+    /// use ashpd::desktop::screenshot::ScreenshotRequest;
+    ///
+    /// async fn run() -> ashpd::Result<()> {
+    ///     ScreenshotRequest::default()
+    ///         .interactive(true)
+    ///         .save_to("/home/user/screenshot.png")
+    ///         .await
+    /// }
+    /// ```
+    pub async fn save_to(self, path: impl AsRef<Path>) -> Result<(), Error> {
+        let uri = self.build().await?;
+        save_screenshot(&uri, path.as_ref()).await
+    }
+
+    /// Takes the screenshot and reads the image, e.g. for the application to
+    /// copy it to the clipboard.
+    ///
+    /// The portal can't copy the screenshot to the clipboard itself, it only
+    /// saves it to a file, which is kept. Fails like
+    /// [`ScreenshotRequest::save_to`] if the file can't be read.
+    ///
+    /// ```rust,no_run
+    /// use ashpd::desktop::screenshot::ScreenshotRequest;
+    ///
+    /// async fn run() -> ashpd::Result<()> {
+    ///     let png = ScreenshotRequest::default().build_bytes().await?;
+    ///     // Hand `png` to the clipboard of the toolkit, as `image/png`
+    ///     Ok(())
+    /// }
+    /// ```
+    pub async fn build_bytes(self) -> Result<Vec<u8>, Error> {
+        let uri = self.build().await?;
+        read_screenshot(&uri).await
+    }
+}
+
+/// Reads the screenshot at `uri`, which has to be a local file.
+async fn read_screenshot(uri: &Url) -> Result<Vec<u8>, Error> {
+    let source = uri.to_file_path().map_err(|_| {
+        std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            format!("{} is not a local file", uri),
+        )
+    })?;
+    read_file(&source).await
+}
+
+/// Copies the screenshot at `uri` to `path`, through a temporary file in the
+/// same folder.
+async fn save_screenshot(uri: &Url, path: &Path) -> Result<(), Error> {
+    let content = read_screenshot(uri).await?;
+    let mut temporary = path.as_os_str().to_owned();
+    temporary.push(format!(".{}.tmp", std::process::id()));
+    let temporary = Path::new(&temporary);
+    write_file(temporary, &content).await?;
+    if let Err(err) = std::fs::rename(temporary, path) {
+        let _ = std::fs::remove_file(temporary);
+        return Err(Error::File(path.to_owned(), err));
+    }
+    Ok(())
+}
+
+/// The geometry of a monitor, in the compositor coordinate space.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MonitorInfo {
+    name: String,
+    x: i32,
+    y: i32,
+    width: i32,
+    height: i32,
+    scale: f64,
+}
+
+impl MonitorInfo {
+    /// Create a new monitor.
+    pub fn new(name: &str, x: i32, y: i32, width: i32, height: i32, scale: f64) -> Self {
+        Self {
+            name: name.to_owned(),
+            x,
+            y,
+            width,
+            height,
+            scale,
+        }
+    }
+
+    /// The monitor of a screen cast stream.
+    ///
+    /// The portal doesn't share the monitors scale, so it is set to `1.0` and
+    /// can be changed with [`MonitorInfo::set_scale`] if it is known.
+    pub fn from_stream(stream: &Stream) -> Option<Self> {
+        if matches!(stream.source_type(), Some(t) if t != SourceType::Monitor) {
+            return None;
+        }
+        let (x, y) = stream.position()?;
+        let (width, height) = stream.size()?;
+        let name = stream
+            .id()
+            .map(ToOwned::to_owned)
+            .unwrap_or_else(|| stream.pipe_wire_node_id().to_string());
+        Some(Self::new(&name, x, y, width, height, 1.0))
+    }
+
+    /// The monitor name, the stream id if it comes from a screen cast stream.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The horizontal position.
+    pub fn x(&self) -> i32 {
+        self.x
+    }
+
+    /// The vertical position.
+    pub fn y(&self) -> i32 {
+        self.y
+    }
+
+    /// The width.
+    pub fn width(&self) -> i32 {
+        self.width
+    }
+
+    /// The height.
+    pub fn height(&self) -> i32 {
+        self.height
+    }
+
+    /// The scale factor between the compositor and the pixel coordinates.
+    pub fn scale(&self) -> f64 {
+        self.scale
+    }
+
+    /// Sets the scale factor.
+    pub fn set_scale(&mut self, scale: f64) {
+        self.scale = scale;
+    }
+
+    /// The `(x, y, width, height)` rectangle of the monitor in the pixel
+    /// coordinate space of the screenshot.
+    pub fn region(&self) -> (i32, i32, u32, u32) {
+        let scaled = |v: i32| (f64::from(v) * self.scale).round();
+        (
+            scaled(self.x) as i32,
+            scaled(self.y) as i32,
+            scaled(self.width).max(0.0) as u32,
+            scaled(self.height).max(0.0) as u32,
+        )
+    }
+}
+
+/// Check whether the screenshot portal is available.
+///
+/// See also [`is_portal_available`](crate::is_portal_available).
+pub async fn is_available() -> Result<bool, Error> {
+    crate::is_portal_available(Portal::Screenshot).await
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use byteorder::LE;
+    use zbus::zvariant::{from_slice, to_bytes, EncodingContext as Context, OwnedValue, Value};
+
+    use super::{
+        ColorRequest, MonitorInfo, Screenshot, ScreenshotRequest, ScreenshotResponse, Stream,
+    };
+
+    #[test]
+    fn serialize_color_options() {
+        let ctxt = Context::<LE>::new_dbus(0);
+
+        let request = ColorRequest::default();
+        let encoded = to_bytes(ctxt, &request.request.options).unwrap();
+        let decoded: HashMap<String, OwnedValue> = from_slice(&encoded, ctxt).unwrap();
+        assert_eq!(decoded.len(), 1);
+        assert!(decoded.contains_key("handle_token"));
+    }
+
+    #[test]
+    fn serialize_modal() {
+        let ctxt = Context::<LE>::new_dbus(0);
+
+        let request = ScreenshotRequest::default();
+        let encoded = to_bytes(ctxt, &request.request.options).unwrap();
+        let decoded: HashMap<String, OwnedValue> = from_slice(&encoded, ctxt).unwrap();
+        assert!(!decoded.contains_key("modal"));
+
+        let mut request = request.modal(true);
+        let encoded = to_bytes(ctxt, &request.request.options).unwrap();
+        let decoded: HashMap<String, OwnedValue> = from_slice(&encoded, ctxt).unwrap();
+        assert!(bool::try_from(&decoded["modal"]).unwrap());
+
+        request.set_modal(None);
+        let encoded = to_bytes(ctxt, &request.request.options).unwrap();
+        let decoded: HashMap<String, OwnedValue> = from_slice(&encoded, ctxt).unwrap();
+        assert!(!decoded.contains_key("modal"));
+    }
+
+    #[test]
+    fn default_modal() {
+        use crate::WindowIdentifier;
+
+        let parent = || WindowIdentifier::try_from("wayland:handle").unwrap();
+
+        let mut screenshot = ScreenshotRequest::default();
+        screenshot.request.resolve_modal();
+        assert_eq!(screenshot.request.options.modal, None);
+        let mut color = ColorRequest::default();
+        color.request.resolve_modal();
+        assert_eq!(color.request.options.modal, None);
+
+        let mut screenshot = ScreenshotRequest::default().identifier(parent());
+        screenshot.request.resolve_modal();
+        assert_eq!(screenshot.request.options.modal, Some(true));
+        let mut color = ColorRequest::default().identifier(parent());
+        color.request.resolve_modal();
+        assert_eq!(color.request.options.modal, Some(true));
+
+        let mut screenshot = ScreenshotRequest::default()
+            .identifier(parent())
+            .modal(false);
+        screenshot.request.resolve_modal();
+        assert_eq!(screenshot.request.options.modal, Some(false));
+
+        let mut color = ColorRequest::default().identifier(parent()).modal(None);
+        color.request.resolve_modal();
+        assert_eq!(color.request.options.modal, None);
+    }
+
+    #[test]
+    fn serialize_shared_options() {
+        let ctxt = Context::<LE>::new_dbus(0);
+        let modal = |options: &[u8]| {
+            let decoded: HashMap<String, OwnedValue> = from_slice(options, ctxt).unwrap();
+            assert!(decoded.contains_key("handle_token"));
+            decoded
+                .get("modal")
+                .map(|modal| bool::try_from(modal).unwrap())
+        };
+
+        for value in [Some(true), Some(false), None] {
+            let mut color = ColorRequest::default();
+            color.set_identifier(Default::default());
+            color.set_modal(value);
+            let mut screenshot = ScreenshotRequest::default();
+            screenshot.set_identifier(Default::default());
+            screenshot.set_modal(value);
+
+            let encoded = to_bytes(ctxt, &color.request.options).unwrap();
+            assert_eq!(modal(&encoded), value);
+            let encoded = to_bytes(ctxt, &screenshot.request.options).unwrap();
+            assert_eq!(modal(&encoded), value);
+        }
+    }
+
+    #[test]
+    fn save_screenshot() {
+        let dir = std::env::temp_dir().join(format!("ashpd-save-to-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let source = dir.join("Screenshot.png");
+        std::fs::write(&source, b"\x89PNG").unwrap();
+        let uri = url::Url::from_file_path(&source).unwrap();
+
+        let path = dir.join("saved.png");
+        std::fs::write(&path, "previous content").unwrap();
+        assert_eq!(
+            zbus::block_on(super::read_screenshot(&uri)).unwrap(),
+            b"\x89PNG"
+        );
+        zbus::block_on(super::save_screenshot(&uri, &path)).unwrap();
+        assert_eq!(std::fs::read(&path).unwrap(), b"\x89PNG");
+        assert_eq!(std::fs::read(&source).unwrap(), b"\x89PNG");
+        // Only the source and the saved screenshot are left
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 2);
+
+        let missing = dir.join("missing").join("saved.png");
+        assert!(matches!(
+            zbus::block_on(super::save_screenshot(&uri, &missing)),
+            Err(crate::Error::File(..))
+        ));
+        let remote = url::Url::parse("https://example.com/Screenshot.png").unwrap();
+        assert!(matches!(
+            zbus::block_on(super::save_screenshot(&remote, &path)),
+            Err(crate::Error::IO(_))
+        ));
+        assert!(matches!(
+            zbus::block_on(super::read_screenshot(&remote)),
+            Err(crate::Error::IO(_))
+        ));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn screenshot_taken_at() {
+        use std::time::{Duration, SystemTime};
+
+        let response = |uri: &str| -> ScreenshotResponse {
+            let ctxt = Context::<LE>::new_dbus(0);
+            let encoded = to_bytes(ctxt, &HashMap::from([("uri", Value::from(uri))])).unwrap();
+            from_slice(&encoded, ctxt).unwrap()
+        };
+        let received_at = SystemTime::UNIX_EPOCH + Duration::from_secs(1_000_000);
+
+        let dir = std::env::temp_dir().join(format!("ashpd-taken-at-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let source = dir.join("Screenshot.png");
+        std::fs::write(&source, b"\x89PNG").unwrap();
+        let uri = url::Url::from_file_path(&source).unwrap();
+        let screenshot = Screenshot::new(response(uri.as_str()), received_at);
+        assert_eq!(screenshot.uri(), &uri);
+        assert_eq!(
+            screenshot.taken_at(),
+            std::fs::metadata(&source).unwrap().modified().unwrap()
+        );
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        // Gone or not a local file
+        let screenshot = Screenshot::new(response(uri.as_str()), received_at);
+        assert_eq!(screenshot.taken_at(), received_at);
+        let screenshot =
+            Screenshot::new(response("https://example.com/Screenshot.png"), received_at);
+        assert_eq!(screenshot.taken_at(), received_at);
+    }
+
+    #[test]
+    fn screenshot_file_name() {
+        let screenshot = |uri: &str| Screenshot {
+            uri: url::Url::parse(uri).unwrap(),
+            taken_at: std::time::SystemTime::UNIX_EPOCH,
+        };
+
+        let encoded =
+            screenshot("file:///home/user/Pictures/Screenshot%20from%202022-10-10%2012-00-00.png");
+        assert_eq!(
+            encoded.file_name().as_deref(),
+            Some("Screenshot from 2022-10-10 12-00-00.png")
+        );
+        assert_eq!(encoded.extension().as_deref(), Some("png"));
+
+        let unicode = screenshot("file:///tmp/%C3%89cran%20%231.tar.jpeg");
+        assert_eq!(unicode.file_name().as_deref(), Some("Écran #1.tar.jpeg"));
+        assert_eq!(unicode.extension().as_deref(), Some("jpeg"));
+
+        // A literal percent sign and invalid UTF-8
+        let percent = screenshot("file:///tmp/100%25%FF.png");
+        assert_eq!(percent.file_name().as_deref(), Some("100%\u{FFFD}.png"));
+
+        let no_extension = screenshot("file:///tmp/.Screenshot");
+        assert_eq!(no_extension.file_name().as_deref(), Some(".Screenshot"));
+        assert_eq!(no_extension.extension(), None);
+
+        let folder = screenshot("file:///tmp/screenshots/");
+        assert_eq!(folder.file_name(), None);
+        assert_eq!(folder.extension(), None);
+
+        let remote = screenshot("https://example.com/shots/a%2Fb.png?size=large");
+        assert_eq!(remote.file_name().as_deref(), Some("a/b.png"));
+        assert_eq!(remote.extension().as_deref(), Some("png"));
+    }
+
+    #[test]
+    fn monitor_from_stream() {
+        let ctxt = Context::<LE>::new_dbus(0);
+        let properties = HashMap::from([
+            ("id", Value::from("HDMI-1")),
+            ("position", Value::from((1920, 0))),
+            ("size", Value::from((1280, 720))),
+            ("source_type", Value::U32(1)),
+        ]);
+        let encoded = to_bytes(ctxt, &(42u32, properties)).unwrap();
+        let stream: Stream = from_slice(&encoded, ctxt).unwrap();
+
+        let mut monitor = MonitorInfo::from_stream(&stream).unwrap();
+        assert_eq!(monitor, MonitorInfo::new("HDMI-1", 1920, 0, 1280, 720, 1.0));
+        assert_eq!(monitor.region(), (1920, 0, 1280, 720));
+        monitor.set_scale(2.0);
+        assert_eq!(monitor.region(), (3840, 0, 2560, 1440));
+
+        // A window stream
+        let properties = HashMap::from([
+            ("position", Value::from((0, 0))),
+            ("size", Value::from((640, 480))),
+            ("source_type", Value::U32(2)),
+        ]);
+        let encoded = to_bytes(ctxt, &(43u32, properties)).unwrap();
+        let stream: Stream = from_slice(&encoded, ctxt).unwrap();
+        assert_eq!(MonitorInfo::from_stream(&stream), None);
+
+        // Without a geometry
+        let properties = HashMap::from([("source_type", Value::U32(1))]);
+        let encoded = to_bytes(ctxt, &(44u32, properties)).unwrap();
+        let stream: Stream = from_slice(&encoded, ctxt).unwrap();
+        assert_eq!(MonitorInfo::from_stream(&stream), None);
+    }
+
+    #[cfg(feature = "rgb")]
+    #[test]
+    fn color_to_rgb() {
+        let color = super::ColorResponse {
+            color: super::Color::new(1.0, 0.5, 0.0),
+        };
+        assert_eq!(rgb::RGB::<f64>::from(color), rgb::RGB::new(1.0, 0.5, 0.0));
+        assert_eq!(rgb::RGB8::from(color), rgb::RGB8::new(255, 128, 0));
+    }
+
+    #[cfg(feature = "palette")]
+    #[test]
+    fn color_to_palette() {
+        let color = super::ColorResponse {
+            color: super::Color::new(1.0, 0.5, 0.0),
+        };
+        assert_eq!(
+            palette::Srgb::<f64>::from(color),
+            palette::Srgb::new(1.0, 0.5, 0.0)
+        );
     }
 }
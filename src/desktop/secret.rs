@@ -22,6 +22,11 @@ use std::os::unix::prelude::AsRawFd;
 
 #[cfg(feature = "async-std")]
 use async_std::{os::unix::net::UnixStream, prelude::*};
+#[cfg(not(any(feature = "async-std", feature = "tokio")))]
+use std::os::unix::net::UnixStream;
+
+#[cfg(not(any(feature = "async-std", feature = "tokio")))]
+use futures_util::AsyncReadExt;
 #[cfg(feature = "tokio")]
 use tokio::{io::AsyncReadExt, net::UnixStream};
 use zbus::zvariant::{Fd, SerializeDict, Type};
@@ -29,7 +34,7 @@ use zbus::zvariant::{Fd, SerializeDict, Type};
 use super::{HandleToken, DESTINATION, PATH};
 use crate::{
     helpers::{call_basic_response_method, session_connection},
-    Error,
+    Error, Portal,
 };
 
 #[derive(SerializeDict, Type, Debug, Default)]
@@ -57,7 +62,7 @@ impl<'a> Secret<'a> {
     pub async fn new() -> Result<Secret<'a>, Error> {
         let connection = session_connection().await?;
         let proxy = zbus::ProxyBuilder::new_bare(&connection)
-            .interface("org.freedesktop.portal.Secret")?
+            .interface(Portal::Secret.as_interface_name())?
             .path(PATH)?
             .destination(DESTINATION)?
             .build()
@@ -89,17 +94,106 @@ impl<'a> Secret<'a> {
     }
 }
 
+/// The secret retrieved by [`retrieve`].
+///
+/// Its [`Debug`](std::fmt::Debug) implementation only shows the length of the
+/// secret, so it doesn't end up in the logs by accident.
+#[derive(Clone, PartialEq, Eq)]
+pub struct SecretBytes(Vec<u8>);
+
+impl SecretBytes {
+    /// The secret.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.0
+    }
+
+    /// Consumes the wrapper, returning the secret.
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.0
+    }
+}
+
+impl std::ops::Deref for SecretBytes {
+    type Target = [u8];
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl AsRef<[u8]> for SecretBytes {
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl std::fmt::Debug for SecretBytes {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SecretBytes")
+            .field("len", &self.0.len())
+            .finish_non_exhaustive()
+    }
+}
+
 /// A handy wrapper around [`Secret::retrieve`].
 ///
 /// It crates a UnixStream internally for receiving the secret.
-pub async fn retrieve() -> Result<Vec<u8>, Error> {
+pub async fn retrieve() -> Result<SecretBytes, Error> {
     let proxy = Secret::new().await?;
 
-    let (mut x1, x2) = UnixStream::pair()?;
+    let (x1, x2) = UnixStream::pair()?;
     proxy.retrieve(&x2).await?;
     drop(x2);
-    let mut buf = Vec::new();
-    x1.read_to_end(&mut buf).await?;
+    Ok(SecretBytes(read_secret(x1).await?))
+}
 
+/// Reads the secret written by the portal until the end of the stream.
+///
+/// The portal might write the secret in several chunks, so a single read
+/// isn't enough. Without a runtime, the stream is polled through `async-io`
+/// so the read doesn't block the executor.
+async fn read_secret(stream: UnixStream) -> std::io::Result<Vec<u8>> {
+    #[cfg(not(any(feature = "async-std", feature = "tokio")))]
+    let mut stream = async_io::Async::new(stream)?;
+    #[cfg(any(feature = "async-std", feature = "tokio"))]
+    let mut stream = stream;
+    let mut buf = Vec::new();
+    stream.read_to_end(&mut buf).await?;
     Ok(buf)
 }
+
+// The tokio streams need a runtime to be created
+#[cfg(all(test, not(feature = "tokio")))]
+mod tests {
+    use std::time::Duration;
+
+    use futures_util::AsyncWriteExt;
+
+    use super::{read_secret, SecretBytes};
+
+    #[test]
+    fn read_secret_chunks() {
+        let (x1, x2) = std::os::unix::net::UnixStream::pair().unwrap();
+        #[cfg(feature = "async-std")]
+        let x1 = super::UnixStream::from(x1);
+        let mut x2 = async_io::Async::new(x2).unwrap();
+
+        // The writer runs on the same task as the reader, a blocking read would
+        // never let it write the second half.
+        let writer = async move {
+            x2.write_all(b"first half, ").await.unwrap();
+            async_io::Timer::after(Duration::from_millis(50)).await;
+            x2.write_all(b"second half").await.unwrap();
+        };
+        let (secret, ()) = zbus::block_on(futures_util::future::join(read_secret(x1), writer));
+        assert_eq!(secret.unwrap(), b"first half, second half");
+    }
+
+    #[test]
+    fn redacted_debug() {
+        let secret = SecretBytes(vec![42; 32]);
+        assert_eq!(format!("{:?}", secret), "SecretBytes { len: 32, .. }");
+        assert!(!format!("{:#?}", secret).contains("42"));
+        assert_eq!(secret.as_bytes(), [42; 32]);
+    }
+}
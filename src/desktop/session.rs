@@ -1,16 +1,35 @@
-use std::{collections::HashMap, convert::TryFrom, fmt::Debug};
+use std::{
+    collections::HashMap,
+    fmt::Debug,
+    future::ready,
+    sync::atomic::{AtomicBool, Ordering},
+};
 
+use futures_util::{Stream, StreamExt};
 use serde::{Serialize, Serializer};
-use zbus::zvariant::{ObjectPath, OwnedValue, Signature, Type};
+use zbus::{
+    names::BusName,
+    zvariant::{DeserializeDict, ObjectPath, OwnedObjectPath, OwnedValue, Signature, Type},
+};
 
 use crate::{
-    desktop::{HandleToken, DESTINATION},
-    helpers::{call_method, receive_signal, session_connection},
-    Error,
+    desktop::HandleToken,
+    helpers::{call_method, call_request_method, close_detached, receive_signal, session_path},
+    Error, PortalError,
 };
 
 pub type SessionDetails = HashMap<String, OwnedValue>;
 
+#[derive(DeserializeDict, Type, Debug)]
+/// The response of the methods creating a session, `CreateSession` for
+/// example.
+#[zvariant(signature = "dict")]
+struct CreateSessionResponse {
+    // TODO: investigate why this doesn't return an ObjectPath
+    // replace with an ObjectPath once https://github.com/flatpak/xdg-desktop-portal/pull/609's merged
+    session_handle: String,
+}
+
 /// Shared by all portal interfaces that involve long lived sessions.
 ///
 /// When a method that creates a session is called, if successful, the reply
@@ -22,39 +41,136 @@ pub type SessionDetails = HashMap<String, OwnedValue>;
 /// and a signal [`Session::receive_closed`]. Whether it is allowed to
 /// directly call [`Session::close`] depends on the interface.
 ///
+/// The session is closed when the [`Session`] is dropped, unless it was
+/// already closed with [`Session::close`]. Keep it around for as long as the
+/// session is needed.
+///
+/// As `Drop` can't wait for the portal, closing on drop is best-effort: the
+/// `Close` call is spawned in the background and its failure is ignored.
+/// Prefer [`Session::close`] to know when, and whether, the session was
+/// closed.
+///
 /// Wrapper of the DBus interface: [`org.freedesktop.portal.Session`](https://flatpak.github.io/xdg-desktop-portal/index.html#gdbus-org.freedesktop.portal.Session).
 #[doc(alias = "org.freedesktop.portal.Session")]
-pub struct Session<'a>(zbus::Proxy<'a>);
+pub struct Session<'a>(zbus::Proxy<'a>, AtomicBool);
 
 impl<'a> Session<'a> {
-    /// Create a new instance of [`Session`].
+    /// Create a new instance of [`Session`] reached through `connection`.
     ///
     /// **Note** A [`Session`] is not supposed to be created manually.
-    pub(crate) async fn new(path: ObjectPath<'a>) -> Result<Session<'a>, Error> {
-        let connection = session_connection().await?;
-        let proxy = zbus::ProxyBuilder::new_bare(&connection)
+    #[cfg(all(test, not(feature = "tokio")))]
+    pub(crate) async fn with_connection(
+        connection: &zbus::Connection,
+        path: ObjectPath<'a>,
+    ) -> Result<Session<'a>, Error> {
+        Self::with_destination(
+            connection,
+            BusName::from_static_str(crate::desktop::DESTINATION)?,
+            path,
+        )
+        .await
+    }
+
+    /// The session at `path` of the service `destination` reached through
+    /// `connection`.
+    async fn with_destination(
+        connection: &zbus::Connection,
+        destination: BusName<'a>,
+        path: ObjectPath<'a>,
+    ) -> Result<Session<'a>, Error> {
+        let proxy = zbus::ProxyBuilder::new_bare(connection)
             .interface("org.freedesktop.portal.Session")?
             .path(path)?
-            .destination(DESTINATION)?
+            .destination(destination)?
             .build()
             .await?;
-        Ok(Self(proxy))
+        Ok(Self(proxy, AtomicBool::new(false)))
     }
 
-    pub(crate) async fn from_unique_name(
+    /// Calls `method_name`, one of the methods creating a session like
+    /// `CreateSession`, on `proxy` and returns the created [`Session`].
+    ///
+    /// `options` in `body` must use `handle_token` and
+    /// `session_handle_token`. The session is reached through the connection
+    /// and the service of `proxy`. Fails with [`zbus::Error::InvalidReply`]
+    /// if the portal created a session at another path than the expected
+    /// one.
+    pub(crate) async fn create<B>(
+        proxy: &zbus::Proxy<'_>,
+        method_name: &str,
+        handle_token: &HandleToken,
+        session_handle_token: &HandleToken,
+        body: &B,
+    ) -> Result<Session<'a>, Error>
+    where
+        B: Serialize + Type + Debug,
+    {
+        let (response, session) = futures_util::try_join!(
+            call_request_method::<CreateSessionResponse, _>(proxy, handle_token, method_name, body),
+            Self::from_unique_name_with_connection(
+                proxy.connection(),
+                proxy.destination().to_owned(),
+                session_handle_token
+            ),
+        )?;
+        session.check_handle(&response.session_handle)
+    }
+
+    /// Calls `method_name`, one of the methods replying with the handle of
+    /// the session they create like the Location `CreateSession`, on `proxy`
+    /// and returns the created [`Session`].
+    ///
+    /// `options` in `body` must use `session_handle_token`. See
+    /// [`Session::create`] for how the session is reached and checked.
+    pub(crate) async fn create_with_reply<B>(
+        proxy: &zbus::Proxy<'_>,
+        method_name: &str,
+        session_handle_token: &HandleToken,
+        body: &B,
+    ) -> Result<Session<'a>, Error>
+    where
+        B: Serialize + Type + Debug,
+    {
+        let (session_handle, session) = futures_util::try_join!(
+            call_method::<OwnedObjectPath, _>(proxy, method_name, body),
+            Self::from_unique_name_with_connection(
+                proxy.connection(),
+                proxy.destination().to_owned(),
+                session_handle_token
+            ),
+        )?;
+        session.check_handle(session_handle.as_str())
+    }
+
+    /// Fails with [`zbus::Error::InvalidReply`] if the portal created the
+    /// session at `session_handle` instead of the expected path.
+    fn check_handle(self, session_handle: &str) -> Result<Session<'a>, Error> {
+        if self.inner().path().as_str() != session_handle {
+            #[cfg(feature = "tracing")]
+            tracing::warn!(
+                "Expected the session {}, got {}",
+                self.inner().path(),
+                session_handle
+            );
+            // Nothing to close at the expected path
+            self.1.store(true, Ordering::SeqCst);
+            return Err(zbus::Error::InvalidReply.into());
+        }
+        Ok(self)
+    }
+
+    /// The session the service `destination` creates for a method called
+    /// through `connection` with `handle_token`.
+    async fn from_unique_name_with_connection(
+        connection: &zbus::Connection,
+        destination: BusName<'a>,
         handle_token: &HandleToken,
     ) -> Result<Session<'a>, crate::Error> {
-        let connection = session_connection().await?;
         let unique_name = connection.unique_name().unwrap();
-        let unique_identifier = unique_name.trim_start_matches(':').replace('.', "_");
-        let path = ObjectPath::try_from(format!(
-            "/org/freedesktop/portal/desktop/session/{}/{}",
-            unique_identifier, handle_token
-        ))
-        .unwrap();
+        let path = session_path(unique_name, &handle_token.to_string())?.into_inner();
         #[cfg(feature = "tracing")]
         tracing::info!("Creating a org.freedesktop.portal.Session {}", path);
-        Self::new(path).await
+        Self::with_destination(connection, destination, path).await
     }
 
     /// Get a reference to the underlying Proxy.
@@ -72,18 +188,52 @@ impl<'a> Session<'a> {
         receive_signal(self.inner(), "Closed").await
     }
 
+    /// A stream of the `Closed` signal, emitted when the session is closed by
+    /// the portal or by the user.
+    ///
+    /// Unlike [`Session::receive_closed`], the stream is subscribed to once
+    /// this method returns, so a signal emitted in the meantime is not
+    /// missed.
+    ///
+    /// # Specifications
+    ///
+    /// See also [`Closed`](https://flatpak.github.io/xdg-desktop-portal/index.html#gdbus-signal-org-freedesktop-portal-Session.Closed).
+    pub async fn on_closed(&self) -> Result<impl Stream<Item = SessionDetails>, Error> {
+        let stream = self
+            .inner()
+            .receive_signal("Closed")
+            .await
+            .map_err::<PortalError, _>(From::from)?;
+        Ok(stream.filter_map(|message| ready(message.body::<SessionDetails>().ok())))
+    }
+
     /// Closes the portal session to which this object refers and ends all
     /// related user interaction (dialogs, etc).
     ///
+    /// Unlike dropping the session, it waits for the portal to handle the
+    /// call and reports its failure.
+    ///
     /// # Specifications
     ///
     /// See also [`Close`](https://flatpak.github.io/xdg-desktop-portal/index.html#gdbus-method-org-freedesktop-portal-Session.Close).
     #[doc(alias = "Close")]
-    pub async fn close(&self) -> Result<(), Error> {
+    pub async fn close(self) -> Result<(), Error> {
+        self.1.store(true, Ordering::SeqCst);
         call_method(self.inner(), "Close", &()).await
     }
 }
 
+impl<'a> Drop for Session<'a> {
+    fn drop(&mut self) {
+        if self.1.load(Ordering::SeqCst) {
+            return;
+        }
+        #[cfg(feature = "tracing")]
+        tracing::info!("Closing the dropped session {}", self.inner().path());
+        close_detached(self.inner());
+    }
+}
+
 impl<'a> Serialize for Session<'a> {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
@@ -106,3 +256,206 @@ impl<'a> Debug for Session<'a> {
             .finish()
     }
 }
+
+// The tokio feature makes zbus expect a tokio UnixStream instead
+#[cfg(all(test, not(feature = "tokio")))]
+mod tests {
+    use std::{
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc,
+        },
+        time::Duration,
+    };
+
+    use std::collections::HashMap;
+
+    use futures_util::StreamExt;
+    use zbus::{
+        dbus_interface,
+        zvariant::{ObjectPath, OwnedObjectPath, OwnedValue, Value},
+        SignalContext,
+    };
+
+    use super::{Session, SessionDetails};
+    use crate::{desktop::HandleToken, helpers::request_path, Error};
+
+    const SESSION_PATH: &str = "/org/freedesktop/portal/desktop/session/1_42/ashpd_test";
+
+    struct MockSession(Arc<AtomicUsize>);
+
+    #[dbus_interface(name = "org.freedesktop.portal.Session")]
+    impl MockSession {
+        async fn close(&self, #[zbus(signal_context)] ctxt: SignalContext<'_>) {
+            self.0.fetch_add(1, Ordering::SeqCst);
+            Self::closed(&ctxt, SessionDetails::new()).await.unwrap();
+        }
+
+        #[dbus_interface(signal)]
+        async fn closed(ctxt: &SignalContext<'_>, details: SessionDetails) -> zbus::Result<()>;
+    }
+
+    #[test]
+    fn close_and_drop() {
+        zbus::block_on(async {
+            let closes = Arc::new(AtomicUsize::new(0));
+            let guid = zbus::Guid::generate();
+            let (server, client) = std::os::unix::net::UnixStream::pair().unwrap();
+            let (_server, client) = futures_util::try_join!(
+                zbus::ConnectionBuilder::unix_stream(server)
+                    .server(&guid)
+                    .p2p()
+                    .serve_at(SESSION_PATH, MockSession(closes.clone()))
+                    .unwrap()
+                    .build(),
+                zbus::ConnectionBuilder::unix_stream(client).p2p().build(),
+            )
+            .unwrap();
+            let path = ObjectPath::try_from(SESSION_PATH).unwrap();
+
+            let session = Session::with_connection(&client, path.clone())
+                .await
+                .unwrap();
+            let mut closed = session.on_closed().await.unwrap();
+            session.close().await.unwrap();
+            assert!(closed.next().await.unwrap().is_empty());
+            assert_eq!(closes.load(Ordering::SeqCst), 1);
+
+            let session = Session::with_connection(&client, path).await.unwrap();
+            drop(session);
+            for _ in 0..100 {
+                if closes.load(Ordering::SeqCst) == 2 {
+                    return;
+                }
+                std::thread::sleep(Duration::from_millis(10));
+            }
+            panic!("The dropped session wasn't closed");
+        });
+    }
+
+    // Creates the sessions at `session_handle`
+    struct MockSessions(&'static str);
+
+    #[dbus_interface(name = "org.freedesktop.portal.Mock")]
+    impl MockSessions {
+        async fn create_session(
+            &self,
+            #[zbus(connection)] connection: &zbus::Connection,
+            options: HashMap<String, OwnedValue>,
+        ) -> zbus::fdo::Result<OwnedObjectPath> {
+            let handle_token = <&str>::try_from(&options["handle_token"]).unwrap();
+            let path = request_path(":1.42", handle_token).unwrap();
+            connection
+                .emit_signal(
+                    None::<zbus::names::BusName<'_>>,
+                    path.as_str(),
+                    "org.freedesktop.portal.Request",
+                    "Response",
+                    &(
+                        0u32,
+                        HashMap::from([("session_handle", Value::from(self.0))]),
+                    ),
+                )
+                .await?;
+            Ok(path)
+        }
+
+        // Replies with the session handle, like the Location portal
+        async fn create_location_session(
+            &self,
+            _options: HashMap<String, OwnedValue>,
+        ) -> zbus::fdo::Result<OwnedObjectPath> {
+            Ok(OwnedObjectPath::try_from(self.0).unwrap())
+        }
+    }
+
+    #[test]
+    fn create() {
+        zbus::block_on(async {
+            for session_handle in [
+                SESSION_PATH,
+                "/org/freedesktop/portal/desktop/session/1_42/other",
+            ] {
+                let guid = zbus::Guid::generate();
+                let (server, client) = std::os::unix::net::UnixStream::pair().unwrap();
+                let (_server, client) = futures_util::try_join!(
+                    zbus::ConnectionBuilder::unix_stream(server)
+                        .server(&guid)
+                        .p2p()
+                        .serve_at(crate::desktop::PATH, MockSessions(session_handle))
+                        .unwrap()
+                        .build(),
+                    zbus::ConnectionBuilder::unix_stream(client)
+                        .p2p()
+                        .unique_name(":1.42")
+                        .unwrap()
+                        .build(),
+                )
+                .unwrap();
+                let proxy: zbus::Proxy<'_> = zbus::ProxyBuilder::new_bare(&client)
+                    .interface("org.freedesktop.portal.Mock")
+                    .unwrap()
+                    .path(crate::desktop::PATH)
+                    .unwrap()
+                    .destination("org.freedesktop.impl.portal.Mock")
+                    .unwrap()
+                    .build()
+                    .await
+                    .unwrap();
+
+                let handle_token = HandleToken::default();
+                let session_handle_token = HandleToken::try_from("ashpd_test").unwrap();
+                let options = HashMap::from([
+                    ("handle_token", Value::from(handle_token.to_string())),
+                    (
+                        "session_handle_token",
+                        Value::from(session_handle_token.to_string()),
+                    ),
+                ]);
+                let result = Session::create(
+                    &proxy,
+                    "CreateSession",
+                    &handle_token,
+                    &session_handle_token,
+                    &(options,),
+                )
+                .await;
+                let direct_result = Session::create_with_reply(
+                    &proxy,
+                    "CreateLocationSession",
+                    &session_handle_token,
+                    &(HashMap::from([(
+                        "session_handle_token",
+                        Value::from(session_handle_token.to_string()),
+                    )]),),
+                )
+                .await;
+                if session_handle == SESSION_PATH {
+                    let session = direct_result.unwrap();
+                    assert_eq!(session.inner().path().as_str(), SESSION_PATH);
+                    session.1.store(true, Ordering::SeqCst);
+                } else {
+                    assert!(matches!(
+                        direct_result,
+                        Err(Error::Zbus(zbus::Error::InvalidReply))
+                    ));
+                }
+                if session_handle == SESSION_PATH {
+                    let session = result.unwrap();
+                    assert_eq!(session.inner().path().as_str(), SESSION_PATH);
+                    // Reached like the portal that created it
+                    assert_eq!(
+                        session.inner().destination().as_str(),
+                        "org.freedesktop.impl.portal.Mock"
+                    );
+                    session.1.store(true, Ordering::SeqCst);
+                } else {
+                    assert!(matches!(
+                        result,
+                        Err(Error::Zbus(zbus::Error::InvalidReply))
+                    ));
+                }
+            }
+        });
+    }
+}
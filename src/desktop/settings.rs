@@ -12,6 +12,15 @@
 //!     let settings = proxy.read_all(&["org.gnome.desktop.interface"]).await?;
 //!     println!("{:#?}", settings);
 //!
+//!     let appearance = proxy.appearance().await?;
+//!     println!("{:#?}", appearance.color_scheme());
+//!     println!("{:#?}", appearance.accent_color());
+//!     println!("{:#?}", appearance.contrast());
+//!
+//!     if let Some(color) = proxy.accent_color().await? {
+//!         println!("{}", color);
+//!     }
+//!
 //!     let setting = proxy.receive_setting_changed().await?;
 //!     println!("{}", setting.namespace());
 //!     println!("{}", setting.key());
@@ -23,13 +32,14 @@
 
 use std::{collections::HashMap, convert::TryFrom, fmt::Debug};
 
+use futures_util::{future::ready, Stream, StreamExt};
 use serde::{de::DeserializeOwned, Deserialize, Serialize};
 use zbus::zvariant::{OwnedValue, Type};
 
-use super::{DESTINATION, PATH};
+use super::{Color, DESTINATION, PATH};
 use crate::{
     helpers::{call_method, receive_signal, session_connection},
-    Error,
+    Error, Portal, PortalError,
 };
 
 /// A HashMap of the <key, value> settings found on a specific namespace.
@@ -77,6 +87,89 @@ pub enum ColorScheme {
     PreferLight,
 }
 
+impl From<u32> for ColorScheme {
+    fn from(value: u32) -> Self {
+        match value {
+            1 => Self::PreferDark,
+            2 => Self::PreferLight,
+            _ => Self::NoPreference,
+        }
+    }
+}
+
+/// The system's preferred contrast level
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Contrast {
+    /// No preference
+    NoPreference,
+    /// Higher contrast
+    High,
+}
+
+impl From<u32> for Contrast {
+    fn from(value: u32) -> Self {
+        match value {
+            1 => Self::High,
+            _ => Self::NoPreference,
+        }
+    }
+}
+
+const APPEARANCE_NAMESPACE: &str = "org.freedesktop.appearance";
+
+/// The settings of the `org.freedesktop.appearance` namespace.
+///
+/// Older backends don't provide all of them, the missing ones are reported as
+/// having no preference.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Appearance {
+    color_scheme: ColorScheme,
+    accent_color: Option<Color>,
+    contrast: Contrast,
+}
+
+impl Appearance {
+    /// The preferred color scheme.
+    pub fn color_scheme(&self) -> ColorScheme {
+        self.color_scheme
+    }
+
+    /// The accent color, `None` if the user didn't pick one.
+    pub fn accent_color(&self) -> Option<Color> {
+        self.accent_color
+    }
+
+    /// The preferred contrast level.
+    pub fn contrast(&self) -> Contrast {
+        self.contrast
+    }
+}
+
+impl From<&Namespace> for Appearance {
+    fn from(namespace: &Namespace) -> Self {
+        let uint = |key| {
+            namespace
+                .get(key)
+                .and_then(|value| u32::try_from(value).ok())
+                .unwrap_or_default()
+        };
+        Self {
+            color_scheme: uint("color-scheme").into(),
+            accent_color: namespace.get("accent-color").and_then(accent_color_from),
+            contrast: uint("contrast").into(),
+        }
+    }
+}
+
+// Values out of the `0.0..=1.0` range mean the accent color is unset.
+fn accent_color_from(value: &OwnedValue) -> Option<Color> {
+    let color = Color::try_from(value).ok()?;
+    [color.red(), color.green(), color.blue()]
+        .iter()
+        .all(|c| (0.0..=1.0).contains(c))
+        .then_some(color)
+}
+
 /// The interface provides read-only access to a small number of host settings
 /// required for toolkits similar to XSettings. It is not for general purpose
 /// settings.
@@ -90,8 +183,12 @@ impl<'a> Settings<'a> {
     /// Create a new instance of [`Settings`].
     pub async fn new() -> Result<Settings<'a>, Error> {
         let connection = session_connection().await?;
-        let proxy = zbus::ProxyBuilder::new_bare(&connection)
-            .interface("org.freedesktop.portal.Settings")?
+        Self::with_connection(&connection).await
+    }
+
+    async fn with_connection(connection: &zbus::Connection) -> Result<Settings<'a>, Error> {
+        let proxy = zbus::ProxyBuilder::new_bare(connection)
+            .interface(Portal::Settings.as_interface_name())?
             .path(PATH)?
             .destination(DESTINATION)?
             .build()
@@ -104,7 +201,7 @@ impl<'a> Settings<'a> {
         &self.0
     }
 
-    /// Reads a single value. Returns an error on any unknown namespace or key.
+    /// Reads all the settings of the given namespaces at once.
     ///
     /// # Arguments
     ///
@@ -153,18 +250,103 @@ impl<'a> Settings<'a> {
         T::try_from(value).map_err(From::from)
     }
 
+    /// Reads all the settings of the `org.freedesktop.appearance` namespace
+    /// at once.
+    ///
+    /// Prefer it over reading each of them to initialize the application's
+    /// style on startup.
+    pub async fn appearance(&self) -> Result<Appearance, Error> {
+        let settings = self.read_all(&[APPEARANCE_NAMESPACE]).await?;
+        Ok(settings
+            .get(APPEARANCE_NAMESPACE)
+            .map(Appearance::from)
+            .unwrap_or_else(|| Appearance::from(&Namespace::new())))
+    }
+
     /// Reads the value of namespace: `org.freedesktop.appearance` and
     /// `color-scheme` key.
     pub async fn color_scheme(&self) -> Result<ColorScheme, Error> {
-        let scheme = match self
-            .read::<u32>("org.freedesktop.appearance", "color-scheme")
-            .await?
+        let scheme = self
+            .read::<u32>(APPEARANCE_NAMESPACE, "color-scheme")
+            .await?;
+        Ok(scheme.into())
+    }
+
+    /// Reads the value of namespace: `org.freedesktop.appearance` and
+    /// `accent-color` key.
+    ///
+    /// Returns `None` if the backend doesn't provide an accent color, either
+    /// because the key is unknown to it or because the user didn't pick one.
+    pub async fn accent_color(&self) -> Result<Option<Color>, Error> {
+        let value = self.read_appearance("accent-color").await?;
+        Ok(value.as_ref().and_then(accent_color_from))
+    }
+
+    /// Reads the value of namespace: `org.freedesktop.appearance` and
+    /// `contrast` key.
+    ///
+    /// Returns [`Contrast::NoPreference`] if the backend doesn't know about
+    /// the contrast preference.
+    pub async fn contrast(&self) -> Result<Contrast, Error> {
+        let value = self.read_appearance("contrast").await?;
+        Ok(value
+            .and_then(|value| u32::try_from(value).ok())
+            .unwrap_or_default()
+            .into())
+    }
+
+    /// Listen to changes of the namespace `org.freedesktop.appearance` for
+    /// `contrast` key.
+    pub async fn receive_contrast_changed(&self) -> Result<Contrast, Error> {
+        loop {
+            let setting = self.receive_setting_changed().await?;
+            if setting.namespace() == APPEARANCE_NAMESPACE && setting.key() == "contrast" {
+                return Ok(u32::try_from(setting.value()).unwrap_or_default().into());
+            }
+        }
+    }
+
+    /// A stream of the changes of the namespace `org.freedesktop.appearance`
+    /// for `contrast` key.
+    ///
+    /// Unlike [`Settings::receive_contrast_changed`], it keeps reporting the
+    /// changes after the first one.
+    pub async fn on_contrast_changed(&self) -> Result<impl Stream<Item = Contrast>, Error> {
+        let stream = self.on_appearance_changed("contrast").await?;
+        Ok(stream.map(|value| u32::try_from(value).unwrap_or_default().into()))
+    }
+
+    // Older backends report the keys they don't know about as not found.
+    async fn read_appearance(&self, key: &str) -> Result<Option<OwnedValue>, Error> {
+        match call_method::<OwnedValue, _>(self.inner(), "Read", &(APPEARANCE_NAMESPACE, key)).await
         {
-            1 => ColorScheme::PreferDark,
-            2 => ColorScheme::PreferLight,
-            _ => ColorScheme::NoPreference,
-        };
-        Ok(scheme)
+            Ok(value) => Ok(Some(value)),
+            Err(Error::Portal(PortalError::NotFound(_))) => Ok(None),
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Listen to changes of the namespace `org.freedesktop.appearance` for
+    /// `accent-color` key.
+    pub async fn receive_accent_color_changed(&self) -> Result<Option<Color>, Error> {
+        loop {
+            let setting = self.receive_setting_changed().await?;
+            if setting.namespace() == APPEARANCE_NAMESPACE && setting.key() == "accent-color" {
+                return Ok(accent_color_from(setting.value()));
+            }
+        }
+    }
+
+    /// A stream of the changes of the namespace `org.freedesktop.appearance`
+    /// for `accent-color` key.
+    ///
+    /// Unlike [`Settings::receive_accent_color_changed`], it keeps reporting
+    /// the changes after the first one.
+    pub async fn on_accent_color_changed(
+        &self,
+    ) -> Result<impl Stream<Item = Option<Color>>, Error> {
+        let stream = self.on_appearance_changed("accent-color").await?;
+        Ok(stream.map(|value| accent_color_from(&value)))
     }
 
     /// Listen to changes of the namespace `org.freedesktop.appearance` for
@@ -172,14 +354,8 @@ impl<'a> Settings<'a> {
     pub async fn receive_color_scheme_changed(&self) -> Result<ColorScheme, Error> {
         loop {
             let setting = self.receive_setting_changed().await?;
-            if setting.namespace() == "org.freedesktop.appearance"
-                && setting.key() == "color-scheme"
-            {
-                return Ok(match u32::try_from(setting.value()) {
-                    Ok(1) => ColorScheme::PreferDark,
-                    Ok(2) => ColorScheme::PreferLight,
-                    _ => ColorScheme::NoPreference,
-                });
+            if setting.namespace() == APPEARANCE_NAMESPACE && setting.key() == "color-scheme" {
+                return Ok(u32::try_from(setting.value()).unwrap_or_default().into());
             }
         }
     }
@@ -193,4 +369,373 @@ impl<'a> Settings<'a> {
     pub async fn receive_setting_changed(&self) -> Result<Setting, Error> {
         receive_signal(self.inner(), "SettingChanged").await
     }
+
+    /// A stream of the changes of all the settings, whatever their namespace.
+    ///
+    /// A single subscription is made for all of them, filter the stream
+    /// instead of calling [`Settings::receive_setting_changed`] for each of
+    /// the settings the application tracks.
+    ///
+    /// # Specifications
+    ///
+    /// See also [`SettingChanged`](https://flatpak.github.io/xdg-desktop-portal/index.html#gdbus-signal-org-freedesktop-portal-Settings.SettingChanged).
+    pub async fn on_setting_changed(&self) -> Result<impl Stream<Item = Setting>, Error> {
+        let stream = self
+            .inner()
+            .receive_signal("SettingChanged")
+            .await
+            .map_err::<PortalError, _>(From::from)?;
+        Ok(stream.filter_map(|message| ready(message.body::<Setting>().ok())))
+    }
+
+    // The values of a single key of the `org.freedesktop.appearance` namespace
+    async fn on_appearance_changed(
+        &self,
+        key: &'static str,
+    ) -> Result<impl Stream<Item = OwnedValue>, Error> {
+        let stream = self.on_setting_changed().await?;
+        Ok(stream.filter_map(move |setting| {
+            let Setting(namespace, setting_key, value) = setting;
+            ready((namespace == APPEARANCE_NAMESPACE && setting_key == key).then_some(value))
+        }))
+    }
+}
+
+/// A stream of the changes of all the settings, whatever their namespace.
+///
+/// See [`Settings::on_setting_changed`].
+///
+/// ```rust,no_run
+/// use ashpd::desktop::settings;
+/// use futures_util::StreamExt;
+///
+/// async fn run() -> ashpd::Result<()> {
+///     let mut changes = settings::changes().await?;
+///     while let Some(setting) = changes.next().await {
+///         match (setting.namespace(), setting.key()) {
+///             ("org.freedesktop.appearance", "color-scheme") => {}
+///             ("org.gnome.desktop.interface", "clock-format") => {}
+///             _ => {}
+///         }
+///     }
+///     Ok(())
+/// }
+/// ```
+pub async fn changes() -> Result<impl Stream<Item = Setting>, Error> {
+    Settings::new().await?.on_setting_changed().await
+}
+
+/// Reads all the settings of the given namespaces at once.
+///
+/// See [`Settings::read_all`].
+///
+/// ```rust,no_run
+/// use ashpd::desktop::settings;
+///
+/// async fn run() -> ashpd::Result<()> {
+///     let settings = settings::read_all(&["org.gnome.desktop.interface"]).await?;
+///     println!("{:#?}", settings);
+///     Ok(())
+/// }
+/// ```
+pub async fn read_all(
+    namespaces: &[impl AsRef<str> + Type + Serialize + Debug],
+) -> Result<HashMap<String, Namespace>, Error> {
+    Settings::new().await?.read_all(namespaces).await
+}
+
+/// Reads the accent color, `None` if the user didn't pick one.
+///
+/// See [`Settings::accent_color`].
+pub async fn accent_color() -> Result<Option<Color>, Error> {
+    Settings::new().await?.accent_color().await
+}
+
+/// A stream of the changes of the accent color.
+///
+/// See [`Settings::on_accent_color_changed`].
+///
+/// ```rust,no_run
+/// use ashpd::desktop::settings;
+/// use futures_util::StreamExt;
+///
+/// async fn run() -> ashpd::Result<()> {
+///     println!("{:?}", settings::accent_color().await?);
+///     let mut changes = settings::accent_color_changes().await?;
+///     while let Some(color) = changes.next().await {
+///         println!("{:?}", color);
+///     }
+///     Ok(())
+/// }
+/// ```
+pub async fn accent_color_changes() -> Result<impl Stream<Item = Option<Color>>, Error> {
+    Settings::new().await?.on_accent_color_changed().await
+}
+
+/// Reads the preferred contrast level.
+///
+/// See [`Settings::contrast`].
+pub async fn contrast() -> Result<Contrast, Error> {
+    Settings::new().await?.contrast().await
+}
+
+/// A stream of the changes of the preferred contrast level.
+///
+/// See [`Settings::on_contrast_changed`].
+///
+/// ```rust,no_run
+/// use ashpd::desktop::settings::{self, Contrast};
+/// use futures_util::StreamExt;
+///
+/// async fn run() -> ashpd::Result<()> {
+///     println!("{}", settings::contrast().await? == Contrast::High);
+///     let mut changes = settings::contrast_changes().await?;
+///     while let Some(contrast) = changes.next().await {
+///         println!("{}", contrast == Contrast::High);
+///     }
+///     Ok(())
+/// }
+/// ```
+pub async fn contrast_changes() -> Result<impl Stream<Item = Contrast>, Error> {
+    Settings::new().await?.on_contrast_changed().await
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use byteorder::LE;
+    use zbus::zvariant::{from_slice, to_bytes, EncodingContext as Context, OwnedValue, Value};
+
+    use super::{accent_color_from, Appearance, Color, ColorScheme, Contrast, Namespace};
+
+    fn read_all(settings: HashMap<&str, HashMap<&str, Value<'_>>>) -> HashMap<String, Namespace> {
+        let ctxt = Context::<LE>::new_dbus(0);
+        let encoded = to_bytes(ctxt, &settings).unwrap();
+        from_slice(&encoded, ctxt).unwrap()
+    }
+
+    #[test]
+    fn parse_appearance() {
+        let settings = read_all(HashMap::from([
+            (
+                "org.freedesktop.appearance",
+                HashMap::from([
+                    ("color-scheme", Value::U32(1)),
+                    ("accent-color", Value::from((0.2, 0.4, 1.0))),
+                    ("contrast", Value::U32(1)),
+                ]),
+            ),
+            (
+                "org.gnome.desktop.interface",
+                HashMap::from([("clock-format", Value::from("24h"))]),
+            ),
+        ]));
+        assert_eq!(settings.len(), 2);
+
+        let appearance = Appearance::from(&settings["org.freedesktop.appearance"]);
+        assert_eq!(appearance.color_scheme(), ColorScheme::PreferDark);
+        assert_eq!(appearance.accent_color(), Some(Color::new(0.2, 0.4, 1.0)));
+        assert_eq!(appearance.contrast(), Contrast::High);
+    }
+
+    #[test]
+    fn parse_appearance_defaults() {
+        // An older backend, only knowing about the color scheme
+        let settings = read_all(HashMap::from([(
+            "org.freedesktop.appearance",
+            HashMap::from([("color-scheme", Value::U32(2))]),
+        )]));
+        let appearance = Appearance::from(&settings["org.freedesktop.appearance"]);
+        assert_eq!(appearance.color_scheme(), ColorScheme::PreferLight);
+        assert_eq!(appearance.accent_color(), None);
+        assert_eq!(appearance.contrast(), Contrast::NoPreference);
+
+        // An unset accent color
+        let settings = read_all(HashMap::from([(
+            "org.freedesktop.appearance",
+            HashMap::from([("accent-color", Value::from((-1.0, -1.0, -1.0)))]),
+        )]));
+        let appearance = Appearance::from(&settings["org.freedesktop.appearance"]);
+        assert_eq!(appearance.accent_color(), None);
+        assert_eq!(appearance.color_scheme(), ColorScheme::NoPreference);
+    }
+
+    #[test]
+    fn parse_accent_color() {
+        // The `Read` reply wraps the `(ddd)` tuple in a variant
+        let ctxt = Context::<LE>::new_dbus(0);
+        let encoded = to_bytes(ctxt, &Value::from((1.0, 0.5, 0.0))).unwrap();
+        let value: OwnedValue = from_slice(&encoded, ctxt).unwrap();
+        assert_eq!(accent_color_from(&value), Some(Color::new(1.0, 0.5, 0.0)));
+
+        let encoded = to_bytes(ctxt, &Value::from((2.0, 0.5, 0.0))).unwrap();
+        let value: OwnedValue = from_slice(&encoded, ctxt).unwrap();
+        assert_eq!(accent_color_from(&value), None);
+    }
+
+    #[test]
+    fn parse_contrast() {
+        assert_eq!(Contrast::from(0), Contrast::NoPreference);
+        assert_eq!(Contrast::from(1), Contrast::High);
+        // Unknown values are treated as having no preference
+        assert_eq!(Contrast::from(2), Contrast::NoPreference);
+
+        let ctxt = Context::<LE>::new_dbus(0);
+        let encoded = to_bytes(ctxt, &Value::U32(1)).unwrap();
+        let value: OwnedValue = from_slice(&encoded, ctxt).unwrap();
+        assert_eq!(
+            Contrast::from(u32::try_from(value).unwrap()),
+            Contrast::High
+        );
+
+        // Absent from an older backend
+        let settings = read_all(HashMap::from([(
+            "org.freedesktop.appearance",
+            HashMap::from([("color-scheme", Value::U32(1))]),
+        )]));
+        let appearance = Appearance::from(&settings["org.freedesktop.appearance"]);
+        assert_eq!(appearance.contrast(), Contrast::NoPreference);
+    }
+
+    // The tokio feature makes zbus expect a tokio UnixStream instead
+    #[cfg(not(feature = "tokio"))]
+    struct MockSettings;
+
+    #[cfg(not(feature = "tokio"))]
+    #[zbus::dbus_interface(name = "org.freedesktop.portal.Settings")]
+    impl MockSettings {
+        async fn read_all(
+            &self,
+            _namespaces: Vec<String>,
+            #[zbus(signal_context)] ctxt: zbus::SignalContext<'_>,
+        ) -> HashMap<String, Namespace> {
+            Self::setting_changed(
+                &ctxt,
+                "org.freedesktop.appearance",
+                "color-scheme",
+                &Value::U32(1),
+            )
+            .await
+            .unwrap();
+            Self::setting_changed(
+                &ctxt,
+                "org.gnome.desktop.interface",
+                "clock-format",
+                &Value::from("24h"),
+            )
+            .await
+            .unwrap();
+            Self::setting_changed(
+                &ctxt,
+                "org.freedesktop.appearance",
+                "accent-color",
+                &Value::from((0.2, 0.4, 1.0)),
+            )
+            .await
+            .unwrap();
+            Self::setting_changed(
+                &ctxt,
+                "org.freedesktop.appearance",
+                "accent-color",
+                &Value::from((-1.0, -1.0, -1.0)),
+            )
+            .await
+            .unwrap();
+            Self::setting_changed(
+                &ctxt,
+                "org.freedesktop.appearance",
+                "contrast",
+                &Value::U32(1),
+            )
+            .await
+            .unwrap();
+            HashMap::new()
+        }
+
+        #[dbus_interface(signal)]
+        async fn setting_changed(
+            ctxt: &zbus::SignalContext<'_>,
+            namespace: &str,
+            key: &str,
+            value: &Value<'_>,
+        ) -> zbus::Result<()>;
+    }
+
+    #[cfg(not(feature = "tokio"))]
+    async fn mock_connection() -> (zbus::Connection, zbus::Connection) {
+        let guid = zbus::Guid::generate();
+        let (server, client) = std::os::unix::net::UnixStream::pair().unwrap();
+        futures_util::try_join!(
+            zbus::ConnectionBuilder::unix_stream(server)
+                .server(&guid)
+                .p2p()
+                .serve_at(crate::desktop::PATH, MockSettings)
+                .unwrap()
+                .build(),
+            zbus::ConnectionBuilder::unix_stream(client).p2p().build(),
+        )
+        .unwrap()
+    }
+
+    #[cfg(not(feature = "tokio"))]
+    #[test]
+    fn setting_changes() {
+        use futures_util::StreamExt;
+
+        use super::Settings;
+
+        zbus::block_on(async {
+            let (_server, client) = mock_connection().await;
+            let proxy = Settings::with_connection(&client).await.unwrap();
+            let mut changes = proxy.on_setting_changed().await.unwrap();
+            proxy.read_all(&[""]).await.unwrap();
+
+            let setting = changes.next().await.unwrap();
+            assert_eq!(setting.namespace(), "org.freedesktop.appearance");
+            assert_eq!(setting.key(), "color-scheme");
+            assert_eq!(u32::try_from(setting.value()).unwrap(), 1);
+
+            let setting = changes.next().await.unwrap();
+            assert_eq!(setting.namespace(), "org.gnome.desktop.interface");
+            assert_eq!(setting.key(), "clock-format");
+            assert_eq!(String::try_from(setting.value().clone()).unwrap(), "24h");
+        });
+    }
+
+    #[cfg(not(feature = "tokio"))]
+    #[test]
+    fn accent_color_changes() {
+        use futures_util::StreamExt;
+
+        use super::Settings;
+
+        zbus::block_on(async {
+            let (_server, client) = mock_connection().await;
+            let proxy = Settings::with_connection(&client).await.unwrap();
+            let mut changes = proxy.on_accent_color_changed().await.unwrap();
+            proxy.read_all(&[""]).await.unwrap();
+
+            // The changes of the other settings are skipped
+            assert_eq!(changes.next().await, Some(Some(Color::new(0.2, 0.4, 1.0))));
+            assert_eq!(changes.next().await, Some(None));
+        });
+    }
+
+    #[cfg(not(feature = "tokio"))]
+    #[test]
+    fn contrast_changes() {
+        use futures_util::StreamExt;
+
+        use super::Settings;
+
+        zbus::block_on(async {
+            let (_server, client) = mock_connection().await;
+            let proxy = Settings::with_connection(&client).await.unwrap();
+            let mut changes = proxy.on_contrast_changed().await.unwrap();
+            proxy.read_all(&[""]).await.unwrap();
+
+            assert_eq!(changes.next().await, Some(Contrast::High));
+        });
+    }
 }
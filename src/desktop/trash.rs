@@ -37,9 +37,8 @@ use zbus::zvariant::{Fd, Type};
 
 use super::{DESTINATION, PATH};
 use crate::{
-    error::PortalError,
     helpers::{call_method, session_connection},
-    Error,
+    Error, Portal,
 };
 
 #[derive(Debug, Deserialize_repr, Serialize_repr, PartialEq, Type)]
@@ -49,6 +48,15 @@ enum TrashStatus {
     Succeeded = 1,
 }
 
+impl TrashStatus {
+    fn into_result(self) -> Result<(), Error> {
+        match self {
+            Self::Failed => Err(Error::PortalOperationFailed(Portal::Trash)),
+            Self::Succeeded => Ok(()),
+        }
+    }
+}
+
 /// The interface lets sandboxed applications send files to the trashcan.
 ///
 /// Wrapper of the DBus interface: [`org.freedesktop.portal.Trash`](https://flatpak.github.io/xdg-desktop-portal/index.html#gdbus-org.freedesktop.portal.Trash).
@@ -61,7 +69,7 @@ impl<'a> TrashProxy<'a> {
     pub async fn new() -> Result<TrashProxy<'a>, Error> {
         let connection = session_connection().await?;
         let proxy = zbus::ProxyBuilder::new_bare(&connection)
-            .interface("org.freedesktop.portal.Trash")?
+            .interface(Portal::Trash.as_interface_name())?
             .path(PATH)?
             .destination(DESTINATION)?
             .build()
@@ -88,11 +96,9 @@ impl<'a> TrashProxy<'a> {
     #[doc(alias = "TrashFile")]
     #[doc(alias = "xdp_portal_trash_file")]
     pub async fn trash_file(&self, fd: &impl AsRawFd) -> Result<(), Error> {
-        let status = call_method(self.inner(), "TrashFile", &(Fd::from(fd.as_raw_fd()))).await?;
-        match status {
-            TrashStatus::Failed => Err(Error::Portal(PortalError::Failed)),
-            TrashStatus::Succeeded => Ok(()),
-        }
+        let status: TrashStatus =
+            call_method(self.inner(), "TrashFile", &(Fd::from(fd.as_raw_fd()))).await?;
+        status.into_result()
     }
 }
 
@@ -106,6 +112,7 @@ pub async fn trash_file(fd: &impl AsRawFd) -> Result<(), Error> {
 #[cfg(test)]
 mod test {
     use super::TrashStatus;
+    use crate::{Error, Portal};
 
     #[test]
     fn status_serde() {
@@ -122,4 +129,14 @@ mod test {
         let y: Test = serde_json::from_str(&x).unwrap();
         assert_eq!(y.status, TrashStatus::Failed);
     }
+
+    #[test]
+    fn status_result() {
+        let status = |code: u32| serde_json::from_str::<TrashStatus>(&code.to_string()).unwrap();
+        assert!(status(1).into_result().is_ok());
+        assert!(matches!(
+            status(0).into_result(),
+            Err(Error::PortalOperationFailed(Portal::Trash))
+        ));
+    }
 }
@@ -38,16 +38,41 @@
 //!     Ok(())
 //! }
 //! ```
+//!
+//! ## Sets a wallpaper from a `gio::File`:
+//!
+//! The feature `gtk4` must be enabled.
+//!
+//! ```rust,ignore
+//! use ashpd::desktop::wallpaper::{SetOn, WallpaperRequest};
+//! use gtk4::gio;
+//!
+//! async fn run() -> ashpd::Result<()> {
+//!     let file = gio::File::for_path("/home/bilelmoussaoui/adwaita-day.jpg");
+//!     WallpaperRequest::default()
+//!         .set_on(SetOn::Background)
+//!         .build_gio_file(&file)
+//!         .await?;
+//!     Ok(())
+//! }
+//! ```
 
-use std::{fmt, os::unix::prelude::AsRawFd, str::FromStr};
+use std::{
+    fmt,
+    os::unix::{io::OwnedFd, prelude::AsRawFd},
+    str::FromStr,
+    time::Duration,
+};
 
 use serde::{self, Deserialize, Serialize};
 use zbus::zvariant::{Fd, SerializeDict, Type};
 
 use crate::{
-    desktop::{HandleToken, DESTINATION, PATH},
-    helpers::{call_basic_response_method, session_connection},
-    Error, WindowIdentifier,
+    desktop::{
+        request::{BasicResponse, RequestBuilder, RequestOptions},
+        HandleToken,
+    },
+    Error, Portal, WindowIdentifier,
 };
 
 #[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash, Type)]
@@ -115,52 +140,13 @@ struct WallpaperOptions {
     set_on: Option<SetOn>,
 }
 
-struct WallpaperProxy<'a>(zbus::Proxy<'a>);
-
-impl<'a> WallpaperProxy<'a> {
-    pub async fn new() -> Result<WallpaperProxy<'a>, Error> {
-        let connection = session_connection().await?;
-        let proxy = zbus::ProxyBuilder::new_bare(&connection)
-            .interface("org.freedesktop.portal.Wallpaper")?
-            .path(PATH)?
-            .destination(DESTINATION)?
-            .build()
-            .await?;
-        Ok(Self(proxy))
+impl RequestOptions for WallpaperOptions {
+    fn handle_token(&self) -> &HandleToken {
+        &self.handle_token
     }
 
-    pub fn inner(&self) -> &zbus::Proxy<'_> {
-        &self.0
-    }
-
-    pub async fn set_wallpaper_file(
-        &self,
-        identifier: &WindowIdentifier,
-        file: &impl AsRawFd,
-        options: WallpaperOptions,
-    ) -> Result<(), Error> {
-        call_basic_response_method(
-            self.inner(),
-            &options.handle_token,
-            "SetWallpaperFile",
-            &(&identifier, Fd::from(file.as_raw_fd()), &options),
-        )
-        .await
-    }
-
-    pub async fn set_wallpaper_uri(
-        &self,
-        identifier: &WindowIdentifier,
-        uri: &url::Url,
-        options: WallpaperOptions,
-    ) -> Result<(), Error> {
-        call_basic_response_method(
-            self.inner(),
-            &options.handle_token,
-            "SetWallpaperURI",
-            &(&identifier, uri, &options),
-        )
-        .await
+    fn set_handle_token(&mut self, handle_token: HandleToken) {
+        self.handle_token = handle_token;
     }
 }
 
@@ -171,8 +157,7 @@ impl<'a> WallpaperProxy<'a> {
 ///
 /// [builder-pattern]: https://doc.rust-lang.org/1.0.0/style/ownership/builders.html
 pub struct WallpaperRequest {
-    identifier: WindowIdentifier,
-    options: WallpaperOptions,
+    request: RequestBuilder<WallpaperOptions>,
 }
 
 impl WallpaperRequest {
@@ -184,7 +169,7 @@ impl WallpaperRequest {
     }
 
     pub fn set_identifier(&mut self, identifier: WindowIdentifier) {
-        self.identifier = identifier;
+        self.request.identifier = identifier;
     }
 
     /// Whether to show a preview of the picture.
@@ -197,7 +182,7 @@ impl WallpaperRequest {
     }
 
     pub fn set_show_preview(&mut self, show_preview: bool) {
-        self.options.show_preview = Some(show_preview);
+        self.request.options.show_preview = Some(show_preview);
     }
 
     /// Sets where to set the wallpaper on.
@@ -208,28 +193,261 @@ impl WallpaperRequest {
     }
 
     pub fn set_set_on(&mut self, set_on: SetOn) {
-        self.options.set_on = Some(set_on);
+        self.request.options.set_on = Some(set_on);
+    }
+
+    /// Sets how long to wait for the user to respond before closing the
+    /// dialog and failing with [`Error::Timeout`].
+    ///
+    /// By default, there is no timeout.
+    #[must_use]
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.set_timeout(timeout);
+        self
+    }
+
+    pub fn set_timeout(&mut self, timeout: Duration) {
+        self.request.timeout = Some(timeout);
+    }
+
+    /// Sets the token of the request instead of a random one, e.g. to find
+    /// the request in the application logs and the D-Bus traces.
+    ///
+    /// The token is used verbatim in the path of the request, see
+    /// [`request_path`](crate::request_path).
+    #[must_use]
+    pub fn handle_token(mut self, handle_token: HandleToken) -> Self {
+        self.set_handle_token(handle_token);
+        self
+    }
+
+    pub fn set_handle_token(&mut self, handle_token: HandleToken) {
+        self.request.options.set_handle_token(handle_token);
     }
 
     /// Build using a URI.
+    ///
+    /// Succeeds once the wallpaper is set. Fails with
+    /// [`ResponseError::Cancelled`](crate::desktop::ResponseError::Cancelled)
+    /// wrapped in [`Error::Response`] if the user cancelled the request, with
+    /// [`ResponseError::Other`](crate::desktop::ResponseError::Other) if the
+    /// portal failed to set it.
+    ///
+    /// # Specifications
+    ///
+    /// See also [`SetWallpaperURI`](https://flatpak.github.io/xdg-desktop-portal/index.html#gdbus-method-org-freedesktop-portal-Wallpaper.SetWallpaperURI).
+    #[doc(alias = "SetWallpaperURI")]
     pub async fn build_uri(self, uri: &url::Url) -> Result<(), Error> {
-        let proxy = WallpaperProxy::new().await?;
-        proxy
-            .set_wallpaper_uri(&self.identifier, uri, self.options)
-            .await
+        self.request
+            .send::<BasicResponse, _>(
+                Portal::Wallpaper,
+                "SetWallpaperURI",
+                &(&self.request.identifier, uri, &self.request.options),
+            )
+            .await?;
+        Ok(())
     }
 
     /// Build using a file.
+    ///
+    /// See [`WallpaperRequest::build_uri`] for the possible outcomes.
+    ///
+    /// **Note** only the raw file descriptor of `file` is sent, it must not be
+    /// closed before the returned future completes, see
+    /// [`WallpaperRequest::build_owned_fd`].
+    ///
+    /// # Specifications
+    ///
+    /// See also [`SetWallpaperFile`](https://flatpak.github.io/xdg-desktop-portal/index.html#gdbus-method-org-freedesktop-portal-Wallpaper.SetWallpaperFile).
+    #[doc(alias = "SetWallpaperFile")]
     pub async fn build_file(self, file: &impl AsRawFd) -> Result<(), Error> {
-        let proxy = WallpaperProxy::new().await?;
-        proxy
-            .set_wallpaper_file(&self.identifier, file, self.options)
-            .await
+        self.request
+            .send::<BasicResponse, _>(
+                Portal::Wallpaper,
+                "SetWallpaperFile",
+                &(
+                    &self.request.identifier,
+                    Fd::from(file.as_raw_fd()),
+                    &self.request.options,
+                ),
+            )
+            .await?;
+        Ok(())
+    }
+
+    #[cfg(feature = "gtk4")]
+    /// Build using a [`gio::File`](https://gtk-rs.org/gtk-rs-core/stable/latest/docs/gio/struct.File.html).
+    ///
+    /// The file is opened and passed to [`WallpaperRequest::build_file`].
+    /// Fails with [`Error::IO`] if the file is not a local one, e.g. a file
+    /// on a remote location, and with [`Error::File`] if it can't be opened.
+    pub async fn build_gio_file(
+        self,
+        file: &impl ::gtk4::glib::IsA<::gtk4::gio::File>,
+    ) -> Result<(), Error> {
+        use ::gtk4::gio::prelude::FileExt;
+
+        let path = file.path().ok_or_else(|| {
+            std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!("{} is not a local file", file.uri()),
+            )
+        })?;
+        let file = std::fs::File::open(&path).map_err(|err| Error::File(path, err))?;
+        self.build_file(&file).await
+    }
+}
+
+impl WallpaperRequest {
+    /// Build using a file, taking ownership of its file descriptor so it
+    /// can't be closed before the portal received it.
+    ///
+    /// The file descriptor is closed once the request completes. See
+    /// [`WallpaperRequest::build_uri`] for the possible outcomes.
+    ///
+    /// ```rust,no_run
+    /// use std::{fs::File, os::unix::io::OwnedFd};
+    ///
+    /// use ashpd::desktop::wallpaper::WallpaperRequest;
+    ///
+    /// async fn run() -> ashpd::Result<()> {
+    ///     let file = File::open("/home/user/adwaita-day.jpg").unwrap();
+    ///     WallpaperRequest::default()
+    ///         .build_owned_fd(OwnedFd::from(file))
+    ///         .await
+    /// }
+    /// ```
+    #[doc(alias = "SetWallpaperFile")]
+    pub async fn build_owned_fd(self, fd: OwnedFd) -> Result<(), Error> {
+        self.build_file(&fd).await
     }
 }
+
+/// Check whether the wallpaper portal is available.
+///
+/// See also [`is_portal_available`](crate::is_portal_available).
+pub async fn is_available() -> Result<bool, Error> {
+    crate::is_portal_available(Portal::Wallpaper).await
+}
+
 #[cfg(test)]
 mod tests {
+    #[cfg(not(feature = "tokio"))]
+    use std::collections::HashMap;
+
+    #[cfg(not(feature = "tokio"))]
+    use zbus::zvariant::{OwnedValue, Value};
+
     use super::SetOn;
+    #[cfg(not(feature = "tokio"))]
+    use super::WallpaperRequest;
+    #[cfg(not(feature = "tokio"))]
+    use crate::{desktop::ResponseError, Error};
+
+    #[cfg(not(feature = "tokio"))]
+    struct MockWallpaper(u32);
+
+    #[cfg(not(feature = "tokio"))]
+    impl MockWallpaper {
+        // Answers the request with the response code of the mock
+        async fn respond(
+            &self,
+            connection: &zbus::Connection,
+            options: HashMap<String, OwnedValue>,
+        ) -> zbus::fdo::Result<zbus::zvariant::OwnedObjectPath> {
+            let handle_token = <&str>::try_from(&options["handle_token"]).unwrap();
+            let path = crate::request_path(":1.42", handle_token).unwrap();
+            connection
+                .emit_signal(
+                    None::<zbus::names::BusName<'_>>,
+                    path.as_str(),
+                    "org.freedesktop.portal.Request",
+                    "Response",
+                    &(self.0, HashMap::<&str, Value<'_>>::new()),
+                )
+                .await?;
+            Ok(path)
+        }
+    }
+
+    #[cfg(not(feature = "tokio"))]
+    #[zbus::dbus_interface(name = "org.freedesktop.portal.Wallpaper")]
+    impl MockWallpaper {
+        #[dbus_interface(name = "SetWallpaperURI")]
+        async fn set_wallpaper_uri(
+            &self,
+            #[zbus(connection)] connection: &zbus::Connection,
+            _parent_window: &str,
+            _uri: &str,
+            options: HashMap<String, OwnedValue>,
+        ) -> zbus::fdo::Result<zbus::zvariant::OwnedObjectPath> {
+            self.respond(connection, options).await
+        }
+
+        async fn set_wallpaper_file(
+            &self,
+            #[zbus(connection)] connection: &zbus::Connection,
+            _parent_window: &str,
+            _fd: zbus::zvariant::Fd,
+            options: HashMap<String, OwnedValue>,
+        ) -> zbus::fdo::Result<zbus::zvariant::OwnedObjectPath> {
+            self.respond(connection, options).await
+        }
+    }
+
+    #[cfg(not(feature = "tokio"))]
+    async fn mock_request(code: u32) -> (zbus::Connection, WallpaperRequest) {
+        let guid = zbus::Guid::generate();
+        let (server, client) = std::os::unix::net::UnixStream::pair().unwrap();
+        let (server, client) = futures_util::try_join!(
+            zbus::ConnectionBuilder::unix_stream(server)
+                .server(&guid)
+                .p2p()
+                .serve_at(crate::desktop::PATH, MockWallpaper(code))
+                .unwrap()
+                .build(),
+            zbus::ConnectionBuilder::unix_stream(client)
+                .p2p()
+                .unique_name(":1.42")
+                .unwrap()
+                .build(),
+        )
+        .unwrap();
+        let mut request = WallpaperRequest::default();
+        request.request.connection = Some(client);
+        (server, request)
+    }
+
+    #[cfg(not(feature = "tokio"))]
+    fn response_error(err: Error) -> Option<ResponseError> {
+        match err {
+            Error::Response(err) => Some(err),
+            _ => None,
+        }
+    }
+
+    #[cfg(not(feature = "tokio"))]
+    #[test]
+    fn response_codes() {
+        zbus::block_on(async {
+            let uri = url::Url::parse("file:///tmp/wallpaper.png").unwrap();
+            let file = std::fs::File::open("/dev/null").unwrap();
+
+            for (code, expected) in [
+                (0, None),
+                (1, Some(ResponseError::Cancelled)),
+                (2, Some(ResponseError::Other)),
+            ] {
+                let (_server, request) = mock_request(code).await;
+                let result = request.build_uri(&uri).await;
+                assert_eq!(result.err().map(response_error), expected.map(Some));
+
+                let (_server, request) = mock_request(code).await;
+                let result = request.build_file(&file).await;
+                assert_eq!(result.err().map(response_error), expected.map(Some));
+            }
+        });
+    }
 
     #[test]
     fn serialize_deserialize() {
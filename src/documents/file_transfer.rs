@@ -28,8 +28,8 @@ use zbus::zvariant::{Fd, SerializeDict, Type, Value};
 
 use super::{DESTINATION, PATH};
 use crate::{
-    helpers::{call_method, receive_signal, session_connection},
-    Error,
+    helpers::{bus_connection, call_method, receive_signal},
+    Error, Portal,
 };
 
 #[derive(SerializeDict, Debug, Type, Default)]
@@ -83,9 +83,9 @@ pub struct FileTransfer<'a>(zbus::Proxy<'a>);
 impl<'a> FileTransfer<'a> {
     /// Create a new instance of [`FileTransfer`].
     pub async fn new() -> Result<FileTransfer<'a>, Error> {
-        let connection = session_connection().await?;
+        let connection = bus_connection().await?;
         let proxy = zbus::ProxyBuilder::new_bare(&connection)
-            .interface("org.freedesktop.portal.FileTransfer")?
+            .interface(Portal::FileTransfer.as_interface_name())?
             .path(PATH)?
             .destination(DESTINATION)?
             .build()
@@ -39,6 +39,8 @@ use std::{
     collections::HashMap,
     ffi::CString,
     fmt,
+    fs::{File, OpenOptions},
+    io,
     os::unix::{ffi::OsStrExt, prelude::AsRawFd},
     path::{Path, PathBuf},
     str::FromStr,
@@ -50,8 +52,9 @@ use serde_repr::{Deserialize_repr, Serialize_repr};
 use zbus::zvariant::{Fd, OwnedValue, Type};
 
 use crate::{
-    helpers::{call_method, path_from_null_terminated, session_connection},
-    Error,
+    desktop::file_chooser::OpenFileRequest,
+    helpers::{bus_connection, call_method, path_from_null_terminated, unblock},
+    Error, Portal,
 };
 
 #[bitflags]
@@ -166,9 +169,9 @@ pub struct Documents<'a>(zbus::Proxy<'a>);
 impl<'a> Documents<'a> {
     /// Create a new instance of [`Documents`].
     pub async fn new() -> Result<Documents<'a>, Error> {
-        let connection = session_connection().await?;
+        let connection = bus_connection().await?;
         let proxy = zbus::ProxyBuilder::new_bare(&connection)
-            .interface("org.freedesktop.portal.Documents")?
+            .interface(Portal::Documents.as_interface_name())?
             .path(PATH)?
             .destination(DESTINATION)?
             .build()
@@ -517,6 +520,114 @@ impl<'a> Documents<'a> {
     }
 }
 
+/// Lets the user pick files and keeps the access to them across restarts of
+/// the application.
+///
+/// The file chooser is shown with `request`, then the selected files are
+/// added permanently to the document store, see
+/// [`OpenFileRequest::persist_access`]. Each selected file is returned with
+/// its document id, in the order they were selected.
+///
+/// To re-open a file later on, e.g. from a list of recent files, store its
+/// document id and pass it to [`open`]. Inside the sandbox, the file is
+/// available as `{mount_point}/{document id}/{file name}`, where
+/// `mount_point` is [`Documents::mount_point`]. Delete the document with
+/// [`Documents::delete`] once the application doesn't need it anymore.
+///
+/// ```rust,no_run
+/// use std::fs::OpenOptions;
+///
+/// use ashpd::{desktop::file_chooser::OpenFileRequest, documents};
+///
+/// async fn run() -> ashpd::Result<()> {
+///     let request = OpenFileRequest::default().title("Open a recent file");
+///     for (uri, document_id) in documents::pick_and_persist(request).await? {
+///         println!("{} is stored as {}", uri, document_id);
+///     }
+///
+///     // On the next start of the application
+///     let file = documents::open("f2ee988d", OpenOptions::new().read(true)).await?;
+///     Ok(())
+/// }
+/// ```
+pub async fn pick_and_persist(
+    request: OpenFileRequest,
+) -> Result<Vec<(url::Url, OwnedDocumentID)>, Error> {
+    let files = request.persist_access(true).build().await?;
+    Ok(files
+        .uris()
+        .iter()
+        .cloned()
+        .zip(files.document_ids().iter().cloned())
+        .collect())
+}
+
+/// Opens the file of the document `doc_id` with `options`, like a document
+/// persisted with [`pick_and_persist`].
+///
+/// The file is resolved through the document store mount point, see
+/// [`Documents::mount_point`]. Fails with [`Error::NotFound`] if the document
+/// doesn't exist or isn't visible to the application anymore, e.g. after its
+/// permissions were revoked, and with [`Error::File`] holding a
+/// [`PermissionDenied`](io::ErrorKind::PermissionDenied) error if `options`
+/// require a permission the application doesn't have, like writing to a
+/// read-only document.
+///
+/// ```rust,no_run
+/// use std::fs::OpenOptions;
+///
+/// use ashpd::documents;
+///
+/// async fn run() -> ashpd::Result<()> {
+///     let file = documents::open("f2ee988d", OpenOptions::new().read(true)).await?;
+///     Ok(())
+/// }
+/// ```
+pub async fn open(doc_id: DocumentID<'_>, options: &OpenOptions) -> Result<File, Error> {
+    let mount_point = Documents::new().await?.mount_point().await?;
+    let doc_id = doc_id.to_owned();
+    let options = options.clone();
+    // The document store is a FUSE file system served by the portal itself
+    unblock(move || {
+        let path = document_path(&mount_point, &doc_id)?;
+        options
+            .open(&path)
+            .map_err(|err| document_error(&doc_id, path, err))
+    })
+    .await
+}
+
+// Each document is a directory of the mount point, containing the file only
+fn document_path(mount_point: &Path, doc_id: DocumentID<'_>) -> Result<PathBuf, Error> {
+    if doc_id.is_empty() || doc_id == "." || doc_id == ".." || doc_id.contains('/') {
+        return Err(Error::InvalidArgument(format!(
+            "Invalid document id {:?}",
+            doc_id
+        )));
+    }
+    let dir = mount_point.join(doc_id);
+    let mut entries = std::fs::read_dir(&dir).map_err(|err| document_error(doc_id, dir, err))?;
+    match entries.next() {
+        Some(Ok(entry)) => Ok(entry.path()),
+        Some(Err(err)) => Err(Error::IO(err)),
+        None => Err(document_error(
+            doc_id,
+            mount_point.join(doc_id),
+            io::ErrorKind::NotFound.into(),
+        )),
+    }
+}
+
+fn document_error(doc_id: DocumentID<'_>, path: PathBuf, err: io::Error) -> Error {
+    match err.kind() {
+        io::ErrorKind::NotFound => Error::NotFound(format!(
+            "No document {}, or the access to it was revoked",
+            doc_id
+        )),
+        _ => Error::File(path, err),
+    }
+}
+
 /// Interact with `org.freedesktop.portal.FileTransfer` interface.
 mod file_transfer;
 
@@ -524,7 +635,38 @@ pub use file_transfer::FileTransfer;
 
 #[cfg(test)]
 mod tests {
-    use crate::documents::Permission;
+    use super::document_path;
+    use crate::{documents::Permission, Error};
+
+    #[test]
+    fn resolve_document_path() {
+        let mount_point =
+            std::env::temp_dir().join(format!("ashpd-documents-{}", std::process::id()));
+        let document = mount_point.join("f2ee988d");
+        std::fs::create_dir_all(&document).unwrap();
+        std::fs::write(document.join("report.pdf"), b"").unwrap();
+        std::fs::create_dir_all(mount_point.join("5a0b0c3f")).unwrap();
+
+        assert_eq!(
+            document_path(&mount_point, "f2ee988d").unwrap(),
+            document.join("report.pdf")
+        );
+        // Deleted, or revoked
+        for doc_id in ["5a0b0c3f", "0a1b2c3d"] {
+            assert!(matches!(
+                document_path(&mount_point, doc_id),
+                Err(Error::NotFound(_))
+            ));
+        }
+        for doc_id in ["", "..", "f2ee988d/report.pdf"] {
+            assert!(matches!(
+                document_path(&mount_point, doc_id),
+                Err(Error::InvalidArgument(_))
+            ));
+        }
+
+        std::fs::remove_dir_all(mount_point).unwrap();
+    }
 
     #[test]
     fn serialize_deserialize() {
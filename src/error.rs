@@ -1,6 +1,6 @@
 use zbus::DBusError;
 
-use crate::desktop::request::ResponseError;
+use crate::{desktop::request::ResponseError, Portal};
 
 /// An error type that describes the various DBus errors.
 ///
@@ -44,28 +44,106 @@ pub enum Error {
     ParseError(&'static str),
     /// Input/Output
     IO(std::io::Error),
+    /// Reading or writing the file at the given path failed.
+    File(std::path::PathBuf, std::io::Error),
+    /// The portal request didn't receive a response in time.
+    Timeout,
+    /// The portal request was cancelled by the application, see
+    /// [`OpenFileRequest::build_with_cancellation`](crate::desktop::file_chooser::OpenFileRequest::build_with_cancellation).
+    Cancelled,
+    /// The portal request needs to show a dialog but neither a parent window
+    /// nor a display is available.
+    NoWindow,
+    /// The portal reported that it failed to carry out the operation, like
+    /// the [`Portal::Trash`] failing to trash a file or GameMode rejecting a
+    /// registration.
+    PortalOperationFailed(Portal),
+    /// The feature isn't available on this system, like a service that isn't
+    /// running or that the sandbox doesn't give access to.
+    Unsupported(&'static str),
+    /// An argument was rejected by ASHPD before calling the portal, like an
+    /// invalid email address.
+    InvalidArgument(String),
+    /// The requested item, like a document, doesn't exist or isn't accessible
+    /// anymore.
+    NotFound(String),
     /// A pipewire error
     #[cfg(feature = "pipewire")]
     Pipewire(pw::Error),
 }
 
-impl std::error::Error for Error {}
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Response(e) => Some(e),
+            Self::Portal(e) => Some(e),
+            Self::Zbus(e) => Some(e),
+            Self::IO(e) | Self::File(_, e) => Some(e),
+            #[cfg(feature = "pipewire")]
+            Self::Pipewire(e) => Some(e),
+            Self::NoResponse
+            | Self::ParseError(_)
+            | Self::Timeout
+            | Self::Cancelled
+            | Self::NoWindow
+            | Self::PortalOperationFailed(_)
+            | Self::Unsupported(_)
+            | Self::InvalidArgument(_)
+            | Self::NotFound(_) => None,
+        }
+    }
+}
 
+// The wrapped errors are exposed through `source()`, so they are not repeated
+// here to avoid duplicated messages when the whole chain gets reported. The
+// portal errors only get a short cause, enough to tell what happened when the
+// error is printed alone.
 impl std::fmt::Display for Error {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            Self::Response(e) => f.write_str(&format!("Portal request didn't succeed: {}", e)),
-            Self::Zbus(e) => f.write_str(&format!("ZBus Error: {}", e)),
-            Self::Portal(e) => f.write_str(&format!("Portal request failed: {}", e)),
+            Self::Response(ResponseError::Cancelled) => {
+                f.write_str("Portal request didn't succeed: cancelled")
+            }
+            Self::Response(ResponseError::Other) => {
+                f.write_str("Portal request didn't succeed: other error")
+            }
+            Self::Zbus(_) => f.write_str("Communicating with the portal over D-Bus failed"),
+            Self::Portal(e) => write!(f, "The portal returned an error: {}", e.cause()),
             Self::NoResponse => f.write_str("Portal error: no response"),
-            Self::IO(e) => f.write_str(&format!("IO: {e}")),
+            Self::IO(_) => f.write_str("An I/O operation failed"),
+            Self::File(path, _) => write!(f, "Failed to access {:?}", path),
+            Self::Timeout => f.write_str("Portal request timed out"),
+            Self::Cancelled => f.write_str("Portal request cancelled by the application"),
+            Self::NoWindow => f.write_str("No window or display to show the portal dialog on"),
+            Self::PortalOperationFailed(portal) => {
+                write!(f, "{} failed to carry out the operation", portal)
+            }
+            Self::Unsupported(feature) => write!(f, "{} is not supported", feature),
+            Self::InvalidArgument(message) => write!(f, "Invalid argument: {}", message),
+            Self::NotFound(message) => write!(f, "Not found: {}", message),
             #[cfg(feature = "pipewire")]
-            Self::Pipewire(e) => f.write_str(&format!("Pipewire: {e}")),
+            Self::Pipewire(_) => f.write_str("Communicating with PipeWire failed"),
             Self::ParseError(e) => f.write_str(e),
         }
     }
 }
 
+impl PortalError {
+    // A short description of the error, without its message
+    fn cause(&self) -> &'static str {
+        match self {
+            Self::ZBus(_) => "D-Bus error",
+            Self::Failed => "failed",
+            Self::InvalidArgument(_) => "invalid argument",
+            Self::NotFound(_) => "not found",
+            Self::Exist(_) => "already exists",
+            Self::NotAllowed(_) => "not allowed",
+            Self::Cancelled(_) => "cancelled",
+            Self::WindowDestroyed(_) => "window destroyed",
+        }
+    }
+}
+
 impl From<ResponseError> for Error {
     fn from(e: ResponseError) -> Self {
         Self::Response(e)
@@ -103,8 +181,70 @@ impl From<zbus::zvariant::Error> for Error {
     }
 }
 
+impl From<zbus::names::Error> for Error {
+    fn from(e: zbus::names::Error) -> Self {
+        Self::Zbus(zbus::Error::Names(e))
+    }
+}
+
 impl From<std::io::Error> for Error {
     fn from(e: std::io::Error) -> Self {
         Self::IO(e)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::error::Error as _;
+
+    use super::{Error, PortalError};
+    use crate::desktop::request::ResponseError;
+
+    #[test]
+    fn source_chain() {
+        let err = Error::from(ResponseError::Cancelled);
+        assert_eq!(err.to_string(), "Portal request didn't succeed: cancelled");
+        let source = err.source().unwrap();
+        assert_eq!(
+            source.downcast_ref::<ResponseError>(),
+            Some(&ResponseError::Cancelled)
+        );
+
+        let err = Error::from(std::io::Error::new(
+            std::io::ErrorKind::NotFound,
+            "no such file",
+        ));
+        let source = err.source().unwrap();
+        let io = source.downcast_ref::<std::io::Error>().unwrap();
+        assert_eq!(io.kind(), std::io::ErrorKind::NotFound);
+        assert_eq!(io.to_string(), "no such file");
+
+        assert_eq!(
+            Error::from(ResponseError::Other).to_string(),
+            "Portal request didn't succeed: other error"
+        );
+
+        let err = Error::from(PortalError::NotFound("portal".to_owned()));
+        assert_eq!(err.to_string(), "The portal returned an error: not found");
+        assert!(matches!(
+            err.source().unwrap().downcast_ref::<PortalError>(),
+            Some(PortalError::NotFound(_))
+        ));
+
+        let err = Error::from(zbus::Error::Unsupported);
+        assert!(matches!(
+            err.source().unwrap().downcast_ref::<zbus::Error>(),
+            Some(zbus::Error::Unsupported)
+        ));
+
+        let err = Error::InvalidArgument("\"a\" is not a valid email address".to_owned());
+        assert_eq!(
+            err.to_string(),
+            "Invalid argument: \"a\" is not a valid email address"
+        );
+        assert!(err.source().is_none());
+
+        assert!(Error::NoResponse.source().is_none());
+        assert!(Error::ParseError("Failed to parse").source().is_none());
+    }
+}
@@ -45,8 +45,8 @@ use serde_repr::{Deserialize_repr, Serialize_repr};
 use zbus::zvariant::{Fd, OwnedObjectPath, SerializeDict, Type};
 
 use crate::{
-    helpers::{call_method, receive_signal, session_connection},
-    Error,
+    helpers::{bus_connection, call_method, receive_signal},
+    Error, Portal,
 };
 
 #[bitflags]
@@ -252,9 +252,9 @@ pub struct Flatpak<'a>(zbus::Proxy<'a>);
 impl<'a> Flatpak<'a> {
     /// Create a new instance of [`Flatpak`].
     pub async fn new() -> Result<Flatpak<'a>, Error> {
-        let connection = session_connection().await?;
+        let connection = bus_connection().await?;
         let proxy = zbus::ProxyBuilder::new_bare(&connection)
-            .interface("org.freedesktop.portal.Flatpak")?
+            .interface(Portal::Flatpak.as_interface_name())?
             .path(PATH)?
             .destination(DESTINATION)?
             .build()
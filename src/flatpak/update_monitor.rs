@@ -25,7 +25,7 @@ use zbus::zvariant::{DeserializeDict, ObjectPath, SerializeDict, Type};
 
 use super::DESTINATION;
 use crate::{
-    helpers::{call_method, receive_signal, session_connection},
+    helpers::{bus_connection, call_method, check_window_kind, receive_signal},
     Error, WindowIdentifier,
 };
 
@@ -117,7 +117,7 @@ impl<'a> UpdateMonitor<'a> {
     /// **Note** A [`UpdateMonitor`] is not supposed to be created
     /// manually.
     pub(crate) async fn new(path: ObjectPath<'a>) -> Result<UpdateMonitor<'a>, Error> {
-        let connection = session_connection().await?;
+        let connection = bus_connection().await?;
         let proxy = zbus::ProxyBuilder::new_bare(&connection)
             .interface("org.freedesktop.portal.Flatpak.UpdateMonitor")?
             .path(path)?
@@ -165,6 +165,7 @@ impl<'a> UpdateMonitor<'a> {
     #[doc(alias = "Update")]
     #[doc(alias = "xdp_portal_update_install")]
     pub async fn update(&self, identifier: &WindowIdentifier) -> Result<(), Error> {
+        check_window_kind(identifier)?;
         let options = UpdateOptions::default();
         call_method(self.inner(), "Update", &(&identifier, options)).await
     }
@@ -1,24 +1,34 @@
 use std::{
+    collections::HashMap,
     ffi::OsStr,
     fmt::Debug,
+    future::Future,
     os::unix::prelude::OsStrExt,
     path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    time::Duration,
 };
 
-#[cfg(feature = "async-std")]
-use async_std::{fs::File, prelude::*};
-use futures_util::StreamExt;
+use futures_util::{
+    future::{self, Either},
+    StreamExt,
+};
+use once_cell::sync::Lazy;
 use serde::Deserialize;
-#[cfg(feature = "tokio")]
-use tokio::{fs::File, io::AsyncReadExt};
-use zbus::zvariant::{ObjectPath, OwnedObjectPath, Type};
+use zbus::{
+    names::InterfaceName,
+    zvariant::{ObjectPath, OwnedObjectPath, Type},
+};
 
 use crate::{
     desktop::{
         request::{BasicResponse, Request, Response},
-        HandleToken,
+        HandleToken, DESTINATION, PATH,
     },
-    Error, PortalError, SESSION,
+    Error, Portal, PortalError, WindowIdentifier, WindowIdentifierKind, SESSION,
 };
 
 pub(crate) async fn call_request_method<R, B>(
@@ -31,15 +41,62 @@ where
     R: for<'de> Deserialize<'de> + Type + Debug,
     B: serde::ser::Serialize + Type + Debug,
 {
+    let call = async {
+        let result = request_method(proxy, handle_token, method_name, body).await;
+        #[cfg(feature = "tracing")]
+        trace_outcome(&result);
+        result
+    };
     #[cfg(feature = "tracing")]
-    tracing::info!(
-        "Calling a request method '{}:{}'",
-        proxy.interface(),
-        method_name
+    let call = tracing::Instrument::instrument(
+        call,
+        tracing::info_span!(
+            target: "ashpd::request",
+            "request",
+            interface = %proxy.interface(),
+            method = method_name,
+            handle_token = %handle_token,
+        ),
     );
-    #[cfg(feature = "tracing")]
-    tracing::debug!("The body is: {:#?}", body);
-    let request = Request::from_unique_name(handle_token).await?;
+    call.await
+}
+
+async fn request_method<R, B>(
+    proxy: &zbus::Proxy<'_>,
+    handle_token: &HandleToken,
+    method_name: &str,
+    body: &B,
+) -> Result<R, Error>
+where
+    R: for<'de> Deserialize<'de> + Type + Debug,
+    B: serde::ser::Serialize + Type + Debug,
+{
+    // Listen on the connection and the service the method is called on, which
+    // might be a mock portal. The match rule has to be added on the connection
+    // the call is sent through to be in place before the portal replies.
+    let request = Request::from_unique_name_with_connection(
+        proxy.connection(),
+        proxy.destination().to_owned(),
+        handle_token,
+    )
+    .await?;
+    send_request(proxy, &request, method_name, body).await
+}
+
+/// Call `method_name` and wait for the `Response` signal of `request`.
+///
+/// The portal can emit the signal before replying to the call, so the
+/// subscription is done before calling the method.
+async fn send_request<R, B>(
+    proxy: &zbus::Proxy<'_>,
+    request: &Request<'_>,
+    method_name: &str,
+    body: &B,
+) -> Result<R, Error>
+where
+    R: for<'de> Deserialize<'de> + Type + Debug,
+    B: serde::ser::Serialize + Type + Debug,
+{
     // We don't use receive_response because we want to create the stream in advance
     #[cfg(feature = "tracing")]
     tracing::info!(
@@ -60,19 +117,10 @@ where
                 "Received signal 'Response' on '{}'",
                 request.inner().interface()
             );
-            let response = match message.body::<Response<R>>()? {
-                Response::Err(e) => Err(e.into()),
-                Response::Ok(r) => Ok(r),
-            };
-            #[cfg(feature = "tracing")]
-            tracing::debug!("Received response {:#?}", response);
-            response as Result<_, Error>
+            message.body::<Response<R>>()?.into_result()
         },
         async {
-            let msg = proxy
-                .call_method(method_name, body)
-                .await
-                .map_err::<PortalError, _>(From::from)?;
+            let msg = call_with_method_timeout(proxy, method_name, body).await?;
             let path = msg.body::<OwnedObjectPath>()?.into_inner();
 
             #[cfg(feature = "tracing")]
@@ -118,10 +166,7 @@ where
         signal_name,
         proxy.interface()
     );
-    let content = message.body::<R>()?;
-    #[cfg(feature = "tracing")]
-    tracing::debug!("With body {:#?}", content);
-    Ok(content)
+    Ok(message.body::<R>()?)
 }
 
 pub(crate) async fn call_method<R, B>(
@@ -133,19 +178,435 @@ where
     R: for<'de> Deserialize<'de> + Type,
     B: serde::ser::Serialize + Type + Debug,
 {
+    let call = async {
+        let result = async {
+            let msg = call_with_method_timeout(proxy, method_name, body).await?;
+            let reply = msg.body::<R>()?;
+            msg.take_fds();
+            Ok(reply)
+        }
+        .await;
+        #[cfg(feature = "tracing")]
+        trace_outcome(&result);
+        result
+    };
+    #[cfg(feature = "tracing")]
+    let call = tracing::Instrument::instrument(
+        call,
+        tracing::info_span!(
+            target: "ashpd::call",
+            "call",
+            interface = %proxy.interface(),
+            method = method_name,
+        ),
+    );
+    call.await
+}
+
+/// Records whether the portal call of the current span succeeded, the replies
+/// are left out as they can contain private data.
+#[cfg(feature = "tracing")]
+fn trace_outcome<R>(result: &Result<R, Error>) {
+    match result {
+        Ok(_) => tracing::info!(outcome = "success"),
+        Err(Error::Response(err)) => tracing::info!(outcome = %err),
+        Err(err) => tracing::warn!(outcome = "failure", error = %err),
+    }
+}
+
+// Most D-Bus implementations give up on a method call after 25 seconds, too
+// short for the portals only replying once the user is done with a dialog.
+static METHOD_TIMEOUT: AtomicU64 = AtomicU64::new(5 * 60 * 1000);
+
+pub(crate) fn set_method_timeout(timeout: Duration) {
+    let millis = u64::try_from(timeout.as_millis()).unwrap_or(u64::MAX);
+    METHOD_TIMEOUT.store(millis, Ordering::Relaxed);
+}
+
+fn method_timeout() -> Option<Duration> {
+    let timeout = Duration::from_millis(METHOD_TIMEOUT.load(Ordering::Relaxed));
+    (!timeout.is_zero()).then_some(timeout)
+}
+
+async fn call_with_method_timeout<B>(
+    proxy: &zbus::Proxy<'_>,
+    method_name: &str,
+    body: &B,
+) -> Result<Arc<zbus::Message>, Error>
+where
+    B: serde::ser::Serialize + Type + Debug,
+{
+    call_with_timeout(proxy, method_name, body, method_timeout()).await
+}
+
+/// Call `method_name`, failing with [`Error::Timeout`] if no reply was
+/// received after `timeout`.
+async fn call_with_timeout<B>(
+    proxy: &zbus::Proxy<'_>,
+    method_name: &str,
+    body: &B,
+    timeout: Option<Duration>,
+) -> Result<Arc<zbus::Message>, Error>
+where
+    B: serde::ser::Serialize + Type + Debug,
+{
+    let call = async {
+        proxy
+            .call_method(method_name, body)
+            .await
+            .map_err(|err| Error::from(PortalError::from(err)))
+    };
+    let timeout = match timeout {
+        Some(timeout) => timeout,
+        None => return call.await,
+    };
+    futures_util::pin_mut!(call);
+    let sleep = sleep(timeout);
+    futures_util::pin_mut!(sleep);
+    match future::select(call, sleep).await {
+        Either::Left((reply, _)) => reply,
+        Either::Right(_) => {
+            #[cfg(feature = "tracing")]
+            tracing::info!("No reply to {}:{} in time", proxy.interface(), method_name);
+            Err(Error::Timeout)
+        }
+    }
+}
+
+/// Wait for the response of a request, giving up after `timeout` if any.
+///
+/// The request made through `proxy` with `handle_token` gets closed on
+/// timeout so the user interaction ends as well.
+pub(crate) async fn with_timeout<R>(
+    proxy: &zbus::Proxy<'_>,
+    timeout: Option<Duration>,
+    handle_token: &HandleToken,
+    response: impl Future<Output = Result<R, Error>>,
+) -> Result<R, Error> {
+    let timeout = match timeout {
+        Some(timeout) => timeout,
+        None => return response.await,
+    };
+    futures_util::pin_mut!(response);
+    let sleep = sleep(timeout);
+    futures_util::pin_mut!(sleep);
+    match future::select(response, sleep).await {
+        Either::Left((response, _)) => response,
+        Either::Right(_) => {
+            #[cfg(feature = "tracing")]
+            tracing::info!("Request {} timed out, closing it", handle_token);
+            close_request(proxy, handle_token).await;
+            Err(Error::Timeout)
+        }
+    }
+}
+
+/// Wait for the response of a request, giving up once `cancel` resolves.
+///
+/// The request made through `proxy` with `handle_token` gets closed on
+/// cancellation so the user interaction ends as well.
+pub(crate) async fn with_cancellation<R>(
+    proxy: &zbus::Proxy<'_>,
+    cancel: impl Future,
+    handle_token: &HandleToken,
+    response: impl Future<Output = Result<R, Error>>,
+) -> Result<R, Error> {
+    futures_util::pin_mut!(response);
+    futures_util::pin_mut!(cancel);
+    match future::select(response, cancel).await {
+        Either::Left((response, _)) => response,
+        Either::Right(_) => {
+            #[cfg(feature = "tracing")]
+            tracing::info!("Request {} cancelled, closing it", handle_token);
+            close_request(proxy, handle_token).await;
+            Err(Error::Cancelled)
+        }
+    }
+}
+
+/// Closes the request made through `proxy` with `handle_token`, on the
+/// connection and the service the method was called on.
+async fn close_request(proxy: &zbus::Proxy<'_>, handle_token: &HandleToken) {
+    let request = Request::from_unique_name_with_connection(
+        proxy.connection(),
+        proxy.destination().to_owned(),
+        handle_token,
+    )
+    .await;
+    // The portal might not be running at all, nothing to close then.
+    if let Ok(request) = request {
+        let _ = request.close().await;
+    }
+}
+
+pub(crate) async fn sleep(duration: Duration) {
+    #[cfg(feature = "async-std")]
+    async_std::task::sleep(duration).await;
+    #[cfg(feature = "tokio")]
+    tokio::time::sleep(duration).await;
+    #[cfg(not(any(feature = "async-std", feature = "tokio")))]
+    async_io::Timer::after(duration).await;
+}
+
+/// Calls `Close` on the object of `proxy` in the background, for the `Drop`
+/// implementations that can't wait for the portal.
+///
+/// The object might have been closed by the portal already, so the failure is
+/// ignored.
+pub(crate) fn close_detached(proxy: &zbus::Proxy<'_>) {
+    let connection = proxy.connection().clone();
+    let destination = proxy.destination().to_owned();
+    let path = proxy.path().to_owned();
+    let interface = proxy.interface().to_owned();
+    spawn_detached(async move {
+        let _ = connection
+            .call_method(Some(destination), path, Some(interface), "Close", &())
+            .await;
+    });
+}
+
+/// Runs `future` in the background, on the runtime in use if any.
+///
+/// Outside of tokio, the session connection's executor is used. It is driven
+/// by zbus or, for a connection built without its internal executor and set
+/// with [`crate::set_session_connection`], by the application's own runtime.
+pub(crate) fn spawn_detached(future: impl Future<Output = ()> + Send + 'static) {
+    #[cfg(not(feature = "tokio"))]
+    if let Some(connection) = SESSION.get() {
+        connection.executor().spawn(future, "ashpd").detach();
+        return;
+    }
+    #[cfg(feature = "async-std")]
+    async_std::task::spawn(future);
+    #[cfg(feature = "tokio")]
+    match tokio::runtime::Handle::try_current() {
+        Ok(handle) => {
+            handle.spawn(future);
+        }
+        Err(_err) => {
+            #[cfg(feature = "tracing")]
+            tracing::warn!("No tokio runtime to spawn a task on: {}", _err);
+        }
+    }
+    #[cfg(not(any(feature = "async-std", feature = "tokio")))]
+    std::thread::spawn(move || zbus::block_on(future));
+}
+
+/// The object path of the `org.freedesktop.portal.Request` a portal method
+/// call made with `handle_token` will use.
+///
+/// Per the specifications, the path is
+/// `/org/freedesktop/portal/desktop/request/SENDER/TOKEN`, where `SENDER` is
+/// the caller's unique name with the initial `:` removed and all the `.`
+/// replaced by `_`. Knowing it in advance helps correlating the D-Bus traffic
+/// with the application's actions.
+///
+/// ```rust
+/// let path = ashpd::request_path(":1.42", "ashpd_token").unwrap();
+/// assert_eq!(
+///     path.as_str(),
+///     "/org/freedesktop/portal/desktop/request/1_42/ashpd_token"
+/// );
+/// ```
+pub fn request_path(unique_name: &str, handle_token: &str) -> Result<OwnedObjectPath, Error> {
+    handle_path("request", unique_name, handle_token)
+}
+
+pub(crate) fn session_path(
+    unique_name: &str,
+    handle_token: &str,
+) -> Result<OwnedObjectPath, Error> {
+    handle_path("session", unique_name, handle_token)
+}
+
+fn handle_path(
+    kind: &str,
+    unique_name: &str,
+    handle_token: &str,
+) -> Result<OwnedObjectPath, Error> {
+    let unique_identifier = unique_name.trim_start_matches(':').replace('.', "_");
+    let path = ObjectPath::try_from(format!(
+        "/org/freedesktop/portal/desktop/{}/{}/{}",
+        kind, unique_identifier, handle_token
+    ))?;
+    Ok(path.into())
+}
+
+/// Fails with [`Error::NoWindow`] when a portal dialog can't be shown, that is
+/// without a parent window and without a Wayland or X11 display to show the
+/// dialog on.
+pub(crate) fn ensure_window(identifier: &WindowIdentifier) -> Result<(), Error> {
+    if !matches!(identifier, WindowIdentifier::None) || has_display() {
+        return Ok(());
+    }
     #[cfg(feature = "tracing")]
     {
-        tracing::info!("Calling method {}:{}", proxy.interface(), method_name);
-        tracing::debug!("With body {:#?}", body);
+        static WARNING: std::sync::Once = std::sync::Once::new();
+        WARNING.call_once(|| {
+            tracing::warn!(
+                "Neither WAYLAND_DISPLAY nor DISPLAY are set, portal dialogs can't be shown"
+            )
+        });
     }
-    let msg = proxy
-        .call_method(method_name, body)
-        .await
-        .map_err::<PortalError, _>(From::from)?;
-    let reply = msg.body::<R>()?;
-    msg.take_fds();
+    Err(Error::NoWindow)
+}
+
+fn has_display() -> bool {
+    is_display_set(std::env::var_os("WAYLAND_DISPLAY"))
+        || is_display_set(std::env::var_os("DISPLAY"))
+}
+
+fn is_display_set(display: Option<std::ffi::OsString>) -> bool {
+    matches!(display, Some(display) if !display.is_empty())
+}
+
+static STRICT_WINDOW_IDENTIFIER: AtomicBool = AtomicBool::new(false);
+
+pub(crate) fn set_strict_window_identifier(strict: bool) {
+    STRICT_WINDOW_IDENTIFIER.store(strict, Ordering::Relaxed);
+}
+
+/// Warns, or fails in strict mode, if the portal can't parent its dialog to
+/// `identifier` as it belongs to another display server than the session's.
+pub(crate) fn check_window_kind(identifier: &WindowIdentifier) -> Result<(), Error> {
+    let kind = identifier.kind();
+    let session = session_kind(
+        std::env::var_os("XDG_SESSION_TYPE"),
+        std::env::var_os("WAYLAND_DISPLAY"),
+        std::env::var_os("DISPLAY"),
+    );
+    let has_x11 = is_display_set(std::env::var_os("DISPLAY"));
+    let session = match session {
+        Some(session) if !is_window_kind_supported(kind, session, has_x11) => session,
+        _ => return Ok(()),
+    };
+    let message = format!(
+        "The {} window identifier {} can't be used in a {} session",
+        kind, identifier, session
+    );
+    if STRICT_WINDOW_IDENTIFIER.load(Ordering::Relaxed) {
+        return Err(Error::InvalidArgument(message));
+    }
+    #[cfg(feature = "tracing")]
+    tracing::warn!("{}", message);
+    Ok(())
+}
+
+/// The display server of the session, from `XDG_SESSION_TYPE` or the display
+/// environment variables.
+fn session_kind(
+    session_type: Option<std::ffi::OsString>,
+    wayland_display: Option<std::ffi::OsString>,
+    display: Option<std::ffi::OsString>,
+) -> Option<WindowIdentifierKind> {
+    match session_type.as_ref().and_then(|t| t.to_str()) {
+        Some("wayland") => Some(WindowIdentifierKind::Wayland),
+        Some("x11") => Some(WindowIdentifierKind::X11),
+        _ if is_display_set(wayland_display) => Some(WindowIdentifierKind::Wayland),
+        _ if is_display_set(display) => Some(WindowIdentifierKind::X11),
+        _ => None,
+    }
+}
+
+fn is_window_kind_supported(
+    kind: WindowIdentifierKind,
+    session: WindowIdentifierKind,
+    has_x11: bool,
+) -> bool {
+    match (kind, session) {
+        (WindowIdentifierKind::None, _) => true,
+        // The X11 windows of a Wayland session go through Xwayland
+        (WindowIdentifierKind::X11, WindowIdentifierKind::Wayland) => has_x11,
+        (kind, session) => kind == session,
+    }
+}
+
+static AVAILABLE_PORTALS: Lazy<Mutex<HashMap<Portal, bool>>> = Lazy::new(Default::default);
+
+pub(crate) async fn is_portal_available(portal: Portal) -> Result<bool, Error> {
+    if let Some(available) = AVAILABLE_PORTALS.lock().unwrap().get(&portal) {
+        return Ok(*available);
+    }
+    // Answer right away instead of waiting for the portal to start up
+    let connection = bus_connection().await?;
+    let available = is_portal_available_on(
+        &connection,
+        portal.destination_and_path(),
+        portal.as_interface_name(),
+    )
+    .await?;
+    AVAILABLE_PORTALS.lock().unwrap().insert(portal, available);
+    Ok(available)
+}
+
+// Every portal interface has a `version` property, which fails to be retrieved
+// if the interface is not implemented.
+async fn is_portal_available_on(
+    connection: &zbus::Connection,
+    (destination, path): (&str, &str),
+    interface: &str,
+) -> Result<bool, Error> {
+    let interface = InterfaceName::try_from(interface)?;
+    let proxy = zbus::fdo::PropertiesProxy::builder(connection)
+        .destination(destination)?
+        .path(path)?
+        .build()
+        .await?;
+    #[cfg(feature = "tracing")]
+    tracing::info!("Checking whether {} is available", interface);
+    match proxy.get(interface, "version").await {
+        Ok(_) => Ok(true),
+        Err(
+            zbus::fdo::Error::UnknownInterface(_)
+            | zbus::fdo::Error::UnknownProperty(_)
+            | zbus::fdo::Error::InvalidArgs(_)
+            | zbus::fdo::Error::UnknownObject(_)
+            | zbus::fdo::Error::ServiceUnknown(_)
+            | zbus::fdo::Error::NameHasNoOwner(_),
+        ) => Ok(false),
+        Err(err) => Err(err.into()),
+    }
+}
+
+/// Reads the content of the file at `path`, failing with [`Error::File`].
+pub(crate) async fn read_file(path: &Path) -> Result<Vec<u8>, Error> {
+    #[cfg(feature = "async-std")]
+    let content = async_std::fs::read(path).await;
+    #[cfg(feature = "tokio")]
+    let content = tokio::fs::read(path).await;
+    #[cfg(not(any(feature = "async-std", feature = "tokio")))]
+    let content = std::fs::read(path);
+    content.map_err(|err| Error::File(path.to_owned(), err))
+}
 
-    Ok(reply)
+/// Writes `data` to the file at `path`, failing with [`Error::File`].
+pub(crate) async fn write_file(path: &Path, data: &[u8]) -> Result<(), Error> {
+    #[cfg(feature = "async-std")]
+    let result = async_std::fs::write(path, data).await;
+    #[cfg(feature = "tokio")]
+    let result = tokio::fs::write(path, data).await;
+    #[cfg(not(any(feature = "async-std", feature = "tokio")))]
+    let result = std::fs::write(path, data);
+    result.map_err(|err| Error::File(path.to_owned(), err))
+}
+
+/// Runs the blocking `f`, like file system calls without an async
+/// counterpart, on a thread of its own instead of the executor.
+pub(crate) async fn unblock<T: Send + 'static>(f: impl FnOnce() -> T + Send + 'static) -> T {
+    #[cfg(feature = "tokio")]
+    return tokio::task::spawn_blocking(f)
+        .await
+        .expect("The blocking task panicked");
+    #[cfg(all(feature = "async-std", not(feature = "tokio")))]
+    return async_std::task::spawn_blocking(f).await;
+    #[cfg(not(any(feature = "async-std", feature = "tokio")))]
+    {
+        let (sender, receiver) = futures_channel::oneshot::channel();
+        std::thread::spawn(move || {
+            let _ = sender.send(f());
+        });
+        receiver.await.expect("The blocking task panicked")
+    }
 }
 
 // Some portals returns paths which are bytes and not a typical string
@@ -155,30 +616,22 @@ pub(crate) fn path_from_null_terminated(bytes: Vec<u8>) -> PathBuf {
     Path::new(OsStr::from_bytes(bytes.split_last().unwrap().1)).to_path_buf()
 }
 
-pub(crate) async fn is_flatpak() -> bool {
-    #[cfg(feature = "async-std")]
-    {
-        async_std::path::PathBuf::from("/.flatpak-info")
-            .exists()
-            .await
-    }
-    #[cfg(not(feature = "async-std"))]
-    {
-        std::path::PathBuf::from("/.flatpak-info").exists()
-    }
+pub(crate) fn is_flatpak() -> bool {
+    is_flatpak_with(Path::new("/.flatpak-info"), std::env::var_os("container"))
+}
+
+// Flatpak sets the `container` environment variable, but it might be cleared
+// by the application, so the info file is checked as well.
+fn is_flatpak_with(info_path: &Path, container: Option<std::ffi::OsString>) -> bool {
+    container.is_some_and(|container| container == "flatpak") || info_path.exists()
 }
 
-pub(crate) async fn is_snap() -> bool {
+// Reading a file of `/proc` doesn't block, it is generated by the kernel.
+pub(crate) fn is_snap() -> bool {
     let pid = std::process::id();
     let path = format!("/proc/{}/cgroup", pid);
-    let mut file = match File::open(path).await {
-        Ok(file) => file,
-        Err(_) => return false,
-    };
-
-    let mut buffer = String::new();
-    match file.read_to_string(&mut buffer).await {
-        Ok(_) => cgroup_v2_is_snap(&buffer),
+    match std::fs::read_to_string(path) {
+        Ok(buffer) => cgroup_v2_is_snap(&buffer),
         Err(_) => false,
     }
 }
@@ -203,20 +656,631 @@ fn cgroup_v2_is_snap(cgroups: &str) -> bool {
         .any(|x| x.unwrap_or(false))
 }
 
-pub(crate) async fn session_connection() -> zbus::Result<zbus::Connection> {
+/// Whether the Desktop portal was waited for and the application id
+/// registered, or the application set the connection itself.
+static DESKTOP_READY: AtomicBool = AtomicBool::new(false);
+
+pub(crate) fn set_session_connection(
+    connection: zbus::Connection,
+) -> std::result::Result<(), zbus::Connection> {
+    SESSION.set(connection)?;
+    DESKTOP_READY.store(true, Ordering::Relaxed);
+    Ok(())
+}
+
+/// The session bus connection, for the services other than the Desktop
+/// portal, like the documents portal.
+pub(crate) async fn bus_connection() -> zbus::Result<zbus::Connection> {
     if let Some(cnx) = SESSION.get() {
         Ok(cnx.clone())
     } else {
         let cnx = zbus::Connection::session().await?;
-        SESSION.set(cnx.clone()).expect("Can't reset a OnceCell");
-        Ok(cnx)
+        // Another task might have set it while connecting
+        Ok(SESSION.get_or_init(|| cnx).clone())
+    }
+}
+
+/// The session bus connection, once the Desktop portal is running.
+pub(crate) async fn session_connection() -> zbus::Result<zbus::Connection> {
+    let cnx = bus_connection().await?;
+    if !DESKTOP_READY.load(Ordering::Relaxed) {
+        let timeout = Duration::from_millis(PORTAL_STARTUP_TIMEOUT.load(Ordering::Relaxed));
+        if !wait_for_portal(&cnx, timeout).await? {
+            #[cfg(feature = "tracing")]
+            tracing::warn!("{} didn't show up after {:?}", DESTINATION, timeout);
+        }
+        register_app_id(&cnx).await;
+        DESKTOP_READY.store(true, Ordering::Relaxed);
     }
+    Ok(cnx)
+}
+
+static APP_ID: Mutex<Option<String>> = Mutex::new(None);
+
+pub(crate) fn set_app_id(app_id: &str) -> Result<(), Error> {
+    if !is_valid_app_id(app_id) {
+        return Err(Error::InvalidArgument(format!(
+            "{:?} is not a valid application id",
+            app_id
+        )));
+    }
+    *APP_ID.lock().unwrap() = Some(app_id.to_owned());
+    Ok(())
+}
+
+/// The application id set with [`set_app_id`], if any.
+pub(crate) fn app_id() -> Option<String> {
+    APP_ID.lock().unwrap().clone()
+}
+
+// The application ids follow the D-Bus well-known bus names rules, with at
+// least two elements. Only `-` isn't recommended, but still allowed.
+fn is_valid_app_id(app_id: &str) -> bool {
+    let elements = app_id.split('.').collect::<Vec<_>>();
+    app_id.len() <= 255
+        && elements.len() >= 2
+        && elements.iter().all(|element| {
+            !element.is_empty()
+                && !element.starts_with(|c: char| c.is_ascii_digit())
+                && element
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+        })
+}
+
+// The sandboxed applications ids are known to the portal already, for the
+// others it has to be registered before using any portal.
+async fn register_app_id(connection: &zbus::Connection) {
+    let app_id = match app_id() {
+        Some(app_id) => app_id,
+        None => return,
+    };
+    if crate::is_sandboxed() {
+        return;
+    }
+    #[cfg(feature = "tracing")]
+    tracing::info!("Registering the application id {}", app_id);
+    let options: HashMap<&str, zbus::zvariant::Value<'_>> = HashMap::new();
+    if let Err(_err) = connection
+        .call_method(
+            Some(DESTINATION),
+            PATH,
+            Some("org.freedesktop.host.portal.Registry"),
+            "Register",
+            &(&app_id, options),
+        )
+        .await
+    {
+        // Older portals don't have a registry, they ignore the application id
+        #[cfg(feature = "tracing")]
+        tracing::warn!("Failed to register the application id {}: {}", app_id, _err);
+    }
+}
+
+/// How long to wait for xdg-desktop-portal to show up on the bus, in
+/// milliseconds.
+static PORTAL_STARTUP_TIMEOUT: AtomicU64 = AtomicU64::new(3000);
+
+pub(crate) fn set_portal_startup_timeout(timeout: Duration) {
+    let millis = u64::try_from(timeout.as_millis()).unwrap_or(u64::MAX);
+    PORTAL_STARTUP_TIMEOUT.store(millis, Ordering::Relaxed);
+}
+
+// On login, the applications might be started before xdg-desktop-portal is,
+// making the first portal calls fail as the name has no owner yet. Returns
+// whether it showed up before `timeout`.
+async fn wait_for_portal(connection: &zbus::Connection, timeout: Duration) -> zbus::Result<bool> {
+    if timeout.is_zero() {
+        return Ok(true);
+    }
+    let proxy = zbus::fdo::DBusProxy::new(connection).await?;
+    // Subscribe first to not miss the name being acquired right after checking
+    let owner_changes = proxy
+        .receive_name_owner_changed_with_args(&[(0, DESTINATION)])
+        .await?
+        .map(|signal| {
+            signal
+                .args()
+                .map(|args| args.new_owner().is_some())
+                .unwrap_or_default()
+        });
+    let destination = zbus::names::BusName::try_from(DESTINATION)?;
+    // The bus starts activatable services on the first call on its own
+    let has_owner = proxy.name_has_owner(destination.clone()).await?
+        || proxy
+            .list_activatable_names()
+            .await?
+            .iter()
+            .any(|name| *name == destination);
+    Ok(wait_for_owner(has_owner, owner_changes, timeout).await)
+}
+
+/// Whether the name got an owner before `timeout`, given whether it already
+/// has one and a stream of its owner changes.
+async fn wait_for_owner(
+    has_owner: bool,
+    owner_changes: impl futures_util::Stream<Item = bool>,
+    timeout: Duration,
+) -> bool {
+    if has_owner {
+        return true;
+    }
+    #[cfg(feature = "tracing")]
+    tracing::info!("Waiting for {} to show up", DESTINATION);
+    let acquired = owner_changes.filter(|has_owner| future::ready(*has_owner));
+    futures_util::pin_mut!(acquired);
+    let acquired = acquired.next();
+    let sleep = sleep(timeout);
+    futures_util::pin_mut!(sleep);
+    matches!(
+        future::select(acquired, sleep).await,
+        Either::Left((Some(true), _))
+    )
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    // The tokio feature makes zbus expect a tokio UnixStream instead
+    #[cfg(not(feature = "tokio"))]
+    struct MockScreenshot;
+
+    #[cfg(not(feature = "tokio"))]
+    #[zbus::dbus_interface(name = "org.freedesktop.portal.Screenshot")]
+    impl MockScreenshot {
+        #[dbus_interface(property, name = "version")]
+        fn version(&self) -> u32 {
+            2
+        }
+    }
+
+    #[test]
+    fn test_is_display_set() {
+        assert!(is_display_set(Some("wayland-0".into())));
+        assert!(is_display_set(Some(":0".into())));
+        assert!(!is_display_set(Some("".into())));
+        assert!(!is_display_set(None));
+        assert!(ensure_window(&WindowIdentifier::from_xid(1024)).is_ok());
+    }
+
+    #[test]
+    fn test_window_kind() {
+        use WindowIdentifierKind::{None as NoWindow, Wayland, X11};
+
+        assert_eq!(
+            session_kind(Some("wayland".into()), None, None),
+            Some(Wayland)
+        );
+        assert_eq!(
+            session_kind(Some("x11".into()), Some("wayland-0".into()), None),
+            Some(X11)
+        );
+        assert_eq!(
+            session_kind(
+                Some("tty".into()),
+                Some("wayland-0".into()),
+                Some(":0".into())
+            ),
+            Some(Wayland)
+        );
+        assert_eq!(
+            session_kind(None, Some("".into()), Some(":0".into())),
+            Some(X11)
+        );
+        assert_eq!(session_kind(None, None, None), None);
+
+        // Matching
+        assert!(is_window_kind_supported(Wayland, Wayland, false));
+        assert!(is_window_kind_supported(X11, X11, true));
+        assert!(is_window_kind_supported(NoWindow, X11, true));
+        assert!(is_window_kind_supported(NoWindow, Wayland, false));
+        // Through Xwayland
+        assert!(is_window_kind_supported(X11, Wayland, true));
+        // Mismatching
+        assert!(!is_window_kind_supported(Wayland, X11, true));
+        assert!(!is_window_kind_supported(X11, Wayland, false));
+    }
+
+    #[test]
+    fn test_handle_paths() {
+        assert_eq!(
+            request_path(":1.42", "ashpd_abc123").unwrap().as_str(),
+            "/org/freedesktop/portal/desktop/request/1_42/ashpd_abc123"
+        );
+        assert_eq!(
+            session_path(":1.2039", "ashpd_session").unwrap().as_str(),
+            "/org/freedesktop/portal/desktop/session/1_2039/ashpd_session"
+        );
+        // Only [A-Z][a-z][0-9]_ are valid object path elements
+        assert!(request_path(":1.42", "ashpd-token").is_err());
+    }
+
+    #[cfg(not(feature = "tokio"))]
+    #[test]
+    fn test_is_portal_available() {
+        zbus::block_on(async {
+            let guid = zbus::Guid::generate();
+            let (server, client) = std::os::unix::net::UnixStream::pair().unwrap();
+            let (_server, client) = futures_util::try_join!(
+                zbus::ConnectionBuilder::unix_stream(server)
+                    .server(&guid)
+                    .p2p()
+                    .serve_at(PATH, MockScreenshot)
+                    .unwrap()
+                    .build(),
+                zbus::ConnectionBuilder::unix_stream(client).p2p().build(),
+            )
+            .unwrap();
+
+            let location = (DESTINATION, PATH);
+            assert!(is_portal_available_on(
+                &client,
+                location,
+                Portal::Screenshot.as_interface_name()
+            )
+            .await
+            .unwrap());
+            assert!(!is_portal_available_on(
+                &client,
+                location,
+                Portal::Wallpaper.as_interface_name()
+            )
+            .await
+            .unwrap());
+            assert!(
+                is_portal_available_on(&client, location, "not an interface")
+                    .await
+                    .is_err()
+            );
+        });
+    }
+
+    #[cfg(not(feature = "tokio"))]
+    struct MockSlow;
+
+    #[cfg(not(feature = "tokio"))]
+    #[zbus::dbus_interface(name = "org.freedesktop.portal.Slow")]
+    impl MockSlow {
+        async fn slow(&self, delay: u64) {
+            sleep(Duration::from_millis(delay)).await;
+        }
+    }
+
+    #[cfg(not(feature = "tokio"))]
+    #[test]
+    fn test_method_timeout() {
+        zbus::block_on(async {
+            let guid = zbus::Guid::generate();
+            let (server, client) = std::os::unix::net::UnixStream::pair().unwrap();
+            let (_server, client) = futures_util::try_join!(
+                zbus::ConnectionBuilder::unix_stream(server)
+                    .server(&guid)
+                    .p2p()
+                    .serve_at(PATH, MockSlow)
+                    .unwrap()
+                    .build(),
+                zbus::ConnectionBuilder::unix_stream(client).p2p().build(),
+            )
+            .unwrap();
+            let proxy = zbus::ProxyBuilder::new_bare(&client)
+                .interface("org.freedesktop.portal.Slow")
+                .unwrap()
+                .path(PATH)
+                .unwrap()
+                .destination(DESTINATION)
+                .unwrap()
+                .build()
+                .await
+                .unwrap();
+
+            let timeout = Some(Duration::from_millis(100));
+            assert!(call_with_timeout(&proxy, "Slow", &(0u64), timeout)
+                .await
+                .is_ok());
+            assert!(matches!(
+                call_with_timeout(&proxy, "Slow", &(1000u64), timeout).await,
+                Err(Error::Timeout)
+            ));
+            assert!(call_with_timeout(&proxy, "Slow", &(200u64), None)
+                .await
+                .is_ok());
+        });
+    }
+
+    #[cfg(not(feature = "tokio"))]
+    const REQUEST_PATH: &str = "/org/freedesktop/portal/desktop/request/1_42/ashpd_test";
+
+    #[cfg(not(feature = "tokio"))]
+    struct MockEager;
+
+    #[cfg(not(feature = "tokio"))]
+    #[zbus::dbus_interface(name = "org.freedesktop.portal.Eager")]
+    impl MockEager {
+        // Responds before returning the request handle
+        async fn pick(
+            &self,
+            #[zbus(connection)] connection: &zbus::Connection,
+        ) -> zbus::fdo::Result<OwnedObjectPath> {
+            connection
+                .emit_signal(
+                    None::<zbus::names::BusName<'_>>,
+                    REQUEST_PATH,
+                    "org.freedesktop.portal.Request",
+                    "Response",
+                    &(0u32, HashMap::<&str, zbus::zvariant::Value<'_>>::new()),
+                )
+                .await?;
+            Ok(ObjectPath::try_from(REQUEST_PATH).unwrap().into())
+        }
+    }
+
+    #[cfg(not(feature = "tokio"))]
+    #[test]
+    fn test_early_response() {
+        zbus::block_on(async {
+            let guid = zbus::Guid::generate();
+            let (server, client) = std::os::unix::net::UnixStream::pair().unwrap();
+            let (_server, client) = futures_util::try_join!(
+                zbus::ConnectionBuilder::unix_stream(server)
+                    .server(&guid)
+                    .p2p()
+                    .serve_at(PATH, MockEager)
+                    .unwrap()
+                    .build(),
+                zbus::ConnectionBuilder::unix_stream(client).p2p().build(),
+            )
+            .unwrap();
+            let proxy: zbus::Proxy<'_> = zbus::ProxyBuilder::new_bare(&client)
+                .interface("org.freedesktop.portal.Eager")
+                .unwrap()
+                .path(PATH)
+                .unwrap()
+                .destination(DESTINATION)
+                .unwrap()
+                .build()
+                .await
+                .unwrap();
+            let request =
+                Request::with_connection(&client, proxy.destination().to_owned(), REQUEST_PATH)
+                    .await
+                    .unwrap();
+
+            let response = send_request::<BasicResponse, _>(&proxy, &request, "Pick", &());
+            let timeout = sleep(Duration::from_secs(10));
+            futures_util::pin_mut!(response, timeout);
+            match future::select(response, timeout).await {
+                Either::Left((response, _)) => assert!(response.is_ok()),
+                Either::Right(_) => panic!("The response emitted before the reply was missed"),
+            }
+        });
+    }
+
+    #[test]
+    fn test_is_valid_app_id() {
+        assert!(is_valid_app_id("org.gnome.Nautilus"));
+        assert!(is_valid_app_id("com.belmoussaoui.ashpd.demo"));
+        assert!(is_valid_app_id("org._7_zip.Archiver"));
+        assert!(is_valid_app_id("io.github.some-user.App"));
+        assert!(!is_valid_app_id(""));
+        assert!(!is_valid_app_id("nautilus"));
+        assert!(!is_valid_app_id("org..Nautilus"));
+        assert!(!is_valid_app_id("org.7zip.Archiver"));
+        assert!(!is_valid_app_id("org.gnome.Nautilus.desktop/"));
+        assert!(!is_valid_app_id(&format!("org.{}", "a".repeat(255))));
+        assert!(matches!(
+            set_app_id("not an id"),
+            Err(Error::InvalidArgument(message)) if message.contains("not an id")
+        ));
+    }
+
+    #[test]
+    fn test_wait_for_owner() {
+        let timeout = Duration::from_secs(60);
+        assert!(zbus::block_on(wait_for_owner(
+            true,
+            futures_util::stream::pending(),
+            timeout
+        )));
+
+        // The name gets an owner after a while, after being released once
+        let (sender, receiver) = futures_channel::mpsc::unbounded();
+        let acquire = std::thread::spawn(move || {
+            sender.unbounded_send(false).unwrap();
+            std::thread::sleep(Duration::from_millis(50));
+            sender.unbounded_send(true).unwrap();
+        });
+        assert!(zbus::block_on(wait_for_owner(false, receiver, timeout)));
+        acquire.join().unwrap();
+
+        // It never does
+        assert!(!zbus::block_on(wait_for_owner(
+            false,
+            futures_util::stream::pending(),
+            Duration::from_millis(10)
+        )));
+    }
+
+    // A bus on which `name` gets an owner once the activatable names are
+    // listed, after the client subscribed to the owner changes
+    #[cfg(not(feature = "tokio"))]
+    struct MockBus(&'static str);
+
+    #[cfg(not(feature = "tokio"))]
+    #[zbus::dbus_interface(name = "org.freedesktop.DBus")]
+    impl MockBus {
+        fn name_has_owner(&self, _name: &str) -> bool {
+            false
+        }
+
+        async fn list_activatable_names(
+            &self,
+            #[zbus(signal_context)] ctxt: zbus::SignalContext<'_>,
+        ) -> Vec<String> {
+            Self::name_owner_changed(&ctxt, self.0, "", ":1.7")
+                .await
+                .unwrap();
+            Vec::new()
+        }
+
+        #[dbus_interface(signal)]
+        async fn name_owner_changed(
+            ctxt: &zbus::SignalContext<'_>,
+            name: &str,
+            old_owner: &str,
+            new_owner: &str,
+        ) -> zbus::Result<()>;
+    }
+
+    #[cfg(not(feature = "tokio"))]
+    #[test]
+    fn test_wait_for_portal() {
+        zbus::block_on(async {
+            for (name, timeout, expected) in [
+                (DESTINATION, Duration::from_secs(60), true),
+                ("org.example.Other", Duration::from_millis(10), false),
+            ] {
+                let guid = zbus::Guid::generate();
+                let (server, client) = std::os::unix::net::UnixStream::pair().unwrap();
+                let (_server, client) = futures_util::try_join!(
+                    zbus::ConnectionBuilder::unix_stream(server)
+                        .server(&guid)
+                        .p2p()
+                        .serve_at("/org/freedesktop/DBus", MockBus(name))
+                        .unwrap()
+                        // The signals of the bus are filtered on its name
+                        .unique_name("org.freedesktop.DBus")
+                        .unwrap()
+                        .build(),
+                    zbus::ConnectionBuilder::unix_stream(client).p2p().build(),
+                )
+                .unwrap();
+                assert_eq!(wait_for_portal(&client, timeout).await.unwrap(), expected);
+            }
+        });
+    }
+
+    #[cfg(not(feature = "tokio"))]
+    struct MockRequest(futures_channel::mpsc::UnboundedSender<()>);
+
+    #[cfg(not(feature = "tokio"))]
+    #[zbus::dbus_interface(name = "org.freedesktop.portal.Request")]
+    impl MockRequest {
+        fn close(&self) {
+            let _ = self.0.unbounded_send(());
+        }
+    }
+
+    /// A proxy to a mock portal serving the request of `handle_token`, along
+    /// with the receiver of its `Close` calls.
+    #[cfg(not(feature = "tokio"))]
+    async fn mock_request(
+        handle_token: &HandleToken,
+    ) -> (
+        zbus::Connection,
+        zbus::Proxy<'static>,
+        futures_channel::mpsc::UnboundedReceiver<()>,
+    ) {
+        let (sender, receiver) = futures_channel::mpsc::unbounded();
+        let path = request_path(":1.42", &handle_token.to_string()).unwrap();
+        let guid = zbus::Guid::generate();
+        let (server, client) = std::os::unix::net::UnixStream::pair().unwrap();
+        let (server, client) = futures_util::try_join!(
+            zbus::ConnectionBuilder::unix_stream(server)
+                .server(&guid)
+                .p2p()
+                .serve_at(path.as_str(), MockRequest(sender))
+                .unwrap()
+                .build(),
+            zbus::ConnectionBuilder::unix_stream(client)
+                .p2p()
+                .unique_name(":1.42")
+                .unwrap()
+                .build(),
+        )
+        .unwrap();
+        let proxy = zbus::ProxyBuilder::new_bare(&client)
+            .interface("org.freedesktop.portal.Mock")
+            .unwrap()
+            .path(PATH)
+            .unwrap()
+            .destination("org.freedesktop.impl.portal.Mock")
+            .unwrap()
+            .build()
+            .await
+            .unwrap();
+        (server, proxy, receiver)
+    }
+
+    #[cfg(not(feature = "tokio"))]
+    #[test]
+    fn test_with_timeout() {
+        zbus::block_on(async {
+            let handle_token = HandleToken::try_from("ashpd_timeout").unwrap();
+            let (_server, proxy, mut closed) = mock_request(&handle_token).await;
+
+            let never = future::pending::<Result<(), Error>>();
+            let result = with_timeout(
+                &proxy,
+                Some(Duration::from_millis(10)),
+                &handle_token,
+                never,
+            )
+            .await;
+            assert!(matches!(result, Err(Error::Timeout)));
+            // Closed on the connection and the service the call was made on
+            assert_eq!(closed.next().await, Some(()));
+
+            let ready = future::ready(Ok(1));
+            let result =
+                with_timeout(&proxy, Some(Duration::from_secs(60)), &handle_token, ready).await;
+            assert_eq!(result.unwrap(), 1);
+            assert!(closed.try_recv().is_err());
+        });
+    }
+
+    #[cfg(not(feature = "tokio"))]
+    #[test]
+    fn test_with_cancellation() {
+        zbus::block_on(async {
+            let handle_token = HandleToken::try_from("ashpd_cancellation").unwrap();
+            let (_server, proxy, mut closed) = mock_request(&handle_token).await;
+
+            let never = future::pending::<Result<(), Error>>();
+            let result = with_cancellation(
+                &proxy,
+                sleep(Duration::from_millis(10)),
+                &handle_token,
+                never,
+            )
+            .await;
+            assert!(matches!(result, Err(Error::Cancelled)));
+            assert_eq!(closed.next().await, Some(()));
+
+            let ready = future::ready(Ok(1));
+            let result =
+                with_cancellation(&proxy, future::pending::<()>(), &handle_token, ready).await;
+            assert_eq!(result.unwrap(), 1);
+            assert!(closed.try_recv().is_err());
+        });
+    }
+
+    #[test]
+    fn test_is_flatpak() {
+        let dir = std::env::temp_dir().join(format!("ashpd-flatpak-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let info_path = dir.join(".flatpak-info");
+
+        assert!(!is_flatpak_with(&info_path, None));
+        assert!(!is_flatpak_with(&info_path, Some("oci".into())));
+        assert!(is_flatpak_with(&info_path, Some("flatpak".into())));
+
+        std::fs::write(&info_path, "[Application]\nname=com.example.App\n").unwrap();
+        assert!(is_flatpak_with(&info_path, None));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
     #[test]
     fn test_cgroup_v2_is_snap() {
         let data =
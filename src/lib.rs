@@ -24,25 +24,190 @@ pub mod desktop;
 pub mod documents;
 mod error;
 mod window_identifier;
-pub use self::window_identifier::WindowIdentifier;
+pub use self::window_identifier::{WindowIdentifier, WindowIdentifierKind};
 /// Spawn commands outside the sandbox or monitor if the running application has
 /// received an update & install it.
 pub mod flatpak;
 mod helpers;
+mod portal;
+pub mod prelude;
 pub use enumflags2;
+pub use helpers::request_path;
 pub use zbus::{self, zvariant};
 
 /// Check whether the application is running inside a sandbox.
 ///
-/// The function checks whether the file `/.flatpak-info` exists, or if the app
-/// is running as a snap, or if the environment variable `GTK_USE_PORTAL` is set
+/// The function checks whether the file `/.flatpak-info` exists or the
+/// environment variable `container` is set to `flatpak`, or if the app is
+/// running as a snap, or if the environment variable `GTK_USE_PORTAL` is set
 /// to `1`.
-pub async fn is_sandboxed() -> bool {
-    crate::helpers::is_flatpak().await
-        || crate::helpers::is_snap().await
+///
+/// None of the checks need the session bus, so it doesn't need a runtime and
+/// can be called before the application sets one up.
+///
+/// ```rust
+/// if ashpd::is_sandboxed() {
+///     println!("Running in a sandbox");
+/// }
+/// ```
+pub fn is_sandboxed() -> bool {
+    crate::helpers::is_flatpak()
+        || crate::helpers::is_snap()
         || std::env::var("GTK_USE_PORTAL")
             .map(|v| v == "1")
             .unwrap_or(false)
 }
 
-pub use self::error::{Error, PortalError};
+/// Set the session bus connection used by all the portals.
+///
+/// By default, ASHPD lazily creates its own connection the first time a portal
+/// is used, relying on zbus's internal executor when the `async-std` feature
+/// is enabled or on the current tokio runtime when the `tokio` feature is.
+/// Applications using a different runtime, or that already have a connection
+/// around, can build the connection themselves and hand it over before
+/// creating any proxy.
+///
+/// Returns the passed connection back if one was already set, either by a
+/// previous call or because a portal was already used.
+///
+/// ```rust,no_run
+/// async fn run() -> ashpd::Result<()> {
+///     let connection = ashpd::zbus::ConnectionBuilder::session()?
+///         .internal_executor(false)
+///         .build()
+///         .await?;
+///     let executor = connection.executor().clone();
+///     // Drive the connection from the application's own runtime, e.g.
+///     // runtime.spawn(async move { loop { executor.tick().await } });
+///     ashpd::set_session_connection(connection).expect("Connection already set");
+///     Ok(())
+/// }
+/// ```
+pub fn set_session_connection(
+    connection: zbus::Connection,
+) -> std::result::Result<(), zbus::Connection> {
+    crate::helpers::set_session_connection(connection)
+}
+
+/// Set the application id, for example `org.gnome.Nautilus`, of an
+/// application running outside of a sandbox.
+///
+/// The portals find out the id of the Flatpak and Snap applications from the
+/// sandbox, but can only guess it for the host ones. Before the first call to
+/// one of the [`desktop`] portals, the id is registered with the portal so it
+/// gets used for the permissions, the notifications or the background apps of
+/// the application instead.
+///
+/// It must be called before using any portal, it has no effect on a
+/// connection set with [`set_session_connection`], on sandboxed applications,
+/// or with xdg-desktop-portal older than 1.19 which can't register host
+/// applications. None of the portal methods take an application id, so it
+/// can't be set per request.
+///
+/// Fails with [`Error::InvalidArgument`] if `app_id` isn't a valid D-Bus name with
+/// at least two elements.
+///
+/// ```rust
+/// assert!(ashpd::set_app_id("com.example.App").is_ok());
+/// assert!(ashpd::set_app_id("App").is_err());
+/// ```
+pub fn set_app_id(app_id: &str) -> Result<()> {
+    crate::helpers::set_app_id(app_id)
+}
+
+/// Set how long the first portal call waits for xdg-desktop-portal to be
+/// running, 3 seconds by default.
+///
+/// On login, the applications can be started before xdg-desktop-portal is,
+/// making the portal calls fail as nobody owns `org.freedesktop.portal.Desktop`
+/// yet. Before the first call to one of the [`desktop`] portals, ASHPD waits
+/// for the name to be acquired, up to `timeout`. It doesn't wait if the portal
+/// can be started by the bus on demand, nor for the [`documents`] and
+/// [`flatpak`] portals or [`is_portal_available`]. A zero `timeout` disables
+/// waiting.
+///
+/// It has no effect on a connection set with [`set_session_connection`] or
+/// once one of the [`desktop`] portals was called.
+pub fn set_portal_startup_timeout(timeout: std::time::Duration) {
+    crate::helpers::set_portal_startup_timeout(timeout);
+}
+
+/// Set how long to wait for the reply to the portal method calls, 5 minutes
+/// by default.
+///
+/// Most portal methods reply right away with the request the response will be
+/// sent on, but some only reply once the user is done with the dialog, so the
+/// default is long enough for interactive dialogs. A method call without a
+/// reply in time fails with [`Error::Timeout`]. A zero `timeout` disables it.
+///
+/// This is distinct from the request builders `timeout`, which waits for the
+/// response of the request and closes it, dismissing the dialog, when it
+/// expires. The method call timeout doesn't close anything, the portal might
+/// still show the dialog.
+pub fn set_method_timeout(timeout: std::time::Duration) {
+    crate::helpers::set_method_timeout(timeout);
+}
+
+/// Set whether the portal methods fail when their parent window doesn't
+/// belong to the display server of the session, disabled by default.
+///
+/// A `wayland:` identifier under X11, or an `x11:` one under Wayland without
+/// Xwayland, can't be used by the portal to parent its dialog, which then
+/// shows up as a separate window. Every method taking a [`WindowIdentifier`]
+/// logs a warning by default, see [`WindowIdentifier::kind`]. When strict,
+/// they fail with [`Error::InvalidArgument`] instead of calling the portal.
+///
+/// The session type is read from `XDG_SESSION_TYPE`, falling back to the
+/// `WAYLAND_DISPLAY` and `DISPLAY` environment variables.
+pub fn set_strict_window_identifier(strict: bool) {
+    crate::helpers::set_strict_window_identifier(strict);
+}
+
+/// The session bus connection used by all the portals.
+///
+/// The connection is created if no portal was used yet. The returned handle
+/// is a clone sharing the same underlying connection, so it can be used to
+/// register other signal handlers or make D-Bus calls the crate doesn't
+/// cover.
+///
+/// ```rust,no_run
+/// async fn run() -> ashpd::Result<()> {
+///     let connection = ashpd::connection().await?;
+///     let proxy = ashpd::zbus::fdo::DBusProxy::new(&connection).await?;
+///     println!("{:#?}", proxy.list_names().await?);
+///     Ok(())
+/// }
+/// ```
+pub async fn connection() -> Result<zbus::Connection> {
+    Ok(crate::helpers::bus_connection().await?)
+}
+
+/// The unique name of the session bus connection used by all the portals,
+/// `:1.42` for example.
+///
+/// It is the sender of the portal method calls, which is handy to find the
+/// application's messages in a `dbus-monitor` or `busctl monitor` log.
+pub async fn connection_unique_name() -> Result<String> {
+    let connection = crate::helpers::bus_connection().await?;
+    let unique_name = connection
+        .unique_name()
+        .ok_or_else(|| zbus::Error::Failure("The connection has no unique name".to_owned()))?;
+    Ok(unique_name.to_string())
+}
+
+/// Check whether `portal`, for example [`Portal::Wallpaper`], is provided by
+/// the running xdg-desktop-portal.
+///
+/// Useful to hide the features relying on a portal the desktop doesn't
+/// implement instead of failing when calling it. Not having xdg-desktop-portal
+/// running at all is reported as the portal not being available.
+///
+/// The result is cached for the lifetime of the session connection.
+pub async fn is_portal_available(portal: Portal) -> Result<bool> {
+    crate::helpers::is_portal_available(portal).await
+}
+
+pub use self::{
+    error::{Error, PortalError},
+    portal::Portal,
+};
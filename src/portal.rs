@@ -0,0 +1,190 @@
+use std::fmt;
+
+/// The portal interfaces wrapped by ASHPD.
+///
+/// The objects created by the portals, like the
+/// `org.freedesktop.portal.Request`, `org.freedesktop.portal.Session` or
+/// `org.freedesktop.portal.Flatpak.UpdateMonitor` ones, are not listed as they
+/// are not portals on their own.
+///
+/// ```rust
+/// use ashpd::Portal;
+///
+/// assert_eq!(
+///     Portal::FileChooser.as_interface_name(),
+///     "org.freedesktop.portal.FileChooser"
+/// );
+/// for portal in Portal::ALL {
+///     println!("{}", portal);
+/// }
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum Portal {
+    /// `org.freedesktop.portal.Account`.
+    Account,
+    /// `org.freedesktop.portal.Background`.
+    Background,
+    /// `org.freedesktop.portal.Camera`.
+    Camera,
+    /// `org.freedesktop.portal.Device`.
+    Device,
+    /// `org.freedesktop.portal.Documents`.
+    Documents,
+    /// `org.freedesktop.portal.DynamicLauncher`.
+    DynamicLauncher,
+    /// `org.freedesktop.portal.Email`.
+    Email,
+    /// `org.freedesktop.portal.FileChooser`.
+    FileChooser,
+    /// `org.freedesktop.portal.FileTransfer`.
+    FileTransfer,
+    /// `org.freedesktop.portal.Flatpak`.
+    Flatpak,
+    /// `org.freedesktop.portal.GameMode`.
+    GameMode,
+    /// `org.freedesktop.portal.Inhibit`.
+    Inhibit,
+    /// `org.freedesktop.portal.Location`.
+    Location,
+    /// `org.freedesktop.portal.MemoryMonitor`.
+    MemoryMonitor,
+    /// `org.freedesktop.portal.NetworkMonitor`.
+    NetworkMonitor,
+    /// `org.freedesktop.portal.Notification`.
+    Notification,
+    /// `org.freedesktop.portal.OpenURI`.
+    OpenURI,
+    /// `org.freedesktop.portal.PowerProfileMonitor`.
+    PowerProfileMonitor,
+    /// `org.freedesktop.portal.Print`.
+    Print,
+    /// `org.freedesktop.portal.ProxyResolver`.
+    ProxyResolver,
+    /// `org.freedesktop.portal.Realtime`.
+    Realtime,
+    /// `org.freedesktop.portal.RemoteDesktop`.
+    RemoteDesktop,
+    /// `org.freedesktop.portal.ScreenCast`.
+    ScreenCast,
+    /// `org.freedesktop.portal.Screenshot`.
+    Screenshot,
+    /// `org.freedesktop.portal.Secret`.
+    Secret,
+    /// `org.freedesktop.portal.Settings`.
+    Settings,
+    /// `org.freedesktop.portal.Trash`.
+    Trash,
+    /// `org.freedesktop.portal.Wallpaper`.
+    Wallpaper,
+}
+
+impl Portal {
+    /// All the portals, in alphabetical order.
+    pub const ALL: &'static [Portal] = &[
+        Self::Account,
+        Self::Background,
+        Self::Camera,
+        Self::Device,
+        Self::Documents,
+        Self::DynamicLauncher,
+        Self::Email,
+        Self::FileChooser,
+        Self::FileTransfer,
+        Self::Flatpak,
+        Self::GameMode,
+        Self::Inhibit,
+        Self::Location,
+        Self::MemoryMonitor,
+        Self::NetworkMonitor,
+        Self::Notification,
+        Self::OpenURI,
+        Self::PowerProfileMonitor,
+        Self::Print,
+        Self::ProxyResolver,
+        Self::Realtime,
+        Self::RemoteDesktop,
+        Self::ScreenCast,
+        Self::Screenshot,
+        Self::Secret,
+        Self::Settings,
+        Self::Trash,
+        Self::Wallpaper,
+    ];
+
+    /// The D-Bus interface name of the portal, for example
+    /// `org.freedesktop.portal.Wallpaper`.
+    pub fn as_interface_name(&self) -> &'static str {
+        match self {
+            Self::Account => "org.freedesktop.portal.Account",
+            Self::Background => "org.freedesktop.portal.Background",
+            Self::Camera => "org.freedesktop.portal.Camera",
+            Self::Device => "org.freedesktop.portal.Device",
+            Self::Documents => "org.freedesktop.portal.Documents",
+            Self::DynamicLauncher => "org.freedesktop.portal.DynamicLauncher",
+            Self::Email => "org.freedesktop.portal.Email",
+            Self::FileChooser => "org.freedesktop.portal.FileChooser",
+            Self::FileTransfer => "org.freedesktop.portal.FileTransfer",
+            Self::Flatpak => "org.freedesktop.portal.Flatpak",
+            Self::GameMode => "org.freedesktop.portal.GameMode",
+            Self::Inhibit => "org.freedesktop.portal.Inhibit",
+            Self::Location => "org.freedesktop.portal.Location",
+            Self::MemoryMonitor => "org.freedesktop.portal.MemoryMonitor",
+            Self::NetworkMonitor => "org.freedesktop.portal.NetworkMonitor",
+            Self::Notification => "org.freedesktop.portal.Notification",
+            Self::OpenURI => "org.freedesktop.portal.OpenURI",
+            Self::PowerProfileMonitor => "org.freedesktop.portal.PowerProfileMonitor",
+            Self::Print => "org.freedesktop.portal.Print",
+            Self::ProxyResolver => "org.freedesktop.portal.ProxyResolver",
+            Self::Realtime => "org.freedesktop.portal.Realtime",
+            Self::RemoteDesktop => "org.freedesktop.portal.RemoteDesktop",
+            Self::ScreenCast => "org.freedesktop.portal.ScreenCast",
+            Self::Screenshot => "org.freedesktop.portal.Screenshot",
+            Self::Secret => "org.freedesktop.portal.Secret",
+            Self::Settings => "org.freedesktop.portal.Settings",
+            Self::Trash => "org.freedesktop.portal.Trash",
+            Self::Wallpaper => "org.freedesktop.portal.Wallpaper",
+        }
+    }
+
+    /// The bus name and object path the portal is exported at.
+    pub(crate) fn destination_and_path(&self) -> (&'static str, &'static str) {
+        match self {
+            Self::Documents | Self::FileTransfer => {
+                (crate::documents::DESTINATION, crate::documents::PATH)
+            }
+            Self::Flatpak => (crate::flatpak::DESTINATION, crate::flatpak::PATH),
+            _ => (crate::desktop::DESTINATION, crate::desktop::PATH),
+        }
+    }
+}
+
+impl fmt::Display for Portal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_interface_name())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use zbus::names::InterfaceName;
+
+    use super::Portal;
+
+    #[test]
+    fn interface_names() {
+        for portal in Portal::ALL {
+            let name = portal.as_interface_name();
+            assert!(InterfaceName::try_from(name).is_ok());
+            let short_name = name.strip_prefix("org.freedesktop.portal.").unwrap();
+            assert_eq!(short_name, format!("{:?}", portal));
+        }
+        let names = Portal::ALL
+            .iter()
+            .map(Portal::as_interface_name)
+            .collect::<HashSet<_>>();
+        assert_eq!(names.len(), Portal::ALL.len());
+    }
+}
@@ -0,0 +1,36 @@
+//! The commonly used types, to be glob imported.
+//!
+//! It re-exports the request builders of the dialog portals along with their
+//! responses, [`WindowIdentifier`] and the [`Error`] & [`Result`] types. The
+//! other portals, the sessions and the less common options types still have
+//! to be imported from their module.
+//!
+//! ```rust,no_run
+//! use ashpd::prelude::*;
+//!
+//! async fn run() -> Result<()> {
+//!     let files: SelectedFiles = OpenFileRequest::default()
+//!         .identifier(WindowIdentifier::default())
+//!         .filter(FileFilter::new("Images").mimetype("image/*"))
+//!         .choice(Choice::boolean("encrypt", "Encrypt", false))
+//!         .build()
+//!         .await?;
+//!     println!("{}", files);
+//!     Ok(())
+//! }
+//! ```
+
+pub use crate::{
+    desktop::{
+        account::{UserInformationRequest, UserInformationResponse},
+        background::{BackgroundRequest, BackgroundResponse},
+        email::EmailRequest,
+        file_chooser::{
+            Choice, FileFilter, OpenFileRequest, SaveFileRequest, SaveFilesRequest, SelectedFiles,
+        },
+        screenshot::{ColorRequest, ColorResponse, ScreenshotRequest},
+        wallpaper::{SetOn, WallpaperRequest},
+        Color, ResponseError,
+    },
+    Error, PortalError, Result, WindowIdentifier,
+};
@@ -83,6 +83,10 @@ impl Gtk3WindowIdentifier {
         }
     }
 
+    pub(crate) fn type_(&self) -> &WindowIdentifierType {
+        &self.type_
+    }
+
     #[cfg(feature = "raw_handle")]
     pub fn as_raw_window_handle(&self) -> RawWindowHandle {
         unsafe {
@@ -69,6 +69,14 @@ use zbus::zvariant::Type;
 /// The constructor should return a valid identifier under both X11 and Wayland
 /// and fallback to the [`Default`] implementation otherwise.
 ///
+/// Under Wayland, the toplevel is exported using xdg-foreign and unexported
+/// once the last [`WindowIdentifier`] created for it is dropped. The request
+/// builders take ownership of the identifier, so the handle stays valid until
+/// the portal call completes and is cleaned up right after. If you pass the
+/// identifier to a proxy method directly instead, keep it alive until the
+/// returned future resolves; dropping it earlier would invalidate the handle
+/// while the portal is still using it to position its dialog.
+///
 /// ## With GTK 3
 ///
 /// The feature `gtk3` must be enabled. You can get a
@@ -99,6 +107,16 @@ use zbus::zvariant::Type;
 /// /// Open some portals
 /// ```
 ///
+/// ## Headless usage
+///
+/// Services and other applications without a window can use the [`Default`]
+/// implementation, the portal dialogs then show up without a parent. Most
+/// portals don't show a dialog at all or can do without a window. The file
+/// chooser, the color picker and interactive screenshots on the other hand
+/// fail with [`Error::NoWindow`](crate::Error::NoWindow) when there is neither
+/// a parent window nor a display, i.e. when none of the `WAYLAND_DISPLAY` and
+/// `DISPLAY` environment variables are set.
+///
 /// In case you don't have access to a WindowIdentifier:
 /// ```rust
 /// use ashpd::WindowIdentifier;
@@ -120,8 +138,9 @@ pub enum WindowIdentifier {
     #[cfg(feature = "wayland")]
     #[doc(hidden)]
     Wayland(WaylandWindowIdentifier),
+    /// An identifier built from an X11 XID or parsed from a string.
     #[doc(hidden)]
-    X11(WindowIdentifierType),
+    Raw(WindowIdentifierType),
     #[doc(hidden)]
     None,
 }
@@ -138,6 +157,8 @@ impl Serialize for WindowIdentifier {
     }
 }
 
+/// The identifier as sent to the portals, `x11:XID` or `wayland:HANDLE`, or
+/// an empty string without a parent window.
 impl std::fmt::Display for WindowIdentifier {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
@@ -147,7 +168,7 @@ impl std::fmt::Display for WindowIdentifier {
             Self::Gtk3(identifier) => f.write_str(&format!("{}", identifier)),
             #[cfg(feature = "wayland")]
             Self::Wayland(identifier) => f.write_str(&format!("{}", identifier)),
-            Self::X11(identifier) => f.write_str(&format!("{}", identifier)),
+            Self::Raw(identifier) => f.write_str(&format!("{}", identifier)),
             Self::None => f.write_str(""),
         }
     }
@@ -161,12 +182,60 @@ impl std::fmt::Debug for WindowIdentifier {
     }
 }
 
+/// Two identifiers are equal when they refer to the same window, whichever
+/// constructor was used.
+impl PartialEq for WindowIdentifier {
+    fn eq(&self, other: &Self) -> bool {
+        self.to_string() == other.to_string()
+    }
+}
+
+impl Eq for WindowIdentifier {}
+
 impl Default for WindowIdentifier {
     fn default() -> Self {
         Self::None
     }
 }
 
+/// Parses an identifier as sent to the portals, the reverse of the
+/// [`Display`](std::fmt::Display) implementation, e.g. to use an identifier
+/// received from another process.
+///
+/// Fails with [`Error::InvalidArgument`](crate::Error::InvalidArgument) if it isn't an `x11:XID` or
+/// `wayland:HANDLE` identifier, or an empty string.
+///
+/// ```rust
+/// use ashpd::WindowIdentifier;
+///
+/// let identifier = WindowIdentifier::try_from("x11:0x400").unwrap();
+/// assert_eq!(identifier, WindowIdentifier::from_xid(1024));
+/// assert_eq!(
+///     WindowIdentifier::try_from(identifier.to_string().as_str()).unwrap(),
+///     identifier
+/// );
+/// assert!(WindowIdentifier::try_from("0x400").is_err());
+/// ```
+impl TryFrom<&str> for WindowIdentifier {
+    type Error = crate::Error;
+
+    fn try_from(identifier: &str) -> Result<Self, Self::Error> {
+        if identifier.is_empty() {
+            return Ok(Self::None);
+        }
+        let identifier = WindowIdentifierType::from_str(identifier).map_err(|err| match err {
+            PortalError::InvalidArgument(message) => crate::Error::InvalidArgument(message),
+            err => err.into(),
+        })?;
+        match identifier {
+            WindowIdentifierType::Wayland(handle) if handle.is_empty() => Err(
+                crate::Error::InvalidArgument("Missing Wayland handle".to_owned()),
+            ),
+            identifier => Ok(Self::Raw(identifier)),
+        }
+    }
+}
+
 impl WindowIdentifier {
     #[cfg(feature = "gtk4")]
     /// Creates a [`WindowIdentifier`] from a [`gtk4::Native`](https://docs.gtk.org/gtk4/class.Native.html).
@@ -175,6 +244,9 @@ impl WindowIdentifier {
     ///
     /// **Note** the function has to be async as the Wayland handle retrieval
     /// API is async as well.
+    ///
+    /// Under Wayland, the exported handle is shared between the identifiers
+    /// of the same toplevel and unexported when the last one is dropped.
     #[doc(alias = "xdp_parent_new_gtk")]
     pub async fn from_native(native: &impl ::gtk4::glib::IsA<::gtk4::Native>) -> Self {
         match Gtk4WindowIdentifier::new(native).await {
@@ -206,6 +278,12 @@ impl WindowIdentifier {
     ///
     /// This method is only async and requires a `RawDisplayHandle` only for
     /// Wayland handles.
+    ///
+    /// `Xlib` and `Xcb` handles are mapped to an `x11:XID` identifier. For
+    /// Wayland, the surface is exported using xdg-foreign and unexported when
+    /// the identifier is dropped. If the display handle is missing or the
+    /// compositor doesn't support xdg-foreign, the [`Default`] implementation
+    /// is returned instead.
     pub async fn from_raw_handle(
         window_handle: &raw_window_handle::RawWindowHandle,
         display_handle: Option<&raw_window_handle::RawDisplayHandle>,
@@ -226,7 +304,7 @@ impl WindowIdentifier {
 
     /// Create an instance of [`WindowIdentifier`] from an X11 window's XID.
     pub fn from_xid(xid: std::os::raw::c_ulong) -> Self {
-        Self::X11(WindowIdentifierType::X11(xid))
+        Self::Raw(WindowIdentifierType::X11(xid))
     }
 
     #[cfg(feature = "wayland")]
@@ -247,6 +325,33 @@ impl WindowIdentifier {
         }
     }
 
+    /// The display server the identifier refers to a window of.
+    ///
+    /// ```rust
+    /// use ashpd::{WindowIdentifier, WindowIdentifierKind};
+    ///
+    /// assert_eq!(
+    ///     WindowIdentifier::from_xid(1024).kind(),
+    ///     WindowIdentifierKind::X11
+    /// );
+    /// assert_eq!(
+    ///     WindowIdentifier::default().kind(),
+    ///     WindowIdentifierKind::None
+    /// );
+    /// ```
+    pub fn kind(&self) -> WindowIdentifierKind {
+        match self {
+            #[cfg(feature = "gtk4")]
+            Self::Gtk4(identifier) => identifier.type_().kind(),
+            #[cfg(feature = "gtk3")]
+            Self::Gtk3(identifier) => identifier.type_().kind(),
+            #[cfg(feature = "wayland")]
+            Self::Wayland(_) => WindowIdentifierKind::Wayland,
+            Self::Raw(identifier) => identifier.kind(),
+            Self::None => WindowIdentifierKind::None,
+        }
+    }
+
     #[cfg(feature = "wayland")]
     /// Create an instance of [`WindowIdentifier`] from a Wayland surface.
     pub async fn from_wayland(surface: &wayland_client::protocol::wl_surface::WlSurface) -> Self {
@@ -299,15 +404,45 @@ unsafe impl HasRawWindowHandle for WindowIdentifier {
     }
 }
 
+/// The display server a [`WindowIdentifier`] refers to a window of, see
+/// [`WindowIdentifier::kind`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum WindowIdentifierKind {
+    /// An `x11:XID` identifier.
+    X11,
+    /// A `wayland:HANDLE` identifier.
+    Wayland,
+    /// No parent window.
+    None,
+}
+
+impl fmt::Display for WindowIdentifierKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::X11 => f.write_str("X11"),
+            Self::Wayland => f.write_str("Wayland"),
+            Self::None => f.write_str("None"),
+        }
+    }
+}
+
 /// Supported WindowIdentifier kinds
 #[derive(Debug, Clone, PartialEq, Eq, Type)]
 #[zvariant(signature = "s")]
 pub enum WindowIdentifierType {
     X11(std::os::raw::c_ulong),
-    #[allow(dead_code)]
     Wayland(String),
 }
 
+impl WindowIdentifierType {
+    fn kind(&self) -> WindowIdentifierKind {
+        match self {
+            Self::X11(_) => WindowIdentifierKind::X11,
+            Self::Wayland(_) => WindowIdentifierKind::Wayland,
+        }
+    }
+}
+
 impl fmt::Display for WindowIdentifierType {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
@@ -375,7 +510,7 @@ pub use self::wayland::WaylandWindowIdentifier;
 mod tests {
     use std::str::FromStr;
 
-    use super::WindowIdentifier;
+    use super::{WindowIdentifier, WindowIdentifierKind};
     use crate::window_identifier::WindowIdentifierType;
 
     #[test]
@@ -397,4 +532,90 @@ mod tests {
         assert!(WindowIdentifierType::from_str("some_handle").is_err());
         assert!(WindowIdentifierType::from_str("some_type:some_handle").is_err());
     }
+
+    #[test]
+    fn test_display_and_eq() {
+        let x11 = WindowIdentifier::from_xid(1024);
+        assert_eq!(format!("{:?}", x11), r#"WindowIdentifier("x11:0x400")"#);
+        assert_eq!(x11, WindowIdentifier::from_xid(0x400));
+        assert_ne!(x11, WindowIdentifier::from_xid(1025));
+        assert_ne!(x11, WindowIdentifier::default());
+
+        let none = WindowIdentifier::default();
+        assert_eq!(none.to_string(), "");
+        assert_eq!(format!("{:?}", none), r#"WindowIdentifier("")"#);
+        assert_eq!(none, WindowIdentifier::None);
+    }
+
+    #[test]
+    fn test_try_from_str() {
+        let x11 = WindowIdentifier::try_from("x11:0x400").unwrap();
+        assert_eq!(x11, WindowIdentifier::from_xid(1024));
+        assert_eq!(x11.kind(), WindowIdentifierKind::X11);
+        assert_eq!(x11.to_string(), "x11:0x400");
+
+        let wayland = WindowIdentifier::try_from("wayland:Somerandomchars").unwrap();
+        assert_eq!(wayland.kind(), WindowIdentifierKind::Wayland);
+        assert_eq!(wayland.to_string(), "wayland:Somerandomchars");
+
+        let none = WindowIdentifier::try_from("").unwrap();
+        assert_eq!(none, WindowIdentifier::default());
+
+        for invalid in ["0x400", "x11:", "x11:xyz", "wayland:", "win32:1234"] {
+            assert!(
+                WindowIdentifier::try_from(invalid).is_err(),
+                "{} is not a valid identifier",
+                invalid
+            );
+        }
+    }
+
+    #[test]
+    fn test_kind() {
+        assert_eq!(
+            WindowIdentifier::from_xid(1024).kind(),
+            WindowIdentifierKind::X11
+        );
+        assert_eq!(
+            WindowIdentifier::Raw(WindowIdentifierType::Wayland("handle".to_owned())).kind(),
+            WindowIdentifierKind::Wayland
+        );
+        assert_eq!(
+            WindowIdentifier::default().kind(),
+            WindowIdentifierKind::None
+        );
+    }
+
+    #[cfg(feature = "raw_handle")]
+    #[test]
+    fn test_from_raw_handle() {
+        use raw_window_handle::{
+            RawWindowHandle, WaylandWindowHandle, XcbWindowHandle, XlibWindowHandle,
+        };
+
+        let mut xlib = XlibWindowHandle::empty();
+        xlib.window = 1024;
+        let identifier = zbus::block_on(WindowIdentifier::from_raw_handle(
+            &RawWindowHandle::Xlib(xlib),
+            None,
+        ));
+        assert_eq!(identifier.to_string(), "x11:0x400");
+
+        let mut xcb = XcbWindowHandle::empty();
+        xcb.window = 70706;
+        let identifier = zbus::block_on(WindowIdentifier::from_raw_handle(
+            &RawWindowHandle::Xcb(xcb),
+            None,
+        ));
+        assert_eq!(identifier.to_string(), "x11:0x11432");
+        assert_eq!(identifier, WindowIdentifier::from_xid(70706));
+
+        // A Wayland surface can't be exported without its display
+        let wayland = WaylandWindowHandle::empty();
+        let identifier = zbus::block_on(WindowIdentifier::from_raw_handle(
+            &RawWindowHandle::Wayland(wayland),
+            None,
+        ));
+        assert_eq!(identifier.to_string(), "");
+    }
 }